@@ -0,0 +1,245 @@
+// Simplified Unicode Bidirectional Algorithm (UAX #9).
+// Explicit embeddings, overrides and isolates are not supported, so every paragraph has
+// at most three levels: base level, level of opposite direction and level of numbers.
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum BidiClass {
+    /// Left-to-right letter.
+    L,
+    /// Right-to-left letter (Hebrew).
+    R,
+    /// Arabic letter.
+    AL,
+    /// European number.
+    EN,
+    /// Arabic number.
+    AN,
+    /// Non-spacing mark, takes class of previous character.
+    NSM,
+    /// Whitespace and separators.
+    WS,
+    /// Other neutral characters.
+    ON,
+}
+
+/// Returns true for combining marks of Latin, Hebrew and Arabic scripts.
+pub(in crate) fn is_mark(code: u32) -> bool {
+    match code {
+        0x0300..=0x036F | 0x0591..=0x05BD | 0x05BF | 0x05C1 | 0x05C2 | 0x05C4 | 0x05C5 | 0x05C7 |
+        0x0610..=0x061A | 0x064B..=0x065F | 0x0670 | 0x06D6..=0x06DC | 0x06DF..=0x06E4 |
+        0x06E7 | 0x06E8 | 0x06EA..=0x06ED => true,
+        _ => false
+    }
+}
+
+fn classify_number(code: u32) -> Option<BidiClass> {
+    match code {
+        0x0030..=0x0039 | 0x06F0..=0x06F9 => Some(BidiClass::EN),
+        0x0660..=0x0669 | 0x066B | 0x066C => Some(BidiClass::AN),
+        _ => None
+    }
+}
+
+fn classify(code: u32) -> BidiClass {
+    if let Some(class) = classify_number(code) {
+        return class;
+    }
+    if is_mark(code) {
+        return BidiClass::NSM;
+    }
+    match code {
+        0x0590..=0x05FF | 0x07C0..=0x085F | 0xFB1D..=0xFB4F | 0x200F => BidiClass::R,
+        0x0600..=0x07BF | 0x0860..=0x08FF | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => BidiClass::AL,
+        0x200E => BidiClass::L,
+        0x0009..=0x000D | 0x0020 | 0x00A0 | 0x2000..=0x200A | 0x3000 => BidiClass::WS,
+        _ => {
+            let is_letter = std::char::from_u32(code).map_or(false, |c| c.is_alphanumeric());
+            if is_letter { BidiClass::L } else { BidiClass::ON }
+        }
+    }
+}
+
+/// Returns level of paragraph - 1 if first strong character of the paragraph is
+/// right-to-left, 0 otherwise.
+pub(in crate) fn paragraph_level(paragraph: &[u32]) -> u8 {
+    for code in paragraph {
+        match classify(*code) {
+            BidiClass::L => return 0,
+            BidiClass::R | BidiClass::AL => return 1,
+            _ => ()
+        }
+    }
+    0
+}
+
+/// Resolves embedding level of each character of a paragraph. Odd levels are right-to-left.
+pub(in crate) fn resolve_levels(paragraph: &[u32], base_level: u8) -> Vec<u8> {
+    let base_class = if base_level % 2 == 0 { BidiClass::L } else { BidiClass::R };
+    let mut classes = paragraph.iter().map(|code| classify(*code)).collect::<Vec<BidiClass>>();
+
+    // W1: Marks take class of previous character.
+    let mut prev = base_class;
+    for class in classes.iter_mut() {
+        if *class == BidiClass::NSM {
+            *class = prev;
+        }
+        prev = *class;
+    }
+
+    // W2, W3, W7: Numbers after Arabic letters are Arabic numbers, numbers after
+    // left-to-right letters behave like letters.
+    let mut last_strong = base_class;
+    for class in classes.iter_mut() {
+        match *class {
+            BidiClass::L | BidiClass::R => last_strong = *class,
+            BidiClass::AL => {
+                last_strong = BidiClass::AL;
+                *class = BidiClass::R;
+            }
+            BidiClass::EN => {
+                if last_strong == BidiClass::AL {
+                    *class = BidiClass::AN;
+                } else if last_strong == BidiClass::L {
+                    *class = BidiClass::L;
+                }
+            }
+            _ => ()
+        }
+    }
+
+    // N1, N2: Neutrals between characters of same direction take that direction,
+    // otherwise direction of paragraph. Numbers count as right-to-left here.
+    let direction = |class: BidiClass| match class {
+        BidiClass::L => Some(BidiClass::L),
+        BidiClass::R | BidiClass::EN | BidiClass::AN => Some(BidiClass::R),
+        _ => None
+    };
+    let mut i = 0;
+    while i < classes.len() {
+        if direction(classes[i]).is_some() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < classes.len() && direction(classes[i]).is_none() {
+            i += 1;
+        }
+        let before = if start > 0 { direction(classes[start - 1]).unwrap() } else { base_class };
+        let after = classes.get(i).and_then(|class| direction(*class)).unwrap_or(base_class);
+        let resolved = if before == after { before } else { base_class };
+        for class in classes[start..i].iter_mut() {
+            *class = resolved;
+        }
+    }
+
+    // I1, I2: Implicit levels.
+    classes
+        .iter()
+        .map(|class| {
+            if base_level % 2 == 0 {
+                match class {
+                    BidiClass::R => base_level + 1,
+                    BidiClass::AN | BidiClass::EN => base_level + 2,
+                    _ => base_level
+                }
+            } else {
+                match class {
+                    BidiClass::L | BidiClass::EN | BidiClass::AN => base_level + 1,
+                    _ => base_level
+                }
+            }
+        })
+        .collect()
+}
+
+/// Returns visual order of characters of a line: index of logical character for each
+/// visual position. Whitespace at the end of line gets level of paragraph.
+pub(in crate) fn reorder_line(line: &[u32], levels: &[u8], base_level: u8) -> Vec<usize> {
+    let mut levels = levels.to_vec();
+    // L1: Trailing whitespace.
+    for (code, level) in line.iter().zip(levels.iter_mut()).rev() {
+        if classify(*code) != BidiClass::WS {
+            break;
+        }
+        *level = base_level;
+    }
+
+    let mut order = (0..levels.len()).collect::<Vec<usize>>();
+    let max_level = levels.iter().cloned().max().unwrap_or(0);
+    let min_odd_level = levels.iter().cloned().filter(|level| level % 2 == 1).min().unwrap_or(max_level + 1);
+
+    // L2: From highest level to lowest odd level reverse every run of characters at that
+    // level or higher.
+    let mut level = max_level;
+    while level >= min_odd_level && level > 0 {
+        let mut i = 0;
+        while i < levels.len() {
+            if levels[order[i]] < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < levels.len() && levels[order[i]] >= level {
+                i += 1;
+            }
+            order[start..i].reverse();
+        }
+        level -= 1;
+    }
+
+    order
+}
+
+/// Returns mirrored character for characters such as brackets in right-to-left text.
+pub(in crate) fn mirror(code: u32) -> u32 {
+    match std::char::from_u32(code) {
+        Some('(') => u32::from(')'),
+        Some(')') => u32::from('('),
+        Some('[') => u32::from(']'),
+        Some(']') => u32::from('['),
+        Some('{') => u32::from('}'),
+        Some('}') => u32::from('{'),
+        Some('<') => u32::from('>'),
+        Some('>') => u32::from('<'),
+        Some('«') => u32::from('»'),
+        Some('»') => u32::from('«'),
+        _ => code
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::bidi::{paragraph_level, resolve_levels, reorder_line};
+
+    fn codes(text: &str) -> Vec<u32> {
+        text.chars().map(|c| c as u32).collect()
+    }
+
+    fn visual(text: &str) -> String {
+        let text = codes(text);
+        let base_level = paragraph_level(&text);
+        let levels = resolve_levels(&text, base_level);
+        reorder_line(&text, &levels, base_level)
+            .into_iter()
+            .filter_map(|i| std::char::from_u32(text[i]))
+            .collect()
+    }
+
+    #[test]
+    fn mixed_directions() {
+        let text = codes("ab \u{5D0}\u{5D1} 12 cd");
+        assert_eq!(paragraph_level(&text), 0);
+        assert_eq!(resolve_levels(&text, 0), vec![0, 0, 0, 1, 1, 1, 2, 2, 0, 0, 0]);
+        // Hebrew is reversed, number inside of it keeps its order.
+        assert_eq!(visual("ab \u{5D0}\u{5D1} 12 cd"), "ab 12 \u{5D1}\u{5D0} cd");
+    }
+
+    #[test]
+    fn numbers_in_arabic() {
+        let text = codes("\u{627}\u{628}\u{62C} 123");
+        assert_eq!(paragraph_level(&text), 1);
+        // Digits after Arabic letters are Arabic numbers.
+        assert_eq!(resolve_levels(&text, 1), vec![1, 1, 1, 1, 2, 2, 2]);
+        assert_eq!(visual("\u{627}\u{628}\u{62C} 123"), "123 \u{62C}\u{628}\u{627}");
+    }
+}
@@ -22,6 +22,7 @@ use crate::{
     draw::CommandTexture,
     brush::Brush,
     message::UiMessage,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 pub struct Border<M: 'static, C: 'static + Control<M, C>> {
@@ -124,6 +125,13 @@ impl<M, C: 'static + Control<M, C>> Border<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for Border<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit("stroke_thickness", &mut self.stroke_thickness)
+    }
+}
+
 pub struct BorderBuilder<M: 'static, C: 'static + Control<M, C>> {
     pub widget_builder: WidgetBuilder<M, C>,
     pub stroke_thickness: Option<Thickness>,
@@ -24,9 +24,11 @@ use crate::{
         UiMessage,
         UiMessageData,
         ButtonMessage,
+        NavigationInput,
     },
     NodeHandleMapping,
-    decorator::DecoratorBuilder
+    decorator::DecoratorBuilder,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 pub struct Button<M: 'static, C: 'static + Control<M, C>> {
@@ -93,20 +95,22 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for Button<M, C> {
 
         match &message.data {
             UiMessageData::Widget(msg) => {
-                if message.source == self_handle || self.widget().has_descendant(message.source, ui) {
-                    match msg {
-                        WidgetMessage::MouseUp { .. } => {
-                            let widget = self.widget_mut();
+                match msg {
+                    WidgetMessage::MouseUp { .. } => {
+                        let widget = self.widget_mut();
 
-                            widget.post_message(UiMessage::new(UiMessageData::Button(ButtonMessage::Click)));
+                        widget.post_message(UiMessage::new(UiMessageData::Button(ButtonMessage::Click)));
 
-                            ui.release_mouse_capture();
-                        }
-                        WidgetMessage::MouseDown { .. } => {
-                            ui.capture_mouse(message.source);
-                        }
-                        _ => ()
+                        ui.release_mouse_capture();
+                    }
+                    WidgetMessage::MouseDown { .. } => {
+                        ui.capture_mouse(message.source);
+                    }
+                    WidgetMessage::Navigation(NavigationInput::Accept) => {
+                        self.widget.post_message(UiMessage::new(UiMessageData::Button(ButtonMessage::Click)));
+                        message.handled = true;
                     }
+                    _ => ()
                 }
             }
             UiMessageData::Button(msg) => {
@@ -139,6 +143,14 @@ pub enum ButtonContent<M: 'static, C: 'static + Control<M, C>> {
     Node(Handle<UINode<M, C>>),
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for Button<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit_handle("decorator", &mut self.decorator)?;
+        visitor.visit_handle("content", &mut self.content)
+    }
+}
+
 pub struct ButtonBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     content: Option<ButtonContent<M, C>>,
@@ -190,7 +202,11 @@ impl<M, C: 'static + Control<M, C>> ButtonBuilder<M, C> {
         self
     }
 
-    pub fn build(self, ui: &mut UserInterface<M, C>) -> Handle<UINode<M, C>> {
+    pub fn build(mut self, ui: &mut UserInterface<M, C>) -> Handle<UINode<M, C>> {
+        if self.widget_builder.focusable.is_none() {
+            self.widget_builder.focusable = Some(true);
+        }
+
         let content = if let Some(content) = self.content {
             match content {
                 ButtonContent::Text(txt) => {
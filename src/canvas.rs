@@ -13,7 +13,8 @@ use crate::{
     },
     UserInterface,
     Control,
-    message::UiMessage
+    message::UiMessage,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 /// Allows user to directly set position and size of a node
@@ -75,6 +76,12 @@ impl<M, C: 'static + Control<M, C>> Canvas<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for Canvas<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)
+    }
+}
+
 pub struct CanvasBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
 }
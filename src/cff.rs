@@ -0,0 +1,583 @@
+// Reader of CFF (Compact Font Format) outlines, used by OpenType fonts that have `CFF `
+// table instead of `glyf`. Only Type 2 charstrings are supported, hinting is ignored.
+
+use crate::core::math::vec2::Vec2;
+use std::collections::HashMap;
+
+/// Outline of a glyph - set of closed contours in font units.
+pub(in crate) type CffOutline = Vec<Vec<Vec2>>;
+
+const CHARSTRINGS_OP: u16 = 17;
+const PRIVATE_OP: u16 = 18;
+const SUBRS_OP: u16 = 19;
+const CHARSTRING_TYPE_OP: u16 = 0x0C06;
+const FD_ARRAY_OP: u16 = 0x0C24;
+const FD_SELECT_OP: u16 = 0x0C25;
+
+/// Max depth of nested subroutine calls, Type 2 charstring spec limits it to 10.
+const MAX_SUBR_DEPTH: usize = 10;
+const CURVE_STEPS: usize = 8;
+/// Max amount of bytes of charstrings (including subroutines) executed for one glyph,
+/// protects from charstrings that call subroutines too many times.
+const MAX_OPERATIONS: usize = 65536;
+
+fn read_u8(data: &[u8], pos: usize) -> Option<u8> {
+    data.get(pos).cloned()
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from(read_u8(data, pos)?) << 8 | u16::from(read_u8(data, pos + 1)?))
+}
+
+fn read_offset(data: &[u8], pos: usize, size: usize) -> Option<usize> {
+    let mut offset = 0;
+    for i in 0..size {
+        offset = offset << 8 | read_u8(data, pos + i)? as usize;
+    }
+    Some(offset)
+}
+
+/// Reads INDEX structure at given position. Returns items and position right after INDEX.
+fn read_index(data: &[u8], pos: usize) -> Option<(Vec<&[u8]>, usize)> {
+    let count = read_u16(data, pos)? as usize;
+    if count == 0 {
+        return Some((Vec::new(), pos.checked_add(2)?));
+    }
+    let off_size = read_u8(data, pos.checked_add(2)?)? as usize;
+    if off_size == 0 || off_size > 4 {
+        return None;
+    }
+    let offsets_start = pos.checked_add(3)?;
+    // Offsets are relative to the byte that precedes object data.
+    let data_start = offsets_start.checked_add((count + 1) * off_size - 1)?;
+    let mut items = Vec::with_capacity(count);
+    let mut begin = read_offset(data, offsets_start, off_size)?;
+    for i in 1..=count {
+        let end = read_offset(data, offsets_start + i * off_size, off_size)?;
+        if end < begin {
+            return None;
+        }
+        items.push(data.get(data_start.checked_add(begin)?..data_start.checked_add(end)?)?);
+        begin = end;
+    }
+    Some((items, data_start.checked_add(begin)?))
+}
+
+fn read_real(data: &[u8], pos: &mut usize) -> Option<f32> {
+    let mut text = String::new();
+    loop {
+        let byte = read_u8(data, *pos)?;
+        *pos += 1;
+        for nibble in [byte >> 4, byte & 0xF].iter() {
+            match nibble {
+                0..=9 => text.push((b'0' + nibble) as char),
+                0xA => text.push('.'),
+                0xB => text.push('E'),
+                0xC => text.push_str("E-"),
+                0xE => text.push('-'),
+                0xF => return text.parse().ok(),
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// Parses DICT into map of operator to operands. Two-byte operators are stored as
+/// `0x0C00 | second byte`.
+fn read_dict(data: &[u8]) -> Option<HashMap<u16, Vec<f32>>> {
+    let mut dict = HashMap::new();
+    let mut operands = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let b0 = data[pos];
+        pos += 1;
+        match b0 {
+            0..=21 => {
+                let op = if b0 == 12 {
+                    pos += 1;
+                    0x0C00 | u16::from(read_u8(data, pos - 1)?)
+                } else {
+                    u16::from(b0)
+                };
+                dict.insert(op, std::mem::take(&mut operands));
+            }
+            28 => {
+                operands.push(f32::from(read_u16(data, pos)? as i16));
+                pos += 2;
+            }
+            29 => {
+                let value = (read_u16(data, pos)? as u32) << 16 | read_u16(data, pos + 2)? as u32;
+                operands.push(value as i32 as f32);
+                pos += 4;
+            }
+            30 => operands.push(read_real(data, &mut pos)?),
+            32..=246 => operands.push(f32::from(b0) - 139.0),
+            247..=250 => {
+                let b1 = f32::from(read_u8(data, pos)?);
+                pos += 1;
+                operands.push((f32::from(b0) - 247.0) * 256.0 + b1 + 108.0);
+            }
+            251..=254 => {
+                let b1 = f32::from(read_u8(data, pos)?);
+                pos += 1;
+                operands.push(-(f32::from(b0) - 251.0) * 256.0 - b1 - 44.0);
+            }
+            _ => return None,
+        }
+    }
+    Some(dict)
+}
+
+/// Returns operand of DICT as size or offset, `None` if it is missing, negative or too large.
+fn dict_value(dict: &HashMap<u16, Vec<f32>>, op: u16, n: usize) -> Option<usize> {
+    dict.get(&op)
+        .and_then(|operands| operands.get(n))
+        .filter(|value| **value >= 0.0 && **value <= std::u32::MAX as f32)
+        .map(|value| *value as usize)
+}
+
+/// Reads local subroutines of a Private DICT which is referenced by given Top or Font DICT.
+fn read_local_subrs<'a>(cff: &'a [u8], dict: &HashMap<u16, Vec<f32>>) -> Vec<&'a [u8]> {
+    let private_size = dict_value(dict, PRIVATE_OP, 0);
+    let private_offset = dict_value(dict, PRIVATE_OP, 1);
+    if let (Some(size), Some(offset)) = (private_size, private_offset) {
+        let private_dict = offset.checked_add(size)
+            .and_then(|end| cff.get(offset..end))
+            .and_then(read_dict);
+        if let Some(private_dict) = private_dict {
+            // Offset of local subroutines is relative to Private DICT.
+            let subrs_pos = dict_value(&private_dict, SUBRS_OP, 0)
+                .and_then(|subrs_offset| offset.checked_add(subrs_offset));
+            if let Some(subrs_pos) = subrs_pos {
+                if let Some((subrs, _)) = read_index(cff, subrs_pos) {
+                    return subrs;
+                }
+            }
+        }
+    }
+    Vec::new()
+}
+
+/// Reads index of Font DICT for each glyph of CID-keyed font.
+fn read_fd_select(cff: &[u8], pos: usize, num_glyphs: usize) -> Option<Vec<usize>> {
+    let mut fd_select = vec![0; num_glyphs];
+    match read_u8(cff, pos)? {
+        0 => {
+            for (i, fd) in fd_select.iter_mut().enumerate() {
+                *fd = read_u8(cff, pos.checked_add(1 + i)?)? as usize;
+            }
+        }
+        3 => {
+            let range_count = read_u16(cff, pos.checked_add(1)?)? as usize;
+            for i in 0..range_count {
+                let range = pos.checked_add(3 + i * 3)?;
+                let first = read_u16(cff, range)? as usize;
+                let fd = read_u8(cff, range + 2)? as usize;
+                // Next range or sentinel holds end of current range.
+                let last = read_u16(cff, range + 3)? as usize;
+                for glyph in first..last.min(num_glyphs) {
+                    fd_select[glyph] = fd;
+                }
+            }
+        }
+        _ => return None,
+    }
+    Some(fd_select)
+}
+
+fn subr_bias(count: usize) -> i32 {
+    if count < 1240 {
+        107
+    } else if count < 33900 {
+        1131
+    } else {
+        32768
+    }
+}
+
+struct CharstringInterpreter<'a> {
+    global_subrs: &'a [&'a [u8]],
+    local_subrs: &'a [&'a [u8]],
+    outline: CffOutline,
+    stack: Vec<f32>,
+    stem_count: usize,
+    width_parsed: bool,
+    budget: usize,
+    x: f32,
+    y: f32,
+}
+
+impl<'a> CharstringInterpreter<'a> {
+    fn new(global_subrs: &'a [&'a [u8]], local_subrs: &'a [&'a [u8]]) -> Self {
+        Self {
+            global_subrs,
+            local_subrs,
+            outline: Vec::new(),
+            stack: Vec::new(),
+            stem_count: 0,
+            width_parsed: false,
+            budget: MAX_OPERATIONS,
+            x: 0.0,
+            y: 0.0,
+        }
+    }
+
+    /// First stack-clearing operator may have advance width as extra first argument,
+    /// it is not needed because advance is taken from `hmtx`.
+    fn skip_width(&mut self, has_width: bool) {
+        if !self.width_parsed {
+            if has_width && !self.stack.is_empty() {
+                self.stack.remove(0);
+            }
+            self.width_parsed = true;
+        }
+    }
+
+    fn move_to(&mut self, dx: f32, dy: f32) {
+        self.x += dx;
+        self.y += dy;
+        self.outline.push(vec![Vec2::new(self.x, self.y)]);
+    }
+
+    fn line_to(&mut self, dx: f32, dy: f32) {
+        self.x += dx;
+        self.y += dy;
+        let point = Vec2::new(self.x, self.y);
+        if let Some(contour) = self.outline.last_mut() {
+            contour.push(point);
+        }
+    }
+
+    /// Adds cubic curve, all points are absolute.
+    fn curve_to(&mut self, c1: Vec2, c2: Vec2, end: Vec2) {
+        let start = Vec2::new(self.x, self.y);
+        if let Some(contour) = self.outline.last_mut() {
+            for step in 1..=CURVE_STEPS {
+                let t = step as f32 / CURVE_STEPS as f32;
+                let inv_t = 1.0 - t;
+                let k0 = inv_t * inv_t * inv_t;
+                let k1 = 3.0 * inv_t * inv_t * t;
+                let k2 = 3.0 * inv_t * t * t;
+                let k3 = t * t * t;
+                contour.push(Vec2::new(
+                    k0 * start.x + k1 * c1.x + k2 * c2.x + k3 * end.x,
+                    k0 * start.y + k1 * c1.y + k2 * c2.y + k3 * end.y,
+                ));
+            }
+        }
+        self.x = end.x;
+        self.y = end.y;
+    }
+
+    /// Adds cubic curve defined by six relative coordinates starting at `i`.
+    fn rel_curve_to(&mut self, i: usize) {
+        let s = &self.stack;
+        let c1 = Vec2::new(self.x + s[i], self.y + s[i + 1]);
+        let c2 = Vec2::new(c1.x + s[i + 2], c1.y + s[i + 3]);
+        let end = Vec2::new(c2.x + s[i + 4], c2.y + s[i + 5]);
+        self.curve_to(c1, c2, end);
+    }
+
+    fn read_stems(&mut self) {
+        let has_width = self.stack.len() % 2 != 0;
+        self.skip_width(has_width);
+        self.stem_count += self.stack.len() / 2;
+        self.stack.clear();
+    }
+
+    /// Executes charstring. Returns `Some(true)` when `endchar` was reached and `None` if
+    /// charstring is malformed.
+    fn execute(&mut self, code: &[u8], depth: usize) -> Option<bool> {
+        if depth > MAX_SUBR_DEPTH {
+            return None;
+        }
+
+        let mut pos = 0;
+        while pos < code.len() {
+            let b0 = code[pos];
+            pos += 1;
+            self.budget = self.budget.checked_sub(1)?;
+            match b0 {
+                1 | 3 | 18 | 23 => self.read_stems(),
+                19 | 20 => {
+                    // hintmask and cntrmask can have implicit vstem arguments.
+                    self.read_stems();
+                    pos += (self.stem_count + 7) / 8;
+                }
+                21 => {
+                    let has_width = self.stack.len() > 2;
+                    self.skip_width(has_width);
+                    let (dx, dy) = (*self.stack.get(0)?, *self.stack.get(1)?);
+                    self.move_to(dx, dy);
+                    self.stack.clear();
+                }
+                22 | 4 => {
+                    let has_width = self.stack.len() > 1;
+                    self.skip_width(has_width);
+                    let d = *self.stack.get(0)?;
+                    if b0 == 22 {
+                        self.move_to(d, 0.0);
+                    } else {
+                        self.move_to(0.0, d);
+                    }
+                    self.stack.clear();
+                }
+                5 => {
+                    let mut i = 0;
+                    while i + 2 <= self.stack.len() {
+                        let (dx, dy) = (self.stack[i], self.stack[i + 1]);
+                        self.line_to(dx, dy);
+                        i += 2;
+                    }
+                    self.stack.clear();
+                }
+                6 | 7 => {
+                    let mut horizontal = b0 == 6;
+                    for i in 0..self.stack.len() {
+                        let d = self.stack[i];
+                        if horizontal {
+                            self.line_to(d, 0.0);
+                        } else {
+                            self.line_to(0.0, d);
+                        }
+                        horizontal = !horizontal;
+                    }
+                    self.stack.clear();
+                }
+                8 => {
+                    let mut i = 0;
+                    while i + 6 <= self.stack.len() {
+                        self.rel_curve_to(i);
+                        i += 6;
+                    }
+                    self.stack.clear();
+                }
+                24 => {
+                    let mut i = 0;
+                    while i + 8 <= self.stack.len() {
+                        self.rel_curve_to(i);
+                        i += 6;
+                    }
+                    if i + 2 <= self.stack.len() {
+                        let (dx, dy) = (self.stack[i], self.stack[i + 1]);
+                        self.line_to(dx, dy);
+                    }
+                    self.stack.clear();
+                }
+                25 => {
+                    let mut i = 0;
+                    while i + 8 <= self.stack.len() {
+                        let (dx, dy) = (self.stack[i], self.stack[i + 1]);
+                        self.line_to(dx, dy);
+                        i += 2;
+                    }
+                    if i + 6 <= self.stack.len() {
+                        self.rel_curve_to(i);
+                    }
+                    self.stack.clear();
+                }
+                26 | 27 => {
+                    // vvcurveto and hhcurveto, odd argument is first offset across curve direction.
+                    let mut i = 0;
+                    let mut first = 0.0;
+                    if self.stack.len() % 2 != 0 {
+                        first = self.stack[0];
+                        i = 1;
+                    }
+                    while i + 4 <= self.stack.len() {
+                        let s = &self.stack;
+                        let (c1, c2, end) = if b0 == 26 {
+                            let c1 = Vec2::new(self.x + first, self.y + s[i]);
+                            let c2 = Vec2::new(c1.x + s[i + 1], c1.y + s[i + 2]);
+                            (c1, c2, Vec2::new(c2.x, c2.y + s[i + 3]))
+                        } else {
+                            let c1 = Vec2::new(self.x + s[i], self.y + first);
+                            let c2 = Vec2::new(c1.x + s[i + 1], c1.y + s[i + 2]);
+                            (c1, c2, Vec2::new(c2.x + s[i + 3], c2.y))
+                        };
+                        self.curve_to(c1, c2, end);
+                        first = 0.0;
+                        i += 4;
+                    }
+                    self.stack.clear();
+                }
+                30 | 31 => {
+                    // vhcurveto and hvcurveto, curves alternate between starting vertically
+                    // and horizontally, last curve can have one extra argument.
+                    let mut horizontal = b0 == 31;
+                    let mut i = 0;
+                    while i + 4 <= self.stack.len() {
+                        let s = &self.stack;
+                        let last = if s.len() - i == 5 { s[i + 4] } else { 0.0 };
+                        let (c1, c2, end) = if horizontal {
+                            let c1 = Vec2::new(self.x + s[i], self.y);
+                            let c2 = Vec2::new(c1.x + s[i + 1], c1.y + s[i + 2]);
+                            (c1, c2, Vec2::new(c2.x + last, c2.y + s[i + 3]))
+                        } else {
+                            let c1 = Vec2::new(self.x, self.y + s[i]);
+                            let c2 = Vec2::new(c1.x + s[i + 1], c1.y + s[i + 2]);
+                            (c1, c2, Vec2::new(c2.x + s[i + 3], c2.y + last))
+                        };
+                        self.curve_to(c1, c2, end);
+                        horizontal = !horizontal;
+                        i += 4;
+                    }
+                    self.stack.clear();
+                }
+                10 | 29 => {
+                    let subrs = if b0 == 10 { self.local_subrs } else { self.global_subrs };
+                    let index = (self.stack.pop()? as i32).checked_add(subr_bias(subrs.len()))?;
+                    if index < 0 {
+                        return None;
+                    }
+                    let subr = subrs.get(index as usize)?;
+                    if self.execute(subr, depth + 1)? {
+                        return Some(true);
+                    }
+                }
+                11 => return Some(false),
+                14 => {
+                    let has_width = self.stack.len() == 1 || self.stack.len() == 5;
+                    self.skip_width(has_width);
+                    return Some(true);
+                }
+                12 => {
+                    let b1 = *code.get(pos)?;
+                    pos += 1;
+                    self.execute_flex(b1)?;
+                    self.stack.clear();
+                }
+                28 => {
+                    let value = read_u16(code, pos)? as i16;
+                    self.stack.push(f32::from(value));
+                    pos += 2;
+                }
+                32..=246 => self.stack.push(f32::from(b0) - 139.0),
+                247..=250 => {
+                    let b1 = f32::from(*code.get(pos)?);
+                    pos += 1;
+                    self.stack.push((f32::from(b0) - 247.0) * 256.0 + b1 + 108.0);
+                }
+                251..=254 => {
+                    let b1 = f32::from(*code.get(pos)?);
+                    pos += 1;
+                    self.stack.push(-(f32::from(b0) - 251.0) * 256.0 - b1 - 44.0);
+                }
+                255 => {
+                    let value = (read_u16(code, pos)? as u32) << 16 | read_u16(code, pos + 2)? as u32;
+                    self.stack.push(value as i32 as f32 / 65536.0);
+                    pos += 4;
+                }
+                _ => return None,
+            }
+        }
+
+        Some(false)
+    }
+
+    /// Executes flex family of operators, other two-byte operators are ignored.
+    fn execute_flex(&mut self, op: u8) -> Option<()> {
+        let s = self.stack.clone();
+        let (x, y) = (self.x, self.y);
+        match op {
+            35 => {
+                if s.len() < 12 {
+                    return None;
+                }
+                self.rel_curve_to(0);
+                self.rel_curve_to(6);
+            }
+            34 => {
+                if s.len() < 7 {
+                    return None;
+                }
+                let c1 = Vec2::new(x + s[0], y);
+                let c2 = Vec2::new(c1.x + s[1], c1.y + s[2]);
+                let p3 = Vec2::new(c2.x + s[3], c2.y);
+                self.curve_to(c1, c2, p3);
+                let c4 = Vec2::new(p3.x + s[4], p3.y);
+                let c5 = Vec2::new(c4.x + s[5], y);
+                self.curve_to(c4, c5, Vec2::new(c5.x + s[6], y));
+            }
+            36 => {
+                if s.len() < 9 {
+                    return None;
+                }
+                let c1 = Vec2::new(x + s[0], y + s[1]);
+                let c2 = Vec2::new(c1.x + s[2], c1.y + s[3]);
+                let p3 = Vec2::new(c2.x + s[4], c2.y);
+                self.curve_to(c1, c2, p3);
+                let c4 = Vec2::new(p3.x + s[5], p3.y);
+                let c5 = Vec2::new(c4.x + s[6], c4.y + s[7]);
+                self.curve_to(c4, c5, Vec2::new(c5.x + s[8], y));
+            }
+            37 => {
+                if s.len() < 11 {
+                    return None;
+                }
+                let c1 = Vec2::new(x + s[0], y + s[1]);
+                let c2 = Vec2::new(c1.x + s[2], c1.y + s[3]);
+                let p3 = Vec2::new(c2.x + s[4], c2.y + s[5]);
+                self.curve_to(c1, c2, p3);
+                let c4 = Vec2::new(p3.x + s[6], p3.y + s[7]);
+                let c5 = Vec2::new(c4.x + s[8], c4.y + s[9]);
+                let (dx, dy) = (c5.x - x, c5.y - y);
+                let end = if dx.abs() > dy.abs() {
+                    Vec2::new(c5.x + s[10], y)
+                } else {
+                    Vec2::new(x, c5.y + s[10])
+                };
+                self.curve_to(c4, c5, end);
+            }
+            _ => (),
+        }
+        Some(())
+    }
+}
+
+/// Reads outlines of every glyph from `CFF ` table. Glyphs with malformed charstrings
+/// get empty outline, `None` is returned only if table itself is malformed.
+pub(in crate) fn read_outlines(cff: &[u8]) -> Option<Vec<CffOutline>> {
+    let header_size = read_u8(cff, 2)? as usize;
+    let (_, top_dict_pos) = read_index(cff, header_size)?;
+    let (top_dicts, string_index_pos) = read_index(cff, top_dict_pos)?;
+    let (_, global_subrs_pos) = read_index(cff, string_index_pos)?;
+    let (global_subrs, _) = read_index(cff, global_subrs_pos)?;
+
+    let top_dict = read_dict(top_dicts.first()?)?;
+    if dict_value(&top_dict, CHARSTRING_TYPE_OP, 0).unwrap_or(2) != 2 {
+        return None;
+    }
+    let (char_strings, _) = read_index(cff, dict_value(&top_dict, CHARSTRINGS_OP, 0)?)?;
+
+    // CID-keyed fonts have separate local subroutines for each Font DICT.
+    let mut local_subrs = Vec::new();
+    let mut fd_select = None;
+    if let Some(fd_array_offset) = dict_value(&top_dict, FD_ARRAY_OP, 0) {
+        let (font_dicts, _) = read_index(cff, fd_array_offset)?;
+        for font_dict in font_dicts {
+            local_subrs.push(read_dict(font_dict).map_or(Vec::new(), |dict| read_local_subrs(cff, &dict)));
+        }
+        fd_select = Some(read_fd_select(cff, dict_value(&top_dict, FD_SELECT_OP, 0)?, char_strings.len())?);
+    } else {
+        local_subrs.push(read_local_subrs(cff, &top_dict));
+    }
+
+    let empty = Vec::new();
+    let mut outlines = Vec::with_capacity(char_strings.len());
+    for (i, char_string) in char_strings.iter().enumerate() {
+        let fd = fd_select.as_ref().map_or(0, |fd_select| fd_select[i]);
+        let subrs = local_subrs.get(fd).unwrap_or(&empty);
+        let mut interpreter = CharstringInterpreter::new(&global_subrs, subrs);
+        let outline = match interpreter.execute(char_string, 0) {
+            Some(_) => interpreter.outline
+                .into_iter()
+                .filter(|contour| contour.len() > 2)
+                .collect(),
+            None => Vec::new()
+        };
+        outlines.push(outline);
+    }
+
+    Some(outlines)
+}
@@ -9,7 +9,8 @@ use crate::{
         UiMessage,
         UiMessageData,
         CheckBoxMessage,
-        WidgetMessage
+        WidgetMessage,
+        NavigationInput,
     },
     Thickness,
     border::BorderBuilder,
@@ -19,7 +20,8 @@ use crate::{
         color::Color,
     },
     brush::Brush,
-    NodeHandleMapping
+    NodeHandleMapping,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 pub struct CheckBox<M: 'static, C: 'static + Control<M, C>> {
@@ -56,23 +58,23 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for CheckBox<M, C> {
             UiMessageData::Widget(ref msg) => {
                 match msg {
                     WidgetMessage::MouseDown { .. } => {
-                        if message.source == self_handle || self.widget.has_descendant(message.source, ui) {
-                            ui.capture_mouse(self_handle);
-                        }
+                        ui.capture_mouse(self_handle);
                     }
                     WidgetMessage::MouseUp { .. } => {
-                        if message.source == self_handle || self.widget.has_descendant(message.source, ui) {
-                            ui.release_mouse_capture();
-
-                            if let Some(value) = self.checked {
-                                // Invert state if it is defined.
-                                self.set_checked(Some(!value));
-                            } else {
-                                // Switch from undefined state to checked.
-                                self.set_checked(Some(true));
-                            }
+                        ui.release_mouse_capture();
+
+                        if let Some(value) = self.checked {
+                            // Invert state if it is defined.
+                            self.set_checked(Some(!value));
+                        } else {
+                            // Switch from undefined state to checked.
+                            self.set_checked(Some(true));
                         }
                     }
+                    WidgetMessage::Navigation(NavigationInput::Accept) => {
+                        self.set_checked(Some(!self.checked.unwrap_or(false)));
+                        message.handled = true;
+                    }
                     _ => ()
                 }
             }
@@ -125,6 +127,14 @@ impl<M, C: 'static + Control<M, C>> CheckBox<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for CheckBox<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit("checked", &mut self.checked)?;
+        visitor.visit_handle("check_mark", &mut self.check_mark)
+    }
+}
+
 pub struct CheckBoxBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     checked: Option<bool>,
@@ -150,7 +160,11 @@ impl<M, C: 'static + Control<M, C>> CheckBoxBuilder<M, C> {
         self
     }
 
-    pub fn build(self, ui: &mut UserInterface<M, C>) -> Handle<UINode<M, C>> {
+    pub fn build(mut self, ui: &mut UserInterface<M, C>) -> Handle<UINode<M, C>> {
+        if self.widget_builder.focusable.is_none() {
+            self.widget_builder.focusable = Some(true);
+        }
+
         let check_mark = self.check_mark.unwrap_or_else(|| {
             BorderBuilder::new(WidgetBuilder::new()
                 .with_background(Brush::Solid(Color::opaque(200, 200, 200)))
@@ -0,0 +1,33 @@
+//! Clipboard abstraction. UI has no access to clipboard of OS by itself, so host application
+//! should install its own implementation using `UserInterface::set_clipboard`. By default
+//! UI uses clipboard that keeps text in memory.
+
+pub trait Clipboard {
+    /// Returns current content of clipboard if it has any text.
+    fn get_text(&mut self) -> Option<String>;
+
+    /// Replaces content of clipboard with given text.
+    fn set_text(&mut self, text: String);
+}
+
+/// Clipboard that keeps text in memory. Useful for headless environments and tests.
+#[derive(Default, Debug)]
+pub struct MemoryClipboard {
+    text: Option<String>,
+}
+
+impl MemoryClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get_text(&mut self) -> Option<String> {
+        self.text.clone()
+    }
+
+    fn set_text(&mut self, text: String) {
+        self.text = Some(text);
+    }
+}
@@ -21,6 +21,7 @@ use crate::{
     },
     border::BorderBuilder,
     NodeHandleMapping,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 pub struct ComboBox<M: 'static, C: 'static + Control<M, C>> {
@@ -65,14 +66,12 @@ impl<M: 'static, C: 'static + Control<M, C>> Control<M, C> for ComboBox<M, C> {
         match &message.data {
             UiMessageData::Widget(msg) => {
                 if let WidgetMessage::MouseDown { .. } = msg {
-                    if message.source == self_handle || self.widget.has_descendant(message.source, ui) {
-                        if let UINode::Popup(popup) = ui.node_mut(self.popup) {
-                            popup.widget_mut()
-                                .set_width_mut(self.widget.actual_size().x);
-                            let placement_position = self.widget.screen_position + Vec2::new(0.0, self.widget.actual_size().y);
-                            popup.set_placement(Placement::Position(placement_position));
-                            popup.open();
-                        }
+                    if let UINode::Popup(popup) = ui.node_mut(self.popup) {
+                        popup.widget_mut()
+                            .set_width_mut(self.widget.actual_size().x);
+                        let placement_position = self.widget.screen_position + Vec2::new(0.0, self.widget.actual_size().y);
+                        popup.set_placement(Placement::Position(placement_position));
+                        popup.open();
                     }
                 }
             }
@@ -130,6 +129,28 @@ impl<M: 'static, C: 'static + Control<M, C>> ComboBox<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Default for ComboBox<M, C> {
+    fn default() -> Self {
+        Self {
+            widget: Widget::default(),
+            popup: Handle::NONE,
+            items: Vec::new(),
+            items_control: Handle::NONE,
+            current: Handle::NONE,
+        }
+    }
+}
+
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for ComboBox<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit_handle("popup", &mut self.popup)?;
+        visitor.visit_handles("items", &mut self.items)?;
+        visitor.visit_handle("items_control", &mut self.items_control)?;
+        visitor.visit_handle("current", &mut self.current)
+    }
+}
+
 pub struct ComboBoxBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     items: Vec<Handle<UINode<M, C>>>,
@@ -148,7 +169,11 @@ impl<M: 'static, C: 'static + Control<M, C>> ComboBoxBuilder<M, C> {
         self
     }
 
-    pub fn build(self, ui: &mut UserInterface<M, C>) -> Handle<UINode<M, C>> where Self: Sized {
+    pub fn build(mut self, ui: &mut UserInterface<M, C>) -> Handle<UINode<M, C>> where Self: Sized {
+        if self.widget_builder.focusable.is_none() {
+            self.widget_builder.focusable = Some(true);
+        }
+
         let items_control = ItemsControlBuilder::new(WidgetBuilder::new()
             .with_max_size(Vec2::new(std::f32::INFINITY, 300.0)))
             .with_items(self.items.clone())
@@ -25,6 +25,7 @@ use crate::{
     },
     border::BorderBuilder,
     NodeHandleMapping,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 /// A visual element that changes its appearance by listening specific events.
@@ -110,42 +111,40 @@ impl<M: 'static, C: 'static + Control<M, C>> Control<M, C> for Decorator<M, C> {
 
         match &message.data {
             UiMessageData::Widget(msg) => {
-                if message.source == self_handle || self.widget().has_descendant(message.source, ui) {
-                    match msg {
-                        WidgetMessage::MouseLeave => {
-                            if self.is_selected {
-                                self.border
-                                    .widget_mut()
-                                    .set_background(self.selected_brush.clone());
-                            } else {
-                                self.border
-                                    .widget_mut()
-                                    .set_background(self.normal_brush.clone());
-                            }
-                        }
-                        WidgetMessage::MouseEnter => {
+                match msg {
+                    WidgetMessage::MouseLeave => {
+                        if self.is_selected {
                             self.border
                                 .widget_mut()
-                                .set_background(self.hover_brush.clone());
-                        }
-                        WidgetMessage::MouseDown { .. } => {
+                                .set_background(self.selected_brush.clone());
+                        } else {
                             self.border
                                 .widget_mut()
-                                .set_background(self.pressed_brush.clone());
+                                .set_background(self.normal_brush.clone());
                         }
-                        WidgetMessage::MouseUp { .. } => {
-                            if self.is_selected {
-                                self.border
-                                    .widget_mut()
-                                    .set_background(self.selected_brush.clone());
-                            } else {
-                                self.border
-                                    .widget_mut()
-                                    .set_background(self.normal_brush.clone());
-                            }
+                    }
+                    WidgetMessage::MouseEnter => {
+                        self.border
+                            .widget_mut()
+                            .set_background(self.hover_brush.clone());
+                    }
+                    WidgetMessage::MouseDown { .. } => {
+                        self.border
+                            .widget_mut()
+                            .set_background(self.pressed_brush.clone());
+                    }
+                    WidgetMessage::MouseUp { .. } => {
+                        if self.is_selected {
+                            self.border
+                                .widget_mut()
+                                .set_background(self.selected_brush.clone());
+                        } else {
+                            self.border
+                                .widget_mut()
+                                .set_background(self.normal_brush.clone());
                         }
-                        _ => {}
                     }
+                    _ => {}
                 }
             }
             UiMessageData::ItemsControl(msg) => {
@@ -191,6 +190,30 @@ impl<M: 'static, C: 'static + Control<M, C>> Control<M, C> for Decorator<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Default for Decorator<M, C> {
+    fn default() -> Self {
+        Self {
+            border: Border::new(Widget::default()),
+            normal_brush: Brush::Solid(Color::TRANSPARENT),
+            hover_brush: Brush::Solid(Color::TRANSPARENT),
+            pressed_brush: Brush::Solid(Color::TRANSPARENT),
+            selected_brush: Brush::Solid(Color::TRANSPARENT),
+            is_selected: false,
+        }
+    }
+}
+
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for Decorator<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.border.visit(visitor)?;
+        visitor.visit("normal_brush", &mut self.normal_brush)?;
+        visitor.visit("hover_brush", &mut self.hover_brush)?;
+        visitor.visit("pressed_brush", &mut self.pressed_brush)?;
+        visitor.visit("selected_brush", &mut self.selected_brush)?;
+        visitor.visit("is_selected", &mut self.is_selected)
+    }
+}
+
 pub struct DecoratorBuilder<M: 'static, C: 'static + Control<M, C>> {
     border_builder: BorderBuilder<M, C>,
     normal_brush: Option<Brush>,
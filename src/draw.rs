@@ -30,6 +30,16 @@ impl Vertex {
             tex_coord,
         }
     }
+
+    #[inline]
+    pub fn pos(&self) -> Vec2 {
+        self.pos
+    }
+
+    #[inline]
+    pub fn tex_coord(&self) -> Vec2 {
+        self.tex_coord
+    }
 }
 
 #[derive(PartialEq, Copy, Clone, Debug)]
@@ -44,7 +54,12 @@ pub type Texture = dyn Any + Sync + Send;
 pub enum CommandTexture {
     None,
     Texture(Arc<Texture>),
-    Font(Arc<Mutex<Font>>),
+    Font {
+        font: Arc<Mutex<Font>>,
+        /// Atlas of font contains signed distance fields of glyphs and must be
+        /// drawn using distance field shader.
+        distance_field: bool,
+    },
 }
 
 #[derive(Clone)]
@@ -366,25 +381,86 @@ impl DrawingContext {
         }
     }
 
+    /// Draws formatted text. Glyphs of each font and brush of the text are committed as
+    /// separate command, because each font has its own atlas texture.
     pub fn draw_text(&mut self, position: Vec2, formatted_text: &FormattedText) {
-        let font = if let Some(font) = formatted_text.get_font() {
-            font
-        } else {
+        if formatted_text.get_font().is_none() {
             println!("Trying to draw text without font!");
             return;
-        };
+        }
 
-        for element in formatted_text.get_glyphs() {
-            let bounds = element.get_bounds();
+        for font_index in 0..formatted_text.font_count() {
+            if let Some(font) = formatted_text.font_by_index(font_index) {
+                self.draw_text_glyphs(position, formatted_text, font_index, font);
+            }
+        }
 
-            let final_bounds = Rect::new(
-                position.x + bounds.x, position.y + bounds.y,
-                bounds.w, bounds.h);
+        self.draw_text_decorations(position, formatted_text);
+    }
 
-            self.push_rect_filled(&final_bounds, Some(element.get_tex_coords()));
+    /// Draws underlines and strikethrough lines of formatted text, one command per brush.
+    fn draw_text_decorations(&mut self, position: Vec2, formatted_text: &FormattedText) {
+        let mut brush_indices = Vec::new();
+        for decoration in formatted_text.get_decorations() {
+            if !brush_indices.contains(&decoration.get_brush_index()) {
+                brush_indices.push(decoration.get_brush_index());
+            }
         }
 
-        self.commit(CommandKind::Geometry, formatted_text.brush(), CommandTexture::Font(font))
+        for brush_index in brush_indices {
+            for decoration in formatted_text.get_decorations().iter().filter(|d| d.get_brush_index() == brush_index) {
+                let bounds = decoration.get_bounds();
+                self.push_rect_filled(&Rect::new(position.x + bounds.x, position.y + bounds.y, bounds.w, bounds.h), None);
+            }
+            self.commit(CommandKind::Geometry, formatted_text.brush_by_index(brush_index), CommandTexture::None);
+        }
+    }
+
+    fn draw_text_glyphs(&mut self, position: Vec2, formatted_text: &FormattedText, font_index: usize, font: Arc<Mutex<Font>>) {
+        // Atlas could grow since text was built, glyphs keep their locations in pixels so
+        // texture coordinates just have to be scaled.
+        let (atlas_size, distance_field) = {
+            let font = font.lock().unwrap();
+            (font.get_atlas_size(), font.is_distance_field())
+        };
+        let tex_coord_scale = if atlas_size > 0 && formatted_text.atlas_size(font_index) > 0 {
+            formatted_text.atlas_size(font_index) as f32 / atlas_size as f32
+        } else {
+            1.0
+        };
+
+        // Glyphs of different brushes are committed as separate commands.
+        let mut brush_indices = Vec::new();
+        for glyph in formatted_text.get_glyphs().iter().filter(|glyph| glyph.get_font_index() == font_index) {
+            if !brush_indices.contains(&glyph.get_brush_index()) {
+                brush_indices.push(glyph.get_brush_index());
+            }
+        }
+
+        for brush_index in brush_indices {
+            let glyphs = formatted_text.get_glyphs()
+                .iter()
+                .filter(|glyph| glyph.get_font_index() == font_index && glyph.get_brush_index() == brush_index);
+            for element in glyphs {
+                let bounds = element.get_bounds();
+
+                let final_bounds = Rect::new(
+                    position.x + bounds.x, position.y + bounds.y,
+                    bounds.w, bounds.h);
+
+                if tex_coord_scale == 1.0 {
+                    self.push_rect_filled(&final_bounds, Some(element.get_tex_coords()));
+                } else {
+                    let mut tex_coords = *element.get_tex_coords();
+                    for tex_coord in tex_coords.iter_mut() {
+                        *tex_coord = tex_coord.scale(tex_coord_scale);
+                    }
+                    self.push_rect_filled(&final_bounds, Some(&tex_coords));
+                }
+            }
+
+            self.commit(CommandKind::Geometry, formatted_text.brush_by_index(brush_index), CommandTexture::Font { font: font.clone(), distance_field })
+        }
     }
 
     pub fn commit_clip_rect(&mut self, clip_rect: &Rect<f32>) {
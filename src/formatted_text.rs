@@ -7,25 +7,42 @@ use crate::{
         },
     },
     ttf::Font,
+    bidi,
+    shaping,
     HorizontalAlignment,
     VerticalAlignment,
-    brush::Brush
+    brush::Brush,
+    Control,
+    serialization::{Visit, Visitor, VisitResult},
 };
 use std::{
     ops::Range,
     sync::{
         Arc,
         Mutex,
+        MutexGuard,
     },
 };
 
 #[derive(Debug, Clone)]
 pub struct TextGlyph {
     bounds: Rect<f32>,
-    tex_coords: [Vec2; 4]
+    tex_coords: [Vec2; 4],
+    /// Index of font in list of fonts of formatted text, zero is primary font.
+    font_index: usize,
+    /// Index of span which brush is used to draw glyph, `None` means brush of text.
+    brush_index: Option<usize>,
 }
 
 impl TextGlyph {
+    pub fn get_font_index(&self) -> usize {
+        self.font_index
+    }
+
+    pub fn get_brush_index(&self) -> Option<usize> {
+        self.brush_index
+    }
+
     pub fn get_bounds(&self) -> Rect<f32> {
         self.bounds
     }
@@ -35,6 +52,188 @@ impl TextGlyph {
     }
 }
 
+/// Underline or strikethrough line of a run of characters.
+#[derive(Debug, Clone)]
+pub struct TextDecoration {
+    bounds: Rect<f32>,
+    /// Index of span which brush is used to draw decoration, `None` means brush of text.
+    brush_index: Option<usize>,
+}
+
+impl TextDecoration {
+    pub fn get_bounds(&self) -> Rect<f32> {
+        self.bounds
+    }
+
+    pub fn get_brush_index(&self) -> Option<usize> {
+        self.brush_index
+    }
+}
+
+/// Adds decoration to the list, decoration is merged with previous one if both of them
+/// form continuous line of same brush.
+fn push_decoration(decorations: &mut Vec<TextDecoration>, bounds: Rect<f32>, brush_index: Option<usize>) {
+    if let Some(last) = decorations.last_mut() {
+        if last.brush_index == brush_index
+            && (last.bounds.y - bounds.y).abs() < 0.01
+            && (last.bounds.h - bounds.h).abs() < 0.01
+            && (last.bounds.x + last.bounds.w - bounds.x).abs() < 0.01 {
+            last.bounds.w = bounds.x + bounds.w - last.bounds.x;
+            return;
+        }
+    }
+    decorations.push(TextDecoration { bounds, brush_index });
+}
+
+/// Style of a range of characters of formatted text. Properties that are not set are
+/// taken from formatted text. Range is in characters and it is not changed when text is
+/// edited.
+#[derive(Clone)]
+pub struct TextSpan {
+    pub range: Range<usize>,
+    pub brush: Option<Brush>,
+    pub font: Option<Arc<Mutex<Font>>>,
+    /// Height of characters in pixels, see `FormattedText::set_font_size`.
+    pub font_size: Option<f32>,
+    pub underline: bool,
+    pub strikethrough: bool,
+}
+
+impl TextSpan {
+    pub fn new(range: Range<usize>) -> Self {
+        Self {
+            range,
+            brush: None,
+            font: None,
+            font_size: None,
+            underline: false,
+            strikethrough: false,
+        }
+    }
+
+    pub fn with_brush(mut self, brush: Brush) -> Self {
+        self.brush = Some(brush);
+        self
+    }
+
+    pub fn with_font(mut self, font: Arc<Mutex<Font>>) -> Self {
+        self.font = Some(font);
+        self
+    }
+
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = Some(font_size);
+        self
+    }
+
+    pub fn with_underline(mut self, underline: bool) -> Self {
+        self.underline = underline;
+        self
+    }
+
+    pub fn with_strikethrough(mut self, strikethrough: bool) -> Self {
+        self.strikethrough = strikethrough;
+        self
+    }
+}
+
+/// Style of a single character, resolved from every span that covers it.
+#[derive(Copy, Clone)]
+struct CharStyle {
+    brush_index: Option<usize>,
+    /// Preferred font, `None` means primary font.
+    font_index: Option<usize>,
+    scale: f32,
+    underline: bool,
+    strikethrough: bool,
+}
+
+pub(in crate) fn parse_color(hex: &str) -> Option<Color> {
+    let hex = hex.trim_start_matches('#');
+    let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+    let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    match hex.len() {
+        3 => Some(Color::opaque(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17)),
+        6 => Some(Color::opaque(byte(0)?, byte(2)?, byte(4)?)),
+        8 => Some(Color::from_rgba(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+        _ => None
+    }
+}
+
+/// Creates span for opening tag of markup, range of span is set later.
+fn parse_tag(tag: &str) -> Option<TextSpan> {
+    let (name, value) = match tag.find('=') {
+        Some(position) => (&tag[..position], Some(&tag[(position + 1)..])),
+        None => (tag, None)
+    };
+    let span = TextSpan::new(0..0);
+    match (name, value) {
+        ("color", Some(value)) => parse_color(value).map(|color| span.with_brush(Brush::Solid(color))),
+        ("size", Some(value)) => value.parse::<f32>().ok()
+            .filter(|size| *size > 0.0)
+            .map(|size| span.with_font_size(size)),
+        ("u", None) => Some(span.with_underline(true)),
+        ("s", None) => Some(span.with_strikethrough(true)),
+        _ => None
+    }
+}
+
+/// Splits markup on plain text and spans. Supported tags are `[color=#rrggbb]` (also
+/// `#rgb` and `#rrggbbaa`), `[size=N]`, `[u]` and `[s]`, each of them is closed by
+/// `[/tag]`. `[[` is literal `[`, unknown tags are left in text as is.
+fn parse_markup(markup: &str) -> (String, Vec<TextSpan>) {
+    let chars = markup.chars().collect::<Vec<char>>();
+    let mut text = String::new();
+    let mut length = 0;
+    let mut spans: Vec<TextSpan> = Vec::new();
+    // Name of tag and index of its span for each opened tag.
+    let mut opened: Vec<(String, usize)> = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '[' {
+            if chars.get(i + 1) == Some(&'[') {
+                text.push('[');
+                length += 1;
+                i += 2;
+                continue;
+            }
+            if let Some(tag_len) = chars[(i + 1)..].iter().position(|c| *c == ']') {
+                let tag = chars[(i + 1)..(i + 1 + tag_len)].iter().collect::<String>();
+                let handled = if let Some(name) = tag.strip_prefix('/') {
+                    match opened.iter().rposition(|(opened_name, _)| opened_name == name) {
+                        Some(position) => {
+                            let (_, span_index) = opened.remove(position);
+                            spans[span_index].range.end = length;
+                            true
+                        }
+                        None => false
+                    }
+                } else if let Some(mut span) = parse_tag(&tag) {
+                    let name = tag.split('=').next().unwrap_or_default().to_owned();
+                    span.range = length..length;
+                    opened.push((name, spans.len()));
+                    spans.push(span);
+                    true
+                } else {
+                    false
+                };
+                if handled {
+                    i += tag_len + 2;
+                    continue;
+                }
+            }
+        }
+        text.push(chars[i]);
+        length += 1;
+        i += 1;
+    }
+    // Tags that were not closed span till the end of text.
+    for (_, span_index) in opened {
+        spans[span_index].range.end = length;
+    }
+    (text, spans)
+}
+
 #[derive(Copy, Clone, Debug)]
 pub struct TextLine {
     /// Index of starting symbol in text array.
@@ -43,7 +242,7 @@ pub struct TextLine {
     pub end: usize,
     /// Total width of line.
     pub width: f32,
-    /// Total height of line. Usually just ascender of a font.
+    /// Total height of line. Usually just ascender of a font multiplied by line spacing.
     pub height: f32,
     /// Local horizontal position of line.
     pub x_offset: f32,
@@ -51,6 +250,62 @@ pub struct TextLine {
     pub y_offset: f32,
 }
 
+/// Defines how text is split on lines when it does not fit in width constraint.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum WrapMode {
+    /// Text is never wrapped, only new line characters start new line.
+    NoWrap,
+    /// Line is broken at the character that overflows constraint.
+    Character,
+    /// Line is broken at last line break opportunity before overflowing character, such
+    /// as whitespace, hyphen or CJK ideograph. Falls back to character wrapping for words
+    /// that are wider than constraint.
+    Word,
+}
+
+impl Default for WrapMode {
+    fn default() -> Self {
+        WrapMode::NoWrap
+    }
+}
+
+fn is_whitespace(code: u32) -> bool {
+    code == u32::from(b' ') || code == u32::from(b'\t') || code == 0x3000 || code == 0x200B
+}
+
+fn is_cjk(code: u32) -> bool {
+    // CJK punctuation (U+3000..U+303F) is excluded to not start line with it.
+    (0x2E80..0x3000).contains(&code)
+        || (0x3040..=0x30FF).contains(&code)
+        || (0x3100..=0x9FFF).contains(&code)
+        || (0xAC00..=0xD7AF).contains(&code)
+        || (0xF900..=0xFAFF).contains(&code)
+}
+
+/// Returns true if new line can start at `next` character which follows `prev` character.
+/// This is simplified subset of Unicode line breaking algorithm (UAX #14): break is allowed
+/// after spaces and hyphens and between ideographs.
+fn is_break_opportunity(prev: u32, next: u32) -> bool {
+    if is_whitespace(next) {
+        return false;
+    }
+    is_whitespace(prev)
+        || prev == u32::from(b'-')
+        || prev == 0x2010
+        || is_cjk(prev)
+        || is_cjk(next)
+}
+
+/// Horizontal ellipsis that is used to trim overflowing lines.
+const ELLIPSIS: u32 = 0x2026;
+
+/// Maximum amount of characters that are checked for ligature.
+const MAX_LIGATURE_LENGTH: usize = 8;
+
+fn is_new_line(code: u32) -> bool {
+    code == u32::from(b'\n') || code == u32::from(b'\r')
+}
+
 impl TextLine {
     fn new() -> TextLine {
         TextLine {
@@ -75,6 +330,11 @@ impl TextLine {
 #[derive(Clone)]
 pub struct FormattedText {
     font: Option<Arc<Mutex<Font>>>,
+    /// Fonts that are used for characters which are missing in primary font, in order
+    /// of priority.
+    fallback_fonts: Vec<Arc<Mutex<Font>>>,
+    /// Index of font for each character of text, filled on build.
+    font_indices: Vec<usize>,
     /// Text in UTF32 format.
     text: Vec<u32>,
     /// Temporary buffer used to split text on lines. We need it to reduce memory allocations
@@ -83,11 +343,54 @@ pub struct FormattedText {
     lines: Vec<TextLine>,
     /// Final glyphs for draw buffer.
     glyphs: Vec<TextGlyph>,
+    /// Underlines and strikethrough lines, filled on build.
+    decorations: Vec<TextDecoration>,
+    /// Styled ranges of text, later spans override earlier ones.
+    spans: Vec<TextSpan>,
+    /// Bidi embedding level of each character, odd levels are right-to-left. Filled on build.
+    levels: Vec<u8>,
+    /// Level of paragraph of each character, filled on build.
+    base_levels: Vec<u8>,
+    /// Advance of each character including size scale, zero for characters that are
+    /// parts of ligatures. Filled on build.
+    advances: Vec<f32>,
+    /// Kerning between each character and previous character, filled on build.
+    kernings: Vec<f32>,
+    /// True if `advances` and `kernings` match current text, fonts and size. Reset by
+    /// every method that changes them, set on build.
+    metrics_valid: bool,
+    /// Horizontal position of each character in visual order, filled on build.
+    char_positions: Vec<f32>,
     vertical_alignment: VerticalAlignment,
     horizontal_alignment: HorizontalAlignment,
     brush: Brush,
     constraint: Vec2,
-    wrap: bool,
+    wrap: WrapMode,
+    /// Lines that do not fit in width constraint are cut and ended with ellipsis.
+    ellipsis: bool,
+    /// Multiplier of line height, 1.0 means that line height is ascender of primary font.
+    line_spacing: f32,
+    /// Character that is drawn instead of every character of text, used for passwords.
+    mask_char: Option<char>,
+    /// Height of text in pixels, height of primary font is used if not set. Text
+    /// scaled this way looks good only with distance field fonts.
+    font_size: Option<f32>,
+    /// Size of atlas of each font at the moment of last build, texture coordinates of
+    /// glyphs are relative to it.
+    atlas_sizes: Vec<i32>,
+}
+
+/// Locks every font of the list. Font that is same as one of previous fonts is not locked
+/// again and has `None` in its place.
+fn lock_fonts(fonts: &[Arc<Mutex<Font>>]) -> Vec<Option<MutexGuard<Font>>> {
+    fonts
+        .iter()
+        .enumerate()
+        .map(|(i, font)| {
+            let is_duplicate = fonts[..i].iter().any(|other| Arc::ptr_eq(font, other));
+            if is_duplicate { None } else { Some(font.lock().unwrap()) }
+        })
+        .collect()
 }
 
 impl FormattedText {
@@ -101,9 +404,97 @@ impl FormattedText {
 
     pub fn set_font(&mut self, font: Arc<Mutex<Font>>) -> &mut Self {
         self.font = Some(font);
+        self.metrics_valid = false;
+        self
+    }
+
+    /// Sets fonts which will be used for characters that are missing in primary font.
+    /// Fonts are checked in given order.
+    pub fn set_fallback_fonts(&mut self, fonts: Vec<Arc<Mutex<Font>>>) -> &mut Self {
+        self.fallback_fonts = fonts;
+        self.metrics_valid = false;
+        self
+    }
+
+    pub fn fallback_fonts(&self) -> &[Arc<Mutex<Font>>] {
+        &self.fallback_fonts
+    }
+
+    /// Returns font by index used in `TextGlyph`, zero is primary font, then fallback
+    /// fonts and fonts of spans follow.
+    pub fn font_by_index(&self, index: usize) -> Option<Arc<Mutex<Font>>> {
+        if self.font.is_none() {
+            None
+        } else if index == 0 {
+            self.font.clone()
+        } else if index <= self.fallback_fonts.len() {
+            self.fallback_fonts.get(index - 1).cloned()
+        } else {
+            self.spans
+                .iter()
+                .filter_map(|span| span.font.clone())
+                .nth(index - 1 - self.fallback_fonts.len())
+        }
+    }
+
+    /// Returns total amount of fonts - primary plus fallback fonts plus fonts of spans.
+    pub fn font_count(&self) -> usize {
+        if self.font.is_some() {
+            1 + self.fallback_fonts.len() + self.spans.iter().filter(|span| span.font.is_some()).count()
+        } else {
+            0
+        }
+    }
+
+    /// Returns every font in order of their indices.
+    fn all_fonts(&self) -> Vec<Arc<Mutex<Font>>> {
+        let mut fonts = Vec::new();
+        if let Some(font) = &self.font {
+            fonts.push(font.clone());
+            fonts.extend(self.fallback_fonts.iter().cloned());
+            fonts.extend(self.spans.iter().filter_map(|span| span.font.clone()));
+        }
+        fonts
+    }
+
+    pub fn get_decorations(&self) -> &[TextDecoration] {
+        &self.decorations
+    }
+
+    /// Sets styled ranges of text, later spans override properties of earlier ones.
+    pub fn set_spans(&mut self, spans: Vec<TextSpan>) -> &mut Self {
+        self.spans = spans;
+        self.metrics_valid = false;
+        self
+    }
+
+    pub fn add_span(&mut self, span: TextSpan) -> &mut Self {
+        self.spans.push(span);
+        self.metrics_valid = false;
         self
     }
 
+    pub fn spans(&self) -> &[TextSpan] {
+        &self.spans
+    }
+
+    /// Sets text and spans from markup such as `[color=#f00]red[/color]`, see
+    /// `parse_markup` for supported tags.
+    pub fn set_markup<P: AsRef<str>>(&mut self, markup: P) -> &mut Self {
+        let (text, spans) = parse_markup(markup.as_ref());
+        self.set_text(text);
+        self.spans = spans;
+        self
+    }
+
+    /// Returns brush of glyph or decoration with given brush index.
+    pub fn brush_by_index(&self, brush_index: Option<usize>) -> Brush {
+        brush_index
+            .and_then(|index| self.spans.get(index))
+            .and_then(|span| span.brush.clone())
+            .unwrap_or_else(|| self.brush.clone())
+    }
+
     pub fn get_lines(&self) -> &[TextLine] {
         &self.lines
     }
@@ -151,19 +542,139 @@ impl FormattedText {
             .collect()
     }
 
+    /// Returns width of given range of characters, including kerning between characters
+    /// inside of the range.
     pub fn get_range_width(&self, range: Range<usize>) -> f32 {
+        // Use metrics of shaped text if nothing that affects them was changed since last build.
+        if self.metrics_valid && range.end <= self.advances.len() {
+            if range.start >= range.end {
+                return 0.0;
+            }
+            return self.advances[range.clone()].iter().sum::<f32>() +
+                self.kernings[(range.start + 1)..range.end].iter().sum::<f32>();
+        }
+
         let mut width = 0.0;
-        if let Some(ref font) = self.font {
-            let font = font.lock().unwrap();
+        let range_start = range.start;
+        let all_fonts = self.all_fonts();
+        if !all_fonts.is_empty() {
+            let fonts = lock_fonts(&all_fonts);
+            let primary = fonts[0].as_ref().unwrap();
+            let size_scale = self.size_scale(primary);
             for index in range {
-                width += font.get_glyph_advance(self.text[index]);
+                let code = self.code_at(index);
+                let font_index = self.font_index_at(index);
+                let font = fonts.get(font_index).and_then(|f| f.as_ref()).unwrap_or(primary);
+                width += size_scale * font.get_glyph_advance(code);
+                if index > range_start && self.font_index_at(index - 1) == font_index {
+                    width += size_scale * font.get_kerning(self.code_at(index - 1), code);
+                }
             }
         }
         width
     }
 
+    /// Returns code of a character that will be drawn at given index, it differs from
+    /// actual character if text is masked.
+    pub fn code_at(&self, index: usize) -> u32 {
+        match self.mask_char {
+            Some(mask) => mask as u32,
+            None => self.text[index],
+        }
+    }
+
+    /// Returns horizontal position of left edge of character at given index in local
+    /// coordinates of text. Characters are placed in visual order, so in right-to-left
+    /// text position decreases with index.
+    pub fn get_char_x(&self, index: usize) -> f32 {
+        self.char_positions.get(index).cloned().unwrap_or(0.0)
+    }
+
+    /// Returns advance of character at given index, zero for characters that are drawn
+    /// as part of ligature.
+    pub fn get_char_advance(&self, index: usize) -> f32 {
+        self.advances.get(index).cloned().unwrap_or(0.0)
+    }
+
+    /// Returns true if character at given index is laid out from right to left.
+    pub fn is_rtl_at(&self, index: usize) -> bool {
+        self.levels.get(index).map_or(false, |level| level % 2 == 1)
+    }
+
+    /// Returns true if paragraph of given line has right-to-left base direction.
+    pub fn is_line_rtl(&self, line_index: usize) -> bool {
+        self.lines
+            .get(line_index)
+            .and_then(|line| self.base_levels.get(line.begin))
+            .map_or(false, |level| level % 2 == 1)
+    }
+
+    /// Returns horizontal position of caret that is placed before character with given
+    /// offset in line, in local coordinates of text.
+    pub fn caret_x(&self, line_index: usize, offset: usize) -> f32 {
+        let line = match self.lines.get(line_index) {
+            Some(line) => line,
+            None => return 0.0,
+        };
+        let end = line.end.min(self.char_positions.len());
+        let index = line.begin + offset;
+        if index < end {
+            // Caret is on the leading edge of character.
+            if self.is_rtl_at(index) {
+                self.get_char_x(index) + self.get_char_advance(index)
+            } else {
+                self.get_char_x(index)
+            }
+        } else if end > line.begin {
+            // Caret is on the trailing edge of last character.
+            let last = end - 1;
+            if self.is_rtl_at(last) {
+                self.get_char_x(last)
+            } else {
+                self.get_char_x(last) + self.get_char_advance(last)
+            }
+        } else {
+            line.x_offset
+        }
+    }
+
+    /// Returns index of a font which is used to draw character at given index.
+    fn font_index_at(&self, index: usize) -> usize {
+        self.font_indices.get(index).cloned().unwrap_or(0)
+    }
+
+    pub fn set_mask_char(&mut self, mask_char: Option<char>) -> &mut Self {
+        self.mask_char = mask_char;
+        self.metrics_valid = false;
+        self
+    }
+
+    pub fn mask_char(&self) -> Option<char> {
+        self.mask_char
+    }
+
+    /// Sets height of text in pixels, `None` means height of primary font.
+    pub fn set_font_size(&mut self, font_size: Option<f32>) -> &mut Self {
+        self.font_size = font_size;
+        self.metrics_valid = false;
+        self
+    }
+
+    pub fn font_size(&self) -> Option<f32> {
+        self.font_size
+    }
+
+    /// Returns scale that converts metrics of primary font into metrics of text.
+    fn size_scale(&self, font: &Font) -> f32 {
+        match self.font_size {
+            Some(size) if font.get_height() > 0.0 => size / font.get_height(),
+            _ => 1.0
+        }
+    }
+
     pub fn set_text<P: AsRef<str>>(&mut self, text: P) -> &mut Self {
         // Convert text to UTF32.
+        self.metrics_valid = false;
         self.text.clear();
         for code in text.as_ref().chars().map(|c| c as u32) {
             self.text.push(code);
@@ -171,16 +682,45 @@ impl FormattedText {
         self
     }
 
+    /// Enables or disables wrapping, enabled wrapping breaks lines at characters. Use
+    /// `set_wrap_mode` to break lines at word boundaries.
     pub fn set_wrap(&mut self, wrap: bool) -> &mut Self {
+        self.set_wrap_mode(if wrap { WrapMode::Character } else { WrapMode::NoWrap })
+    }
+
+    pub fn is_wrap(&self) -> bool {
+        self.wrap != WrapMode::NoWrap
+    }
+
+    pub fn set_wrap_mode(&mut self, wrap: WrapMode) -> &mut Self {
         self.wrap = wrap;
         self
     }
 
-    pub fn is_wrap(&self) -> bool {
+    pub fn wrap_mode(&self) -> WrapMode {
         self.wrap
     }
 
+    pub fn set_ellipsis(&mut self, ellipsis: bool) -> &mut Self {
+        self.ellipsis = ellipsis;
+        self
+    }
+
+    pub fn is_ellipsis(&self) -> bool {
+        self.ellipsis
+    }
+
+    pub fn set_line_spacing(&mut self, line_spacing: f32) -> &mut Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+
+    pub fn line_spacing(&self) -> f32 {
+        self.line_spacing
+    }
+
     pub fn insert_char(&mut self, c: char, index: usize) -> &mut Self {
+        self.metrics_valid = false;
         let c = c as u32;
         if index == self.text.len() {
             self.text.push(c);
@@ -190,50 +730,335 @@ impl FormattedText {
         self
     }
 
+    pub fn insert_str(&mut self, str: &str, index: usize) -> &mut Self {
+        let index = index.min(self.text.len());
+        self.text.splice(index..index, str.chars().map(|c| c as u32));
+        self.metrics_valid = false;
+        self
+    }
+
     pub fn remove_at(&mut self, index: usize) -> &mut Self {
         self.text.remove(index);
+        self.metrics_valid = false;
+        self
+    }
+
+    pub fn remove_range(&mut self, range: Range<usize>) -> &mut Self {
+        self.text.drain(range);
+        self.metrics_valid = false;
         self
     }
 
+    /// Returns size of atlas of font with given index for which texture coordinates of
+    /// glyphs were calculated. Atlas can grow after that, keeping locations of glyphs in pixels.
+    pub fn atlas_size(&self, font_index: usize) -> i32 {
+        self.atlas_sizes.get(font_index).cloned().unwrap_or(0)
+    }
+
     pub fn build(&mut self) -> Vec2 {
-        let font = if let Some(font) = &self.font {
-            font.lock().unwrap()
-        } else {
+        let all_fonts = self.all_fonts();
+        if all_fonts.is_empty() {
             return Vec2::ZERO;
+        }
+        let mut fonts = lock_fonts(&all_fonts);
+        // Same font can be used several times, every use is mapped to its first occurrence.
+        let canonical = all_fonts
+            .iter()
+            .map(|font| all_fonts.iter().position(|other| Arc::ptr_eq(font, other)).unwrap())
+            .collect::<Vec<usize>>();
+        let fallback_count = 1 + self.fallback_fonts.len();
+
+        let mask = self.mask_char.map(|c| c as u32);
+
+        let (size_scale, primary_height) = {
+            let primary = fonts[0].as_ref().unwrap();
+            (self.size_scale(primary), primary.get_height())
+        };
+
+        // Resolve style of each character, properties of later spans override properties
+        // of earlier ones.
+        let mut styles = Vec::with_capacity(self.text.len());
+        for i in 0..self.text.len() {
+            let mut style = CharStyle {
+                brush_index: None,
+                font_index: None,
+                scale: size_scale,
+                underline: false,
+                strikethrough: false,
+            };
+            let mut span_font_index = fallback_count;
+            for (span_index, span) in self.spans.iter().enumerate() {
+                let font_index = if span.font.is_some() {
+                    span_font_index += 1;
+                    Some(canonical[span_font_index - 1])
+                } else {
+                    None
+                };
+                if !span.range.contains(&i) {
+                    continue;
+                }
+                if span.brush.is_some() {
+                    style.brush_index = Some(span_index);
+                }
+                if font_index.is_some() {
+                    style.font_index = font_index;
+                }
+                if let Some(size) = span.font_size {
+                    if primary_height > 0.0 {
+                        style.scale = size / primary_height;
+                    }
+                }
+                style.underline |= span.underline;
+                style.strikethrough |= span.strikethrough;
+            }
+            styles.push(style);
+        }
+
+        // Resolve direction of each character, every line of text is separate paragraph.
+        // Masked text is never reordered.
+        self.levels.clear();
+        self.base_levels.clear();
+        let mut paragraph_begin = 0;
+        while paragraph_begin <= self.text.len() {
+            let paragraph_end = self.text[paragraph_begin..]
+                .iter()
+                .position(|code| is_new_line(*code))
+                .map_or(self.text.len(), |position| paragraph_begin + position);
+            let paragraph = &self.text[paragraph_begin..paragraph_end];
+            let base_level = if mask.is_some() { 0 } else { bidi::paragraph_level(paragraph) };
+            if mask.is_some() {
+                self.levels.extend(paragraph.iter().map(|_| 0));
+            } else {
+                self.levels.extend(bidi::resolve_levels(paragraph, base_level));
+            }
+            self.base_levels.extend(paragraph.iter().map(|_| base_level));
+            if paragraph_end < self.text.len() {
+                // New line character.
+                self.levels.push(base_level);
+                self.base_levels.push(base_level);
+            }
+            paragraph_begin = paragraph_end + 1;
+        }
+
+        // Characters that will be drawn, brackets are mirrored in right-to-left text.
+        let codes = self.text
+            .iter()
+            .zip(self.levels.iter())
+            .map(|(code, level)| match mask {
+                Some(mask) => mask,
+                None if level % 2 == 1 => bidi::mirror(*code),
+                None => *code
+            })
+            .collect::<Vec<u32>>();
+
+        // Pick font for each character. Font of span is preferred, then primary font and
+        // fallback fonts are checked.
+        self.font_indices.clear();
+        for (code, style) in codes.iter().zip(styles.iter()) {
+            let has_glyph = |index: &usize| fonts[*index].as_ref().map_or(false, |font| font.has_glyph(*code));
+            let font_index = style.font_index
+                .filter(has_glyph)
+                .or_else(|| (0..fallback_count).find(has_glyph))
+                .unwrap_or_else(|| style.font_index.unwrap_or(0));
+            self.font_indices.push(font_index);
+        }
+
+        // Shape text: pick contextual forms of letters and replace sequences of glyphs with
+        // ligatures. Ligature is drawn by its first character, rest of its characters have
+        // no glyph.
+        let forms = if mask.is_some() {
+            vec![None; codes.len()]
+        } else {
+            shaping::joining_forms(&codes)
+        };
+        let mut glyph_indices = codes
+            .iter()
+            .zip(forms)
+            .zip(self.font_indices.iter())
+            .map(|((code, form), font_index)| {
+                let font = fonts[*font_index].as_ref().unwrap();
+                let glyph = font.glyph_index(*code);
+                Some(form.map_or(glyph, |form| font.substitute_glyph(form.feature(), glyph)))
+            })
+            .collect::<Vec<Option<usize>>>();
+        if mask.is_none() {
+            let mut i = 0;
+            while i < codes.len() {
+                // Ligature can be formed only by characters of same font, style and direction.
+                let mut run_end = i + 1;
+                while run_end < codes.len()
+                    && run_end - i < MAX_LIGATURE_LENGTH
+                    && !is_new_line(self.text[run_end])
+                    && self.font_indices[run_end] == self.font_indices[i]
+                    && self.levels[run_end] == self.levels[i]
+                    && styles[run_end].scale == styles[i].scale
+                    && styles[run_end].brush_index == styles[i].brush_index {
+                    run_end += 1;
+                }
+                let run = glyph_indices[i..run_end]
+                    .iter()
+                    .map(|glyph| glyph.unwrap_or(0))
+                    .collect::<Vec<usize>>();
+                match fonts[self.font_indices[i]].as_ref().unwrap().find_ligature(&run) {
+                    Some((ligature, count)) => {
+                        glyph_indices[i] = Some(ligature);
+                        for glyph in glyph_indices[(i + 1)..(i + count)].iter_mut() {
+                            *glyph = None;
+                        }
+                        i += count;
+                    }
+                    None => i += 1
+                }
+            }
+        }
+
+        // Make sure that every glyph is rasterized before layout. Glyph that cannot be
+        // cached is drawn as empty space, font remembers the failure and does not retry it.
+        for (glyph, font_index) in glyph_indices.iter().zip(self.font_indices.iter()) {
+            if let Some(glyph) = glyph {
+                let _ = fonts[*font_index].as_mut().unwrap().cache_glyph(*glyph);
+            }
+        }
+        // Ellipsis is always drawn using primary font, three dots are used if there is no
+        // special character for it.
+        let ellipsis = if self.ellipsis {
+            let primary = fonts[0].as_mut().unwrap();
+            let code = if primary.has_glyph(ELLIPSIS) { ELLIPSIS } else { u32::from(b'.') };
+            let _ = primary.cache_char(code);
+            if code == ELLIPSIS { vec![code] } else { vec![code; 3] }
+        } else {
+            Vec::new()
+        };
+        self.atlas_sizes = fonts
+            .iter()
+            .map(|font| font.as_ref().map_or(0, |font| font.get_atlas_size()))
+            .collect();
+
+        let font = fonts[0].as_ref().unwrap();
+        let ascender = size_scale * font.get_ascender();
+        let height = size_scale * font.get_height();
+
+        // Measure each character once, width of any part of a line is computed from these.
+        let mut advances = Vec::with_capacity(self.text.len());
+        let mut kernings = Vec::with_capacity(self.text.len());
+        let mut ascenders = Vec::with_capacity(self.text.len());
+        for (i, glyph) in glyph_indices.iter().enumerate() {
+            let font_index = self.font_indices[i];
+            let scale = styles[i].scale;
+            let char_font = fonts[font_index].as_ref().unwrap_or(font);
+            advances.push(match glyph {
+                Some(glyph) => char_font
+                    .get_glyph_by_index(*glyph)
+                    .map_or(scale * font.get_height(), |glyph| scale * glyph.get_advance()),
+                None => 0.0
+            });
+            // Kerning is applied only to characters of same font, size and direction, pair
+            // of glyphs is taken in visual order.
+            let same_run = i > 0
+                && !is_new_line(self.text[i - 1])
+                && self.font_indices[i - 1] == font_index
+                && styles[i - 1].scale == scale
+                && self.levels[i - 1] == self.levels[i];
+            kernings.push(match (glyph_indices.get(i.wrapping_sub(1)), glyph) {
+                (Some(Some(prev)), Some(glyph)) if same_run => {
+                    if self.levels[i] % 2 == 0 {
+                        scale * char_font.get_kerning_by_index(*prev, *glyph)
+                    } else {
+                        scale * char_font.get_kerning_by_index(*glyph, *prev)
+                    }
+                }
+                _ => 0.0
+            });
+            ascenders.push(scale * char_font.get_ascender());
+        }
+        let range_width = |range: Range<usize>| -> f32 {
+            if range.start >= range.end {
+                return 0.0;
+            }
+            advances[range.clone()].iter().sum::<f32>() + kernings[(range.start + 1)..range.end].iter().sum::<f32>()
         };
 
         // Split on lines.
-        let mut total_height = 0.0;
         let mut current_line = TextLine::new();
+        // Position at which new line can start if current line overflows.
+        let mut last_break = None;
         self.lines.clear();
         for (i, code) in self.text.iter().enumerate() {
-            let advance =
-                match font.get_glyph(*code) {
-                    Some(glyph) => glyph.get_advance(),
-                    None => font.get_height()
-                };
-            let is_new_line = *code == u32::from(b'\n') || *code == u32::from(b'\r');
-            let new_width = current_line.width + advance;
-            if self.wrap && new_width > self.constraint.x || is_new_line {
+            let advance = advances[i];
+            let new_width = current_line.width + kernings[i] + advance;
+            if is_new_line(*code) {
                 self.lines.push(current_line);
-                current_line.begin = if is_new_line { i + 1 } else { i };
+                current_line.begin = i + 1;
                 current_line.end = current_line.begin + 1;
                 current_line.width = advance;
-                total_height += font.get_ascender();
+                last_break = None;
+            } else if self.wrap != WrapMode::NoWrap && new_width > self.constraint.x {
+                match last_break {
+                    Some(position) if self.wrap == WrapMode::Word && position > current_line.begin => {
+                        // Move beginning of current word to new line.
+                        current_line.end = position;
+                        current_line.width = range_width(current_line.begin..position);
+                        self.lines.push(current_line);
+                        current_line.begin = position;
+                        current_line.end = i + 1;
+                        current_line.width = range_width(position..(i + 1));
+                    }
+                    _ => {
+                        self.lines.push(current_line);
+                        current_line.begin = i;
+                        current_line.end = current_line.begin + 1;
+                        current_line.width = advance;
+                    }
+                }
+                last_break = None;
             } else {
                 current_line.width = new_width;
                 current_line.end += 1;
             }
+            if let Some(next) = self.text.get(i + 1) {
+                if is_break_opportunity(*code, *next) {
+                    last_break = Some(i + 1);
+                }
+            }
         }
         // Commit rest of text.
         if current_line.begin != current_line.end {
             current_line.end = self.text.len();
             self.lines.push(current_line);
-            total_height += font.get_ascender();
         }
 
-        // Align lines according to desired alignment.
+        // Cut lines that do not fit in constraint, index of first hidden character is stored
+        // for each trimmed line.
+        let ellipsis_width = ellipsis
+            .iter()
+            .map(|code| font.get_glyph(*code).map_or(height, |glyph| size_scale * glyph.get_advance()))
+            .sum::<f32>();
+        let mut visible_ends = Vec::with_capacity(self.lines.len());
         for line in self.lines.iter_mut() {
+            let end = line.end.min(self.text.len());
+            if ellipsis.is_empty() || line.width <= self.constraint.x {
+                visible_ends.push(end);
+                continue;
+            }
+            let mut visible_end = line.begin;
+            let mut width = 0.0;
+            for i in line.begin..end {
+                let kerning = if i > line.begin { kernings[i] } else { 0.0 };
+                if width + kerning + advances[i] + ellipsis_width > self.constraint.x {
+                    break;
+                }
+                width += kerning + advances[i];
+                visible_end = i + 1;
+            }
+            line.width = width + ellipsis_width;
+            visible_ends.push(visible_end);
+        }
+
+        // Align lines according to desired alignment. Line is tall enough to fit biggest
+        // character in it, but not smaller than ascender of primary font.
+        let mut total_height = 0.0;
+        let mut line_ascenders = Vec::with_capacity(self.lines.len());
+        for (line, visible_end) in self.lines.iter_mut().zip(visible_ends.iter()) {
             match self.horizontal_alignment {
                 HorizontalAlignment::Left => line.x_offset = 0.0,
                 HorizontalAlignment::Center => {
@@ -252,10 +1077,17 @@ impl FormattedText {
                 },
                 HorizontalAlignment::Stretch => line.x_offset = 0.0
             }
+            let line_ascender = ascenders[line.begin.min(*visible_end)..*visible_end]
+                .iter()
+                .fold(ascender, |max, ascender| ascender.max(max));
+            line.height = self.line_spacing * line_ascender;
+            total_height += line.height;
+            line_ascenders.push(line_ascender);
         }
 
         // Generate glyphs for each text line.
         self.glyphs.clear();
+        self.decorations.clear();
 
         let cursor_y_start = match self.vertical_alignment {
             VerticalAlignment::Top => 0.0,
@@ -282,51 +1114,120 @@ impl FormattedText {
             self.constraint.x
         };
 
+        let mut underlines = Vec::new();
+        let mut strikethroughs = Vec::new();
+        self.char_positions.clear();
+        self.char_positions.resize(self.text.len(), 0.0);
         let mut cursor = Vec2::new(cursor_x_start, cursor_y_start);
-        for line in self.lines.iter_mut() {
+        for ((line, visible_end), line_ascender) in self.lines.iter_mut().zip(visible_ends).zip(line_ascenders) {
             cursor.x = line.x_offset;
+            // Glyphs of every font and size are placed on common baseline.
+            let baseline = cursor.y + line_ascender;
 
-            for code_index in line.begin..line.end {
-                let code = self.text[code_index];
+            let begin = line.begin.min(visible_end);
+            let base_level = self.base_levels.get(begin).cloned().unwrap_or(0);
+            let visual_order = bidi::reorder_line(&self.text[begin..visible_end], &self.levels[begin..visible_end], base_level);
+            for code_index in visual_order.into_iter().map(|i| begin + i) {
+                let font_index = self.font_indices[code_index];
+                let char_font = fonts[font_index].as_ref().unwrap_or(font);
+                let style = styles[code_index];
+                let scale = style.scale;
 
-                match font.get_glyph(code) {
-                    Some(glyph) => {
+                // Kerning is stored for logical pairs, logically previous character of
+                // right-to-left text is on the right side.
+                let char_x = cursor.x;
+                if self.levels[code_index] % 2 == 0 {
+                    if code_index > line.begin {
+                        cursor.x += kernings[code_index];
+                    }
+                } else if code_index + 1 < visible_end {
+                    cursor.x += kernings[code_index + 1];
+                }
+                self.char_positions[code_index] = cursor.x;
+
+                match glyph_indices[code_index].map(|glyph| char_font.get_glyph_by_index(glyph)) {
+                    // Character is part of ligature that is drawn by its first character.
+                    None => (),
+                    Some(Some(glyph)) => {
                         // Insert glyph
                         if glyph.has_outline() {
                             let rect = Rect {
-                                x: cursor.x + glyph.get_bitmap_left(),
-                                y: cursor.y + font.get_ascender() - glyph.get_bitmap_top() - glyph.get_bitmap_height(),
-                                w: glyph.get_bitmap_width(),
-                                h: glyph.get_bitmap_height(),
+                                x: cursor.x + scale * glyph.get_bitmap_left(),
+                                y: baseline - scale * (glyph.get_bitmap_top() + glyph.get_bitmap_height()),
+                                w: scale * glyph.get_bitmap_width(),
+                                h: scale * glyph.get_bitmap_height(),
                             };
                             let text_glyph = TextGlyph {
                                 bounds: rect,
                                 tex_coords: *glyph.get_tex_coords(),
+                                font_index,
+                                brush_index: style.brush_index,
                             };
                             self.glyphs.push(text_glyph);
                         }
-                        cursor.x += glyph.get_advance();
                     }
-                    None => {
+                    Some(None) => {
                         // Insert invalid symbol
                         let rect = Rect {
                             x: cursor.x,
-                            y: cursor.y + font.get_ascender(),
-                            w: font.get_height(),
-                            h: font.get_height(),
+                            y: baseline,
+                            w: advances[code_index],
+                            h: advances[code_index],
+                        };
+                        self.glyphs.push(TextGlyph {
+                            bounds: rect,
+                            tex_coords: [Vec2::ZERO; 4],
+                            font_index: 0,
+                            brush_index: style.brush_index,
+                        });
+                    }
+                }
+                cursor.x += advances[code_index];
+
+                if style.underline || style.strikethrough {
+                    let thickness = (ascenders[code_index] / 12.0).max(1.0);
+                    if style.underline {
+                        let bounds = Rect::new(char_x, baseline + thickness, cursor.x - char_x, thickness);
+                        push_decoration(&mut underlines, bounds, style.brush_index);
+                    }
+                    if style.strikethrough {
+                        let y = baseline - 0.35 * ascenders[code_index] - 0.5 * thickness;
+                        let bounds = Rect::new(char_x, y, cursor.x - char_x, thickness);
+                        push_decoration(&mut strikethroughs, bounds, style.brush_index);
+                    }
+                }
+            }
+            // Hidden characters are placed at the end of line.
+            for position in self.char_positions[visible_end..line.end.min(self.text.len())].iter_mut() {
+                *position = cursor.x;
+            }
+            if visible_end < line.end.min(self.text.len()) {
+                for code in ellipsis.iter() {
+                    if let Some(glyph) = font.get_glyph(*code) {
+                        let rect = Rect {
+                            x: cursor.x + size_scale * glyph.get_bitmap_left(),
+                            y: baseline - size_scale * (glyph.get_bitmap_top() + glyph.get_bitmap_height()),
+                            w: size_scale * glyph.get_bitmap_width(),
+                            h: size_scale * glyph.get_bitmap_height(),
                         };
                         self.glyphs.push(TextGlyph {
                             bounds: rect,
-                            tex_coords: [Vec2::ZERO; 4]
+                            tex_coords: *glyph.get_tex_coords(),
+                            font_index: 0,
+                            brush_index: None,
                         });
-                        cursor.x += rect.w;
+                        cursor.x += size_scale * glyph.get_advance();
                     }
                 }
             }
-            line.height = font.get_ascender();
             line.y_offset = cursor.y;
-            cursor.y += font.get_ascender();
+            cursor.y += line.height;
         }
+        self.decorations.extend(underlines);
+        self.decorations.extend(strikethroughs);
+        self.advances = advances;
+        self.kernings = kernings;
+        self.metrics_valid = true;
 
         let mut full_size = Vec2::new(0.0, total_height);
         for line in self.lines.iter() {
@@ -336,14 +1237,54 @@ impl FormattedText {
     }
 }
 
+/// Only text and its layout settings are visited, fonts and spans are not saved.
+impl<M: 'static, C: 'static + Control<M, C>> Visit<M, C> for FormattedText {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        visitor.visit_property("text", self.text(), |text| { self.set_text(text); })?;
+        visitor.visit("font_size", &mut self.font_size)?;
+        visitor.visit("vertical_alignment", &mut self.vertical_alignment)?;
+        visitor.visit("horizontal_alignment", &mut self.horizontal_alignment)?;
+        visitor.visit("wrap", &mut self.wrap)?;
+        visitor.visit("ellipsis", &mut self.ellipsis)?;
+        visitor.visit("line_spacing", &mut self.line_spacing)?;
+        visitor.visit("mask_char", &mut self.mask_char)?;
+        // Fonts of spans are not saved, loaded span uses font of text.
+        let mut count = self.spans.len();
+        visitor.visit_len("spans", &mut count, "start")?;
+        if visitor.is_reading() {
+            self.spans.resize_with(count, || TextSpan::new(0..0));
+        }
+        for (i, span) in self.spans.iter_mut().enumerate() {
+            visitor.enter_region(&format!("spans.{}", i));
+            visitor.visit("start", &mut span.range.start)?;
+            visitor.visit("end", &mut span.range.end)?;
+            visitor.visit("brush", &mut span.brush)?;
+            visitor.visit("font_size", &mut span.font_size)?;
+            visitor.visit("underline", &mut span.underline)?;
+            visitor.visit("strikethrough", &mut span.strikethrough)?;
+            visitor.leave_region();
+        }
+        if visitor.is_reading() {
+            self.metrics_valid = false;
+        }
+        Ok(())
+    }
+}
+
 pub struct FormattedTextBuilder {
     font: Option<Arc<Mutex<Font>>>,
+    fallback_fonts: Vec<Arc<Mutex<Font>>>,
     brush: Brush,
     constraint: Vec2,
     text: String,
     vertical_alignment: VerticalAlignment,
     horizontal_alignment: HorizontalAlignment,
-    wrap: bool,
+    wrap: WrapMode,
+    ellipsis: bool,
+    line_spacing: f32,
+    mask_char: Option<char>,
+    font_size: Option<f32>,
+    spans: Vec<TextSpan>,
 }
 
 impl Default for FormattedTextBuilder {
@@ -357,12 +1298,18 @@ impl FormattedTextBuilder {
     pub fn new() -> FormattedTextBuilder {
         FormattedTextBuilder {
             font: None,
+            fallback_fonts: Vec::new(),
             text: "".to_owned(),
             horizontal_alignment: HorizontalAlignment::Left,
             vertical_alignment: VerticalAlignment::Top,
             brush: Brush::Solid(Color::WHITE),
             constraint: Vec2::new(128.0, 128.0),
-            wrap: false,
+            wrap: WrapMode::NoWrap,
+            ellipsis: false,
+            line_spacing: 1.0,
+            mask_char: None,
+            font_size: None,
+            spans: Vec::new(),
         }
     }
 
@@ -371,16 +1318,35 @@ impl FormattedTextBuilder {
         self
     }
 
+    pub fn with_fallback_fonts(mut self, fonts: Vec<Arc<Mutex<Font>>>) -> Self {
+        self.fallback_fonts = fonts;
+        self
+    }
+
     pub fn with_vertical_alignment(mut self, vertical_alignment: VerticalAlignment) -> Self {
         self.vertical_alignment = vertical_alignment;
         self
     }
 
-    pub fn with_wrap(mut self, wrap: bool) -> Self {
+    pub fn with_wrap(self, wrap: bool) -> Self {
+        self.with_wrap_mode(if wrap { WrapMode::Character } else { WrapMode::NoWrap })
+    }
+
+    pub fn with_wrap_mode(mut self, wrap: WrapMode) -> Self {
         self.wrap = wrap;
         self
     }
 
+    pub fn with_ellipsis(mut self, ellipsis: bool) -> Self {
+        self.ellipsis = ellipsis;
+        self
+    }
+
+    pub fn with_line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = line_spacing;
+        self
+    }
+
     pub fn with_horizontal_alignment(mut self, horizontal_alignment: HorizontalAlignment) -> Self {
         self.horizontal_alignment = horizontal_alignment;
         self
@@ -401,17 +1367,412 @@ impl FormattedTextBuilder {
         self
     }
 
+    pub fn with_mask_char(mut self, mask_char: Option<char>) -> Self {
+        self.mask_char = mask_char;
+        self
+    }
+
+    pub fn with_font_size(mut self, font_size: Option<f32>) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    pub fn with_spans(mut self, spans: Vec<TextSpan>) -> Self {
+        self.spans = spans;
+        self
+    }
+
+    /// Sets text and spans from markup, see `FormattedText::set_markup`.
+    pub fn with_markup(mut self, markup: &str) -> Self {
+        let (text, spans) = parse_markup(markup);
+        self.text = text;
+        self.spans = spans;
+        self
+    }
+
     pub fn build(self) -> FormattedText {
         FormattedText {
             font: self.font,
+            fallback_fonts: self.fallback_fonts,
+            font_indices: Vec::new(),
             text: self.text.chars().map(|c| c as u32).collect(),
             lines: Vec::new(),
             glyphs: Vec::new(),
+            decorations: Vec::new(),
+            spans: self.spans,
+            levels: Vec::new(),
+            base_levels: Vec::new(),
+            advances: Vec::new(),
+            kernings: Vec::new(),
+            metrics_valid: false,
+            char_positions: Vec::new(),
             vertical_alignment: self.vertical_alignment,
             horizontal_alignment: self.horizontal_alignment,
             brush: self.brush,
             constraint: self.constraint,
             wrap: self.wrap,
+            ellipsis: self.ellipsis,
+            line_spacing: self.line_spacing,
+            mask_char: self.mask_char,
+            font_size: self.font_size,
+            atlas_sizes: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        formatted_text::{FormattedText, FormattedTextBuilder, WrapMode, ELLIPSIS},
+        draw::{DrawingContext, CommandKind, CommandTexture},
+        ttf::Font,
+        core::math::vec2::Vec2,
+    };
+    use std::sync::{Arc, Mutex};
+
+    fn built_in_font() -> Arc<Mutex<Font>> {
+        built_in_font_with_height(20.0)
+    }
+
+    /// Rebuilds built-in font with given tables, table with existing tag is replaced,
+    /// others are added to the end of font data.
+    fn patch_font(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let data = std::include_bytes!("built_in_font.ttf");
+        let num_tables = u16::from_be_bytes([data[4], data[5]]) as usize;
+        let mut entries = (0..num_tables)
+            .map(|i| {
+                let record = &data[(12 + i * 16)..(28 + i * 16)];
+                let offset = u32::from_be_bytes([record[8], record[9], record[10], record[11]]) as usize;
+                let length = u32::from_be_bytes([record[12], record[13], record[14], record[15]]) as usize;
+                ([record[0], record[1], record[2], record[3]], data[offset..(offset + length)].to_vec())
+            })
+            .collect::<Vec<_>>();
+        for (tag, table) in tables {
+            match entries.iter_mut().find(|(existing, _)| existing == *tag) {
+                Some(entry) => entry.1 = table.clone(),
+                None => entries.push((**tag, table.clone())),
+            }
+        }
+
+        // Checksums are not verified by parser, so they are left zero.
+        let mut font = data[..12].to_vec();
+        font[4..6].copy_from_slice(&(entries.len() as u16).to_be_bytes());
+        let mut bodies = Vec::new();
+        for (tag, table) in entries.iter() {
+            let offset = (12 + 16 * entries.len() + bodies.len()) as u32;
+            font.extend_from_slice(tag);
+            font.extend_from_slice(&[0; 4]);
+            font.extend_from_slice(&offset.to_be_bytes());
+            font.extend_from_slice(&(table.len() as u32).to_be_bytes());
+            bodies.extend_from_slice(table);
+            bodies.resize((bodies.len() + 3) & !3, 0);
+        }
+        font.extend_from_slice(&bodies);
+        font
+    }
+
+    fn built_in_font_glyph_indices(chars: &[char]) -> Vec<u16> {
+        let base = Font::from_memory(std::include_bytes!("built_in_font.ttf").to_vec(), 20.0, Font::default_char_set()).unwrap();
+        chars.iter().map(|c| base.glyph_index(*c as u32) as u16).collect()
+    }
+
+    /// Built-in font has no `kern` table, so one with given pairs (in font units) is added.
+    fn font_with_kerning(pairs: &[(char, char, i16)]) -> Arc<Mutex<Font>> {
+        let mut glyph_pairs = pairs.iter()
+            .map(|(left, right, value)| {
+                let glyphs = built_in_font_glyph_indices(&[*left, *right]);
+                (glyphs[0], glyphs[1], *value)
+            })
+            .collect::<Vec<_>>();
+        glyph_pairs.sort();
+
+        let mut kern = Vec::new();
+        // Version 0 table header with one format 0 horizontal subtable.
+        kern.extend_from_slice(&[0, 0, 0, 1]);
+        kern.extend_from_slice(&0u16.to_be_bytes());
+        kern.extend_from_slice(&(14 + 6 * glyph_pairs.len() as u16).to_be_bytes());
+        kern.extend_from_slice(&1u16.to_be_bytes());
+        kern.extend_from_slice(&(glyph_pairs.len() as u16).to_be_bytes());
+        kern.extend_from_slice(&[0; 6]);
+        for (left, right, value) in glyph_pairs {
+            kern.extend_from_slice(&left.to_be_bytes());
+            kern.extend_from_slice(&right.to_be_bytes());
+            kern.extend_from_slice(&value.to_be_bytes());
+        }
+
+        let font = patch_font(&[(b"kern", kern)]);
+        Arc::new(Mutex::new(Font::from_memory(font, 20.0, Font::default_char_set()).unwrap()))
+    }
+
+    /// Built-in font which maps only given characters, every other character has no glyph
+    /// in it. Characters are mapped by format 4 `cmap` subtable with one segment per character.
+    fn font_with_chars(chars: &str, height: f32) -> Arc<Mutex<Font>> {
+        let mut chars = chars.chars().collect::<Vec<_>>();
+        chars.sort();
+        chars.dedup();
+        let glyphs = built_in_font_glyph_indices(&chars);
+        let mut segments = chars.iter()
+            .zip(glyphs)
+            .map(|(c, glyph)| (*c as u16, glyph.wrapping_sub(*c as u16)))
+            .collect::<Vec<_>>();
+        // Last segment must end with 0xFFFF.
+        segments.push((0xFFFF, 1));
+
+        let mut subtable = Vec::new();
+        subtable.extend_from_slice(&4u16.to_be_bytes());
+        subtable.extend_from_slice(&(16 + 8 * segments.len() as u16).to_be_bytes());
+        subtable.extend_from_slice(&0u16.to_be_bytes());
+        subtable.extend_from_slice(&(2 * segments.len() as u16).to_be_bytes());
+        // Search hints are not used by parser.
+        subtable.extend_from_slice(&[0; 6]);
+        for (code, _) in segments.iter() {
+            subtable.extend_from_slice(&code.to_be_bytes());
+        }
+        subtable.extend_from_slice(&0u16.to_be_bytes());
+        for (code, _) in segments.iter() {
+            subtable.extend_from_slice(&code.to_be_bytes());
+        }
+        for (_, delta) in segments.iter() {
+            subtable.extend_from_slice(&delta.to_be_bytes());
+        }
+        for _ in segments.iter() {
+            subtable.extend_from_slice(&0u16.to_be_bytes());
+        }
+
+        // Single Windows Unicode BMP encoding record.
+        let mut cmap = vec![0, 0, 0, 1, 0, 3, 0, 1];
+        cmap.extend_from_slice(&12u32.to_be_bytes());
+        cmap.extend_from_slice(&subtable);
+
+        let font = patch_font(&[(b"cmap", cmap)]);
+        Arc::new(Mutex::new(Font::from_memory(font, height, Font::default_char_set()).unwrap()))
+    }
+
+    fn built_in_font_with_height(height: f32) -> Arc<Mutex<Font>> {
+        let font_bytes = std::include_bytes!("built_in_font.ttf").to_vec();
+        Arc::new(Mutex::new(Font::from_memory(font_bytes, height, Font::default_char_set()).unwrap()))
+    }
+
+    fn text_with_fallback(text: &str, primary: Arc<Mutex<Font>>, fallback: Arc<Mutex<Font>>) -> FormattedText {
+        let mut text = FormattedTextBuilder::new()
+            .with_font(primary)
+            .with_fallback_fonts(vec![fallback])
+            .with_text(text.to_owned())
+            .with_constraint(Vec2::new(std::f32::INFINITY, std::f32::INFINITY))
+            .build();
+        text.build();
+        text
+    }
+
+    fn make_text(text: &str) -> FormattedText {
+        FormattedTextBuilder::new()
+            .with_font(built_in_font())
+            .with_text(text.to_owned())
+            .with_constraint(Vec2::new(std::f32::INFINITY, std::f32::INFINITY))
+            .build()
+    }
+
+    fn line_strings(text: &FormattedText) -> Vec<String> {
+        text.get_lines()
+            .iter()
+            .map(|line| text.get_raw_text()[line.begin..line.end]
+                .iter()
+                .filter_map(|c| std::char::from_u32(*c))
+                .collect())
+            .collect()
+    }
+
+    #[test]
+    fn word_wrap_breaks_at_word_boundary() {
+        let mut text = make_text("hello world");
+        text.build();
+        let width = text.get_range_width(0.."hello wor".len()) + 0.1;
+        text.set_constraint(Vec2::new(width, std::f32::INFINITY));
+
+        text.set_wrap_mode(WrapMode::Character);
+        text.build();
+        assert_eq!(line_strings(&text), vec!["hello wor", "ld"]);
+
+        text.set_wrap_mode(WrapMode::Word);
+        text.build();
+        assert_eq!(line_strings(&text), vec!["hello ", "world"]);
+
+        text.set_wrap_mode(WrapMode::NoWrap);
+        text.build();
+        assert_eq!(line_strings(&text), vec!["hello world"]);
+    }
+
+    #[test]
+    fn word_wider_than_constraint_is_wrapped_by_characters() {
+        let mut text = make_text("aaaaaaaaaa");
+        text.build();
+        let width = text.get_range_width(0..4) + 0.1;
+        text.set_constraint(Vec2::new(width, std::f32::INFINITY))
+            .set_wrap_mode(WrapMode::Word);
+        text.build();
+        assert_eq!(line_strings(&text), vec!["aaaa", "aaaa", "aa"]);
+    }
+
+    #[test]
+    fn wrap_flag_maps_to_character_wrapping() {
+        let mut text = make_text("a");
+        assert!(!text.is_wrap());
+        text.set_wrap(true);
+        assert_eq!(text.wrap_mode(), WrapMode::Character);
+        assert!(text.is_wrap());
+        text.set_wrap_mode(WrapMode::Word);
+        assert!(text.is_wrap());
+        text.set_wrap(false);
+        assert_eq!(text.wrap_mode(), WrapMode::NoWrap);
+    }
+
+    #[test]
+    fn ellipsis_trims_overflowing_line() {
+        let mut text = make_text("aaaaaaaaaa");
+        text.set_ellipsis(true);
+        text.build();
+        // Nothing is trimmed without width constraint.
+        let full_glyphs = text.get_glyphs().len();
+        assert_eq!(full_glyphs, 10);
+
+        let (ellipsis_width, ellipsis_glyphs) = {
+            let font = text.get_font().unwrap();
+            let font = font.lock().unwrap();
+            let codes = if font.has_glyph(ELLIPSIS) { vec![ELLIPSIS] } else { vec![u32::from(b'.'); 3] };
+            let width = codes.iter()
+                .map(|code| font.get_glyph(*code).map_or(font.get_height(), |glyph| glyph.get_advance()))
+                .sum::<f32>();
+            (width, codes.iter().filter(|code| font.get_glyph(**code).is_some()).count())
+        };
+        let visible_width = text.get_range_width(0..4);
+        let constraint = visible_width + ellipsis_width + 0.1;
+        text.set_constraint(Vec2::new(constraint, std::f32::INFINITY));
+        text.build();
+
+        let lines = text.get_lines();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].width <= constraint);
+        assert!((lines[0].width - (visible_width + ellipsis_width)).abs() < 0.01);
+        assert_eq!(text.get_glyphs().len(), 4 + ellipsis_glyphs);
+        // Hidden characters are placed at the end of visible part of line.
+        for i in 5..10 {
+            assert_eq!(text.get_char_x(i), text.get_char_x(4));
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn line_spacing_scales_line_height() {
+        let mut text = make_text("a\nb");
+        let size = text.build();
+        let lines = text.get_lines().to_vec();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].y_offset, lines[0].y_offset + lines[0].height);
+
+        text.set_line_spacing(2.0);
+        let spaced_size = text.build();
+        let spaced_lines = text.get_lines();
+        for (line, spaced_line) in lines.iter().zip(spaced_lines.iter()) {
+            assert!((spaced_line.height - 2.0 * line.height).abs() < 0.001);
+        }
+        assert!((spaced_lines[1].y_offset - spaced_lines[0].y_offset - spaced_lines[0].height).abs() < 0.001);
+        assert!((spaced_size.y - 2.0 * size.y).abs() < 0.001);
+    }
+
+    #[test]
+    fn kerning_is_applied() {
+        let font = font_with_kerning(&[('A', 'V', -200)]);
+        let (kerning, advance_a, advance_v) = {
+            let font = font.lock().unwrap();
+            (font.get_kerning('A' as u32, 'V' as u32), font.get_glyph_advance('A' as u32), font.get_glyph_advance('V' as u32))
+        };
+        assert!(kerning < 0.0);
+        assert_eq!(font.lock().unwrap().get_kerning('V' as u32, 'A' as u32), 0.0);
+
+        let mut text = FormattedTextBuilder::new()
+            .with_font(font)
+            .with_text("AVA".to_owned())
+            .with_constraint(Vec2::new(std::f32::INFINITY, std::f32::INFINITY))
+            .build();
+
+        // Width is same before and after build, with and without cached metrics.
+        let expected = 2.0 * advance_a + advance_v + kerning;
+        assert!((text.get_range_width(0..3) - expected).abs() < 0.001);
+        text.build();
+        assert!((text.get_range_width(0..3) - expected).abs() < 0.001);
+        assert!((text.get_range_width(0..2) - (advance_a + advance_v + kerning)).abs() < 0.001);
+        assert!((text.get_range_width(1..3) - (advance_a + advance_v)).abs() < 0.001);
+        assert!((text.get_lines()[0].width - expected).abs() < 0.001);
+
+        // Second character is moved towards first one.
+        assert!((text.get_char_x(1) - (text.get_char_x(0) + advance_a + kerning)).abs() < 0.001);
+        assert!((text.get_char_x(2) - (text.get_char_x(1) + advance_v)).abs() < 0.001);
+    }
+
+    #[test]
+    fn missing_glyphs_are_taken_from_fallback_font() {
+        let primary = font_with_chars("0123456789 ", 20.0);
+        assert!(primary.lock().unwrap().has_glyph(u32::from(b'1')));
+        assert!(!primary.lock().unwrap().has_glyph(u32::from(b'A')));
+
+        // Character which is missing in every font stays with primary font.
+        let text = text_with_fallback("1A 2\u{4E2D}", primary, built_in_font());
+        let font_indices = (0..5).map(|i| text.font_index_at(i)).collect::<Vec<_>>();
+        assert_eq!(font_indices, vec![0, 1, 0, 0, 0]);
+        assert_eq!(text.get_glyphs().iter().filter(|glyph| glyph.get_font_index() == 1).count(), 1);
+    }
+
+    #[test]
+    fn glyphs_of_all_fonts_share_baseline() {
+        let primary = font_with_chars("0123456789", 20.0);
+        let fallback = built_in_font_with_height(40.0);
+        let text = text_with_fallback("1A", primary.clone(), fallback.clone());
+
+        let glyph_baseline = |index: usize, font: &Arc<Mutex<Font>>, code: char| {
+            let font = font.lock().unwrap();
+            let metrics = font.get_glyph(code as u32).unwrap();
+            let bounds = text.get_glyphs()[index].get_bounds();
+            bounds.y + metrics.get_bitmap_top() + metrics.get_bitmap_height()
+        };
+        let digit_baseline = glyph_baseline(0, &primary, '1');
+        let letter_baseline = glyph_baseline(1, &fallback, 'A');
+        assert!((digit_baseline - letter_baseline).abs() < 0.001);
+
+        // Line is tall enough for bigger fallback font.
+        let fallback_ascender = fallback.lock().unwrap().get_ascender();
+        assert!(fallback_ascender > primary.lock().unwrap().get_ascender());
+        assert!((digit_baseline - fallback_ascender).abs() < 0.001);
+        assert!((text.get_lines()[0].height - fallback_ascender).abs() < 0.001);
+    }
+
+    #[test]
+    fn draw_text_emits_command_per_font() {
+        let primary = font_with_chars("0123456789", 20.0);
+        let fallback = built_in_font();
+
+        let command_fonts = |text: &FormattedText| {
+            let mut context = DrawingContext::new();
+            context.draw_text(Vec2::ZERO, text);
+            context.get_commands()
+                .iter()
+                .map(|command| {
+                    assert_eq!(*command.get_kind(), CommandKind::Geometry);
+                    match command.texture() {
+                        CommandTexture::Font { font, .. } => font.clone(),
+                        _ => panic!("text must be drawn with font texture"),
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Glyphs of same font are batched together regardless of their order.
+        let fonts = command_fonts(&text_with_fallback("1A1A", primary.clone(), fallback.clone()));
+        assert_eq!(fonts.len(), 2);
+        assert!(Arc::ptr_eq(&fonts[0], &primary));
+        assert!(Arc::ptr_eq(&fonts[1], &fallback));
+
+        // Unused fallback font produces no command.
+        let fonts = command_fonts(&text_with_fallback("11", primary.clone(), fallback));
+        assert_eq!(fonts.len(), 1);
+        assert!(Arc::ptr_eq(&fonts[0], &primary));
+    }
+}
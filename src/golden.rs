@@ -0,0 +1,169 @@
+// Golden-image testing of layouts. UI is updated with fixed screen size, rendered by
+// software renderer and compared with PNG image stored in `goldens` directory of the crate.
+// Goldens are committed together with tests, missing golden is a failure. Set
+// `UPDATE_GOLDENS=1` environment variable to create new goldens or to overwrite existing ones
+// after intended visual changes. When comparison fails, actual image and diff image are
+// written to `target/golden-diffs`.
+
+use crate::{
+    UserInterface,
+    Control,
+    software_renderer::{SoftwareRenderer, RgbaImage},
+    core::{
+        color::Color,
+        math::vec2::Vec2,
+    },
+};
+use std::{
+    fs::{self, File},
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+pub struct GoldenTest {
+    name: String,
+    width: usize,
+    height: usize,
+    channel_tolerance: u8,
+    max_mismatched_pixels: usize,
+    clear_color: Color,
+}
+
+impl GoldenTest {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            width: 256,
+            height: 256,
+            channel_tolerance: 2,
+            max_mismatched_pixels: 0,
+            clear_color: Color::opaque(0, 0, 0),
+        }
+    }
+
+    pub fn with_size(mut self, width: usize, height: usize) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    /// Sets max difference of a color channel at which pixels are still considered equal.
+    pub fn with_channel_tolerance(mut self, tolerance: u8) -> Self {
+        self.channel_tolerance = tolerance;
+        self
+    }
+
+    /// Sets amount of pixels that are allowed to differ.
+    pub fn with_max_mismatched_pixels(mut self, count: usize) -> Self {
+        self.max_mismatched_pixels = count;
+        self
+    }
+
+    pub fn with_clear_color(mut self, color: Color) -> Self {
+        self.clear_color = color;
+        self
+    }
+
+    /// Updates layout of UI with screen size of the test and renders it.
+    pub fn render<M, C: 'static + Control<M, C>>(&self, ui: &mut UserInterface<M, C>) -> RgbaImage {
+        ui.update(Vec2::new(self.width as f32, self.height as f32), 0.0);
+        let mut renderer = SoftwareRenderer::new(self.width, self.height);
+        renderer.set_clear_color(self.clear_color);
+        renderer.render(ui.draw()).clone()
+    }
+
+    /// Renders UI and compares it with golden image, panics if they differ.
+    pub fn assert<M, C: 'static + Control<M, C>>(&self, ui: &mut UserInterface<M, C>) {
+        let image = self.render(ui);
+        self.assert_image(&image);
+    }
+
+    pub fn assert_image(&self, image: &RgbaImage) {
+        let golden_path = goldens_dir().join(format!("{}.png", self.name));
+
+        if std::env::var_os("UPDATE_GOLDENS").is_some() {
+            save_png(&golden_path, image);
+            return;
+        }
+
+        if !golden_path.exists() {
+            let actual_path = self.write_failure(image, None);
+            panic!("golden {:?} is missing, actual image is written to {:?}, run tests with UPDATE_GOLDENS=1 to create it",
+                   golden_path, actual_path);
+        }
+
+        let golden = load_png(&golden_path);
+        if golden.width() != image.width() || golden.height() != image.height() {
+            let actual_path = self.write_failure(image, None);
+            panic!("golden {:?} is {}x{}, but rendered image is {}x{}, actual image is written to {:?}",
+                   golden_path, golden.width(), golden.height(), image.width(), image.height(), actual_path);
+        }
+
+        let (mismatched, diff) = compare(&golden, image, self.channel_tolerance);
+        if mismatched > self.max_mismatched_pixels {
+            let actual_path = self.write_failure(image, Some(&diff));
+            panic!("{} pixels of {:?} differ from golden (max {} allowed), see {:?} and diff next to it",
+                   mismatched, golden_path, self.max_mismatched_pixels, actual_path);
+        }
+    }
+
+    fn write_failure(&self, image: &RgbaImage, diff: Option<&RgbaImage>) -> PathBuf {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("target").join("golden-diffs");
+        let actual_path = dir.join(format!("{}.actual.png", self.name));
+        save_png(&actual_path, image);
+        if let Some(diff) = diff {
+            save_png(&dir.join(format!("{}.diff.png", self.name)), diff);
+        }
+        actual_path
+    }
+}
+
+fn goldens_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("goldens")
+}
+
+/// Returns amount of mismatched pixels and diff image, where mismatched pixels are red and
+/// others are dimmed pixels of expected image.
+fn compare(expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> (usize, RgbaImage) {
+    let mut diff = RgbaImage::new(expected.width(), expected.height());
+    let mut mismatched = 0;
+    let pixels = expected.pixels().chunks_exact(4).zip(actual.pixels().chunks_exact(4));
+    for ((a, b), out) in pixels.zip(diff.pixels_mut().chunks_exact_mut(4)) {
+        let differs = a.iter()
+            .zip(b.iter())
+            .any(|(a, b)| (i16::from(*a) - i16::from(*b)).abs() > i16::from(tolerance));
+        if differs {
+            mismatched += 1;
+            out.copy_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let luminance = ((u16::from(a[0]) + u16::from(a[1]) + u16::from(a[2])) / 12) as u8;
+            out.copy_from_slice(&[luminance, luminance, luminance, 255]);
+        }
+    }
+    (mismatched, diff)
+}
+
+fn load_png(path: &Path) -> RgbaImage {
+    let file = File::open(path).unwrap_or_else(|e| panic!("unable to open {:?}: {}", path, e));
+    let decoder = png::Decoder::new(file);
+    let (info, mut reader) = decoder.read_info().unwrap_or_else(|e| panic!("unable to decode {:?}: {}", path, e));
+    assert!(info.color_type == png::ColorType::RGBA && info.bit_depth == png::BitDepth::Eight,
+            "golden {:?} must be 8-bit RGBA image", path);
+    let mut pixels = vec![0; info.buffer_size()];
+    reader.next_frame(&mut pixels).unwrap_or_else(|e| panic!("unable to decode {:?}: {}", path, e));
+    RgbaImage::from_pixels(info.width as usize, info.height as usize, pixels)
+        .unwrap_or_else(|| panic!("golden {:?} has invalid size", path))
+}
+
+fn save_png(path: &Path, image: &RgbaImage) {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).unwrap_or_else(|e| panic!("unable to create {:?}: {}", dir, e));
+    }
+    let file = File::create(path).unwrap_or_else(|e| panic!("unable to create {:?}: {}", path, e));
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width() as u32, image.height() as u32);
+    encoder.set_color(png::ColorType::RGBA);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.write_header()
+        .and_then(|mut writer| writer.write_image_data(image.pixels()))
+        .unwrap_or_else(|e| panic!("unable to write {:?}: {}", path, e));
+}
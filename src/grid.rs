@@ -21,7 +21,8 @@ use crate::{
         CommandKind,
         CommandTexture
     },
-    message::UiMessage
+    message::UiMessage,
+    serialization::{Visit, Visitor, VisitResult, FieldValue},
 };
 
 #[derive(Clone, Copy, PartialEq)]
@@ -123,6 +124,45 @@ impl Row {
     }
 }
 
+fn size_to_text(size_mode: SizeMode, size: f32) -> String {
+    match size_mode {
+        SizeMode::Strict => format!("strict {}", size),
+        SizeMode::Stretch => "stretch".to_owned(),
+        SizeMode::Auto => "auto".to_owned(),
+    }
+}
+
+fn size_from_text(text: &str) -> Option<(SizeMode, f32)> {
+    match text {
+        "stretch" => Some((SizeMode::Stretch, 0.0)),
+        "auto" => Some((SizeMode::Auto, 0.0)),
+        _ if text.starts_with("strict ") => text["strict ".len()..].parse().ok().map(|size| (SizeMode::Strict, size)),
+        _ => None,
+    }
+}
+
+/// Written as "strict <width>", "stretch" or "auto".
+impl FieldValue for Column {
+    fn to_text(&self) -> String {
+        size_to_text(self.size_mode, self.desired_width)
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        size_from_text(text).map(|(size_mode, size)| Column::generic(size_mode, size))
+    }
+}
+
+/// Written as "strict <height>", "stretch" or "auto".
+impl FieldValue for Row {
+    fn to_text(&self) -> String {
+        size_to_text(self.size_mode, self.desired_height)
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        size_from_text(text).map(|(size_mode, size)| Row::generic(size_mode, size))
+    }
+}
+
 /// Automatically arranges children by rows and columns
 pub struct Grid<M: 'static, C: 'static + Control<M, C>> {
     widget: Widget<M, C>,
@@ -262,6 +302,16 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for Grid<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for Grid<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit_vec("rows", self.rows.get_mut())?;
+        visitor.visit_vec("columns", self.columns.get_mut())?;
+        visitor.visit("draw_border", &mut self.draw_border)?;
+        visitor.visit("border_thickness", &mut self.border_thickness)
+    }
+}
+
 pub struct GridBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     rows: Vec<Row>,
@@ -506,3 +556,94 @@ impl<M, C: 'static + Control<M, C>> Grid<M, C> {
         self.border_thickness
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        grid::{GridBuilder, Row, Column},
+        border::BorderBuilder,
+        widget::WidgetBuilder,
+        golden::GoldenTest,
+        test::{StubUiMessage, StubUiNode},
+        brush::Brush,
+        Thickness,
+        UserInterface,
+        HorizontalAlignment,
+        VerticalAlignment,
+        node::UINode,
+        core::{
+            color::Color,
+            pool::Handle,
+        },
+    };
+
+    type Ui = UserInterface<StubUiMessage, StubUiNode>;
+
+    fn cell(ui: &mut Ui, row: usize, column: usize, color: Color) -> Handle<UINode<StubUiMessage, StubUiNode>> {
+        BorderBuilder::new(WidgetBuilder::new()
+            .on_row(row)
+            .on_column(column)
+            .with_margin(Thickness::uniform(2.0))
+            .with_background(Brush::Solid(color))
+            .with_foreground(Brush::Solid(Color::WHITE)))
+            .build(ui)
+    }
+
+    #[test]
+    fn grid_strict_and_stretch() {
+        let mut ui = Ui::new();
+        let children = [
+            cell(&mut ui, 0, 0, Color::opaque(200, 0, 0)),
+            cell(&mut ui, 0, 1, Color::opaque(0, 200, 0)),
+            cell(&mut ui, 1, 0, Color::opaque(0, 0, 200)),
+            cell(&mut ui, 1, 1, Color::opaque(200, 200, 0)),
+            cell(&mut ui, 2, 2, Color::opaque(0, 200, 200)),
+        ];
+        GridBuilder::new(WidgetBuilder::new()
+            .with_width(256.0)
+            .with_height(256.0)
+            .with_children(&children))
+            .add_row(Row::strict(40.0))
+            .add_row(Row::stretch())
+            .add_row(Row::strict(60.0))
+            .add_column(Column::strict(80.0))
+            .add_column(Column::stretch())
+            .add_column(Column::stretch())
+            .build(&mut ui);
+
+        GoldenTest::new("grid_strict_and_stretch").assert(&mut ui);
+    }
+
+    #[test]
+    fn grid_auto_with_alignment() {
+        let mut ui = Ui::new();
+        let small = BorderBuilder::new(WidgetBuilder::new()
+            .on_row(0)
+            .on_column(0)
+            .with_width(30.0)
+            .with_height(20.0)
+            .with_horizontal_alignment(HorizontalAlignment::Right)
+            .with_vertical_alignment(VerticalAlignment::Bottom)
+            .with_background(Brush::Solid(Color::opaque(200, 0, 200))))
+            .build(&mut ui);
+        let wide = BorderBuilder::new(WidgetBuilder::new()
+            .on_row(1)
+            .on_column(0)
+            .with_width(100.0)
+            .with_height(50.0)
+            .with_background(Brush::Solid(Color::opaque(0, 150, 250))))
+            .build(&mut ui);
+        let rest = cell(&mut ui, 1, 1, Color::opaque(250, 150, 0));
+        GridBuilder::new(WidgetBuilder::new()
+            .with_width(256.0)
+            .with_height(256.0)
+            .with_children(&[small, wide, rest]))
+            .add_row(Row::stretch())
+            .add_row(Row::auto())
+            .add_column(Column::auto())
+            .add_column(Column::stretch())
+            .build(&mut ui);
+
+        GoldenTest::new("grid_auto_with_alignment").assert(&mut ui);
+    }
+}
@@ -20,6 +20,7 @@ use crate::{
     draw::{Texture, CommandTexture},
     UserInterface,
     message::UiMessage,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 pub struct Image<M: 'static, C: 'static + Control<M, C>> {
@@ -76,6 +77,12 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for Image<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for Image<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)
+    }
+}
+
 pub struct ImageBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     texture: Option<Arc<Texture>>,
@@ -22,7 +22,8 @@ use crate::{
     },
     brush::Brush,
     NodeHandleMapping,
-    draw::{DrawingContext, CommandTexture, CommandKind}
+    draw::{DrawingContext, CommandTexture, CommandKind},
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 pub struct ItemsControl<M: 'static, C: 'static + Control<M, C>> {
@@ -52,7 +53,7 @@ impl<M, C: 'static + Control<M, C>> ItemsControl<M, C> {
         if old_value.is_none() && new_index.is_some() ||
             old_value.is_some() && new_index.is_none() ||
             old_value.unwrap() != new_index.unwrap() {
-            self.widget.post_message(UiMessage::new(
+            self.widget.post_message(UiMessage::broadcast(
                     UiMessageData::ItemsControl(
                         ItemsControlMessage::SelectionChanged(self.selected_index))))
         }
@@ -108,13 +109,11 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for ItemContainer<M, C> {
         });
 
         if let UiMessageData::Widget(msg) = &message.data {
-            if message.source == self_handle || self.widget().has_descendant(message.source, ui) {
-                if let WidgetMessage::MouseUp { .. } = msg {
-                    // Explicitly set selection on parent items control. This will send
-                    // SelectionChanged message and all items will react.
-                    if let UINode::ItemsControl(items_control) = ui.node_mut(items_control) {
-                        items_control.set_selected(Some(self.index));
-                    }
+            if let WidgetMessage::MouseUp { .. } = msg {
+                // Explicitly set selection on parent items control. This will send
+                // SelectionChanged message and all items will react.
+                if let UINode::ItemsControl(items_control) = ui.node_mut(items_control) {
+                    items_control.set_selected(Some(self.index));
                 }
             }
         }
@@ -179,6 +178,32 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for ItemsControl<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for ItemsControl<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit("selected_index", &mut self.selected_index)?;
+        visitor.visit_handles("item_containers", &mut self.item_containers)?;
+        visitor.visit_handle("panel", &mut self.panel)?;
+        visitor.visit_handles("items", &mut self.items)
+    }
+}
+
+impl<M, C: 'static + Control<M, C>> Default for ItemContainer<M, C> {
+    fn default() -> Self {
+        Self {
+            widget: Widget::default(),
+            index: 0,
+        }
+    }
+}
+
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for ItemContainer<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit("index", &mut self.index)
+    }
+}
+
 pub struct ItemsControlBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     items: Vec<Handle<UINode<M, C>>>,
@@ -30,6 +30,9 @@ pub mod text_box;
 pub mod check_box;
 pub mod tab_control;
 pub mod ttf;
+mod cff;
+mod bidi;
+mod shaping;
 pub mod brush;
 pub mod node;
 pub mod popup;
@@ -37,6 +40,15 @@ pub mod combobox;
 pub mod items_control;
 pub mod decorator;
 pub mod progress_bar;
+pub mod shortcut;
+pub mod clipboard;
+pub mod software_renderer;
+pub mod test_driver;
+pub mod recording;
+pub mod markup;
+pub mod serialization;
+#[cfg(test)]
+mod golden;
 
 use std::{
     collections::VecDeque,
@@ -66,14 +78,32 @@ use crate::{
     message::{
         OsEvent,
         ButtonState,
+        KeyCode,
+        NavigationInput,
+        KeyboardModifiers,
         UiMessage,
         UiMessageData,
         WidgetMessage,
-        WidgetProperty
+        WidgetProperty,
+        RoutingStrategy,
+        PopupMessage,
+        WindowMessage,
     },
     brush::Brush,
     draw::CommandTexture,
     node::UINode,
+    shortcut::{
+        ShortcutRegistry,
+        KeyChord,
+    },
+    clipboard::{
+        Clipboard,
+        MemoryClipboard,
+    },
+    recording::{
+        InputRecording,
+        RecordedEvent,
+    },
 };
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -92,6 +122,20 @@ pub enum VerticalAlignment {
     Bottom,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FocusDirection {
+    Forward,
+    Backward,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum NavigationDirection {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Thickness {
     pub left: f32,
@@ -338,6 +382,12 @@ pub trait Control<M: 'static, C: 'static + Control<M, C>> {
 
     /// Performs event-specific actions. Must call widget_mut().handle_message()!
     ///
+    /// Messages are routed, so with `RoutingStrategy::BubbleUp` this method is called only
+    /// if the node is the destination of the message or one of its ancestors. There is no
+    /// need to check if message came from some descendant node - it is guaranteed by the
+    /// dispatcher. Set `message.handled` to stop propagation of a message further up on
+    /// the tree.
+    ///
     /// # Notes
     ///
     /// Do *not* try to borrow node by `self_handle` in UI - at this moment node has been moved
@@ -345,6 +395,14 @@ pub trait Control<M: 'static, C: 'static + Control<M, C>> {
     /// to check if event came from/for this node or to capture input on node.
     fn handle_message(&mut self, self_handle: Handle<UINode<M, C>>, ui: &mut UserInterface<M, C>, message: &mut UiMessage<M, C>);
 
+    /// Called on tunneling phase of message routing, before any `handle_message`. Nodes are
+    /// visited from root down to the destination of the message, so parent nodes have a
+    /// chance to intercept a message before it reaches its destination. Set `message.handled`
+    /// to prevent message from going further.
+    ///
+    /// Same restrictions as for `handle_message` are applied to `self_handle`.
+    fn preview_message(&mut self, _self_handle: Handle<UINode<M, C>>, _ui: &mut UserInterface<M, C>, _message: &mut UiMessage<M, C>) {}
+
     /// Provides a way to respond to OS specific events. Can be useful to detect if a key or mouse
     /// button was pressed. This method significantly differs from `handle_message` because os events
     /// are not dispatched - they'll be passed to this method in any case.
@@ -353,6 +411,12 @@ pub trait Control<M: 'static, C: 'static + Control<M, C>> {
     /// Called when a node is deleted from container thus giving a chance to remove dangling
     /// handles which may cause panic.
     fn remove_ref(&mut self, _handle: Handle<UINode<M, C>>) {}
+
+    /// Returns screen-space rectangle of text caret if node accepts text input. It is used
+    /// by host application to place candidate window of input method next to caret.
+    fn caret_rect(&self) -> Option<Rect<f32>> {
+        None
+    }
 }
 
 pub struct UserInterface<M: 'static, C: 'static + Control<M, C>> {
@@ -365,10 +429,22 @@ pub struct UserInterface<M: 'static, C: 'static + Control<M, C>> {
     prev_picked_node: Handle<UINode<M, C>>,
     captured_node: Handle<UINode<M, C>>,
     keyboard_focus_node: Handle<UINode<M, C>>,
+    keyboard_modifiers: KeyboardModifiers,
+    shortcuts: ShortcutRegistry<M, C>,
+    clipboard: Box<dyn Clipboard>,
+    spatial_navigation: bool,
     cursor_position: Vec2,
     messages: VecDeque<UiMessage<M, C>>,
     stack: Vec<Handle<UINode<M, C>>>,
+    route: Vec<Handle<UINode<M, C>>>,
     root_picking_node: Handle<UINode<M, C>>,
+    /// Keys whose press was consumed by UI itself (shortcuts and focus navigation), their
+    /// release is consumed too, so focused node does not get unmatched KeyUp.
+    consumed_keys: Vec<KeyCode>,
+    /// Focus scope that was opened (popup or window) and will get focus on next update,
+    /// when its visibility is known.
+    opened_focus_scope: Handle<UINode<M, C>>,
+    recording: Option<InputRecording>,
 }
 
 lazy_static! {
@@ -399,8 +475,16 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
             picked_node: Handle::NONE,
             prev_picked_node: Handle::NONE,
             keyboard_focus_node: Handle::NONE,
+            keyboard_modifiers: Default::default(),
+            shortcuts: ShortcutRegistry::new(),
+            clipboard: Box::new(MemoryClipboard::new()),
+            spatial_navigation: false,
             stack: Default::default(),
+            route: Default::default(),
             root_picking_node: Default::default(),
+            consumed_keys: Vec::new(),
+            opened_focus_scope: Handle::NONE,
+            recording: None,
         };
         ui.root_canvas = ui.add_node(UINode::Canvas(Canvas::new(Widget::default())));
         ui
@@ -475,9 +559,18 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
     }
 
     pub fn update(&mut self, screen_size: Vec2, dt: f32) {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.push(RecordedEvent::Update { screen_size, dt });
+        }
+
         self.screen_size = screen_size;
         self.update_visibility();
 
+        let opened_focus_scope = std::mem::replace(&mut self.opened_focus_scope, Handle::NONE);
+        if self.nodes.is_valid_handle(opened_focus_scope) {
+            self.focus_first_tab_stop(opened_focus_scope);
+        }
+
         for n in self.nodes.iter() {
             if !n.widget().is_globally_visible() && n.widget().prev_global_visibility == n.widget().is_globally_visible() {
                 n.widget().commit_measure(Vec2::ZERO);
@@ -780,33 +873,98 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
         }
     }
 
-    /// Extracts UI event one-by-one from common queue. Each extracted event will go to *all*
-    /// available nodes first and only then will be moved outside of this method. This is one
-    /// of most important methods which must be called each frame of your game loop, otherwise
-    /// UI will not respond to any kind of events and simply speaking will just not work.
-    pub fn poll_message(&mut self) -> Option<UiMessage<M, C>> {
-        // Gather events from nodes.
-        for (handle, node) in self.nodes.pair_iter_mut() {
-            while let Some(mut outgoing_message) = node.widget_mut().pop_message() {
-                outgoing_message.source = handle;
-                self.messages.push_back(outgoing_message)
+    /// Delivers message along its route. Message without source and target has no route,
+    /// it is broadcast to every node like all messages were before routing was added.
+    fn route_message(&mut self, message: &mut UiMessage<M, C>) {
+        let destination = message.destination();
+        if destination.is_none() {
+            self.broadcast_message(message);
+            return;
+        }
+        if !self.nodes.is_valid_handle(destination) {
+            return;
+        }
+
+        // Route is taken out to be able to borrow self as mutable while iterating.
+        let mut route = std::mem::take(&mut self.route);
+        route.clear();
+        let mut handle = destination;
+        while handle.is_some() {
+            route.push(handle);
+            handle = self.nodes.borrow(handle).widget().parent();
+        }
+
+        // Tunneling: from root down to destination.
+        for &handle in route.iter().rev() {
+            if message.handled {
+                break;
+            }
+            // Node can be removed by some other node on the route.
+            if self.nodes.is_valid_handle(handle) {
+                let (ticket, mut node) = self.nodes.take_reserve(handle);
+
+                node.preview_message(handle, self, message);
+
+                self.nodes.put_back(ticket, node);
             }
         }
 
-        let mut event = self.messages.pop_front();
+        // Bubbling: from destination up to root.
+        for &handle in route.iter() {
+            if message.handled {
+                break;
+            }
+            if self.nodes.is_valid_handle(handle) {
+                let (ticket, mut node) = self.nodes.take_reserve(handle);
 
-        if let Some(ref mut message) = event {
-            for i in 0..self.nodes.get_capacity() {
-                let handle = self.nodes.handle_from_index(i);
+                node.handle_message(handle, self, message);
 
-                if self.nodes.is_valid_handle(handle) {
-                    let (ticket, mut node) = self.nodes.take_reserve(handle);
+                self.nodes.put_back(ticket, node);
+            }
+        }
 
-                    node.handle_message(handle, self, message);
+        self.route = route;
+    }
+
+    fn broadcast_message(&mut self, message: &mut UiMessage<M, C>) {
+        for i in 0..self.nodes.get_capacity() {
+            let handle = self.nodes.handle_from_index(i);
+
+            if self.nodes.is_valid_handle(handle) {
+                let (ticket, mut node) = self.nodes.take_reserve(handle);
+
+                node.handle_message(handle, self, message);
+
+                self.nodes.put_back(ticket, node);
+            }
+        }
+    }
 
-                    self.nodes.put_back(ticket, node);
+    /// Extracts UI event one-by-one from common queue. Each extracted event will be routed
+    /// to nodes first (see `RoutingStrategy`) and only then will be moved outside of this
+    /// method. This is one of most important methods which must be called each frame of
+    /// your game loop, otherwise UI will not respond to any kind of events and simply
+    /// speaking will just not work.
+    pub fn poll_message(&mut self) -> Option<UiMessage<M, C>> {
+        // Gather events from nodes. There is no need to do this while there are pending
+        // messages in the queue - messages from nodes will be put at the end of the queue
+        // anyway.
+        if self.messages.is_empty() {
+            for (handle, node) in self.nodes.pair_iter_mut() {
+                while let Some(mut outgoing_message) = node.widget_mut().pop_message() {
+                    outgoing_message.source = handle;
+                    self.messages.push_back(outgoing_message)
                 }
             }
+        }
+
+        let mut event = self.messages.pop_front();
+
+        if let Some(ref mut message) = event {
+            match message.routing {
+                RoutingStrategy::BubbleUp => self.route_message(message),
+                RoutingStrategy::Broadcast => self.broadcast_message(message),
+            }
 
             if let UiMessageData::Widget(msg) = &message.data {
                 match msg {
@@ -843,9 +1001,27 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
                             self.make_topmost(message.source);
                         }
                     }
+                    WidgetMessage::KeyDown { code, modifiers } => {
+                        // Key that was not handled by focused node and its ancestors can
+                        // trigger a shortcut.
+                        if !message.handled {
+                            let node = if message.target.is_some() { message.target } else { message.source };
+                            self.try_trigger_shortcut(KeyChord { code: *code, modifiers: *modifiers }, node);
+                        }
+                    }
                     _ => {}
                 }
             }
+
+            match &message.data {
+                UiMessageData::Popup(PopupMessage::Open) | UiMessageData::Window(WindowMessage::Opened) => {
+                    let node = if message.target.is_some() { message.target } else { message.source };
+                    if self.nodes.is_valid_handle(node) && self.nodes.borrow(node).widget().is_focus_scope() {
+                        self.opened_focus_scope = node;
+                    }
+                }
+                _ => {}
+            }
         }
 
         event
@@ -855,14 +1031,362 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
         self.captured_node
     }
 
+    pub fn keyboard_focus_node(&self) -> Handle<UINode<M, C>> {
+        self.keyboard_focus_node
+    }
+
+    /// Returns screen-space rectangle of text caret of focused node, if focused node accepts
+    /// text input. Use it to position candidate window of input method.
+    pub fn caret_rect(&self) -> Option<Rect<f32>> {
+        if self.nodes.is_valid_handle(self.keyboard_focus_node) {
+            self.nodes.borrow(self.keyboard_focus_node).caret_rect()
+        } else {
+            None
+        }
+    }
+
+    /// Returns current state of keyboard modifiers.
+    pub fn keyboard_modifiers(&self) -> KeyboardModifiers {
+        self.keyboard_modifiers
+    }
+
+    pub fn shortcuts(&self) -> &ShortcutRegistry<M, C> {
+        &self.shortcuts
+    }
+
+    /// Returns registry of shortcuts, use it to add or remove shortcuts.
+    pub fn shortcuts_mut(&mut self) -> &mut ShortcutRegistry<M, C> {
+        &mut self.shortcuts
+    }
+
+    /// Installs new clipboard implementation. Use it to provide access to clipboard of OS.
+    pub fn set_clipboard(&mut self, clipboard: Box<dyn Clipboard>) {
+        self.clipboard = clipboard;
+    }
+
+    pub fn clipboard(&self) -> &dyn Clipboard {
+        &*self.clipboard
+    }
+
+    pub fn clipboard_mut(&mut self) -> &mut dyn Clipboard {
+        &mut *self.clipboard
+    }
+
+    /// Starts recording of OS events and updates, previous recording is discarded.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(InputRecording::new());
+    }
+
+    /// Stops recording and returns recorded events, `None` if recording was not started.
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        self.recording.take()
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Enables or disables spatial navigation by arrow keys. When enabled, arrow keys will
+    /// move focus to the nearest focusable node in respective direction, except when a
+    /// text box is focused - it uses arrow keys to move caret. `OsEvent::Navigation` events
+    /// are processed regardless of this setting.
+    pub fn set_spatial_navigation(&mut self, state: bool) {
+        self.spatial_navigation = state;
+    }
+
+    pub fn is_spatial_navigation_enabled(&self) -> bool {
+        self.spatial_navigation
+    }
+
+    /// Moves keyboard focus to specified node. Previously focused node will receive `LostFocus`
+    /// message and new one will receive `GotFocus` message. Pass `Handle::NONE` to remove focus
+    /// from any node.
+    pub fn set_focus(&mut self, node: Handle<UINode<M, C>>) {
+        if self.keyboard_focus_node == node {
+            return;
+        }
+
+        if self.nodes.is_valid_handle(self.keyboard_focus_node) {
+            self.messages.push_back(UiMessage {
+                handled: false,
+                data: UiMessageData::Widget(WidgetMessage::LostFocus),
+                target: Handle::NONE,
+                source: self.keyboard_focus_node,
+                routing: RoutingStrategy::BubbleUp,
+            });
+        }
+
+        self.keyboard_focus_node = node;
+
+        if self.nodes.is_valid_handle(self.keyboard_focus_node) {
+            self.messages.push_back(UiMessage {
+                handled: false,
+                data: UiMessageData::Widget(WidgetMessage::GotFocus),
+                target: Handle::NONE,
+                source: self.keyboard_focus_node,
+                routing: RoutingStrategy::BubbleUp,
+            });
+        }
+    }
+
+    /// Returns a node which restricts Tab navigation for currently focused node. This is the
+    /// closest visible ancestor (or node itself) marked as focus scope, if there is no such
+    /// node - then it is either picking restriction node or root canvas.
+    fn focus_scope(&self) -> Handle<UINode<M, C>> {
+        let mut handle = self.keyboard_focus_node;
+        while self.nodes.is_valid_handle(handle) {
+            let widget = self.nodes.borrow(handle).widget();
+            if widget.is_focus_scope() && widget.is_globally_visible() {
+                return handle;
+            }
+            handle = widget.parent();
+        }
+
+        if self.nodes.is_valid_handle(self.root_picking_node) {
+            self.root_picking_node
+        } else {
+            self.root_canvas
+        }
+    }
+
+    /// Collects every visible focusable node in a subtree. Nodes with explicit tab index go
+    /// first (sorted by the index), then all other nodes in order of their appearance in
+    /// the tree.
+    fn tab_order(&self, scope: Handle<UINode<M, C>>) -> Vec<Handle<UINode<M, C>>> {
+        let mut indexed = Vec::new();
+        let mut unindexed = Vec::new();
+
+        let mut stack = vec![scope];
+        while let Some(handle) = stack.pop() {
+            let widget = self.nodes.borrow(handle).widget();
+            if !widget.is_globally_visible() {
+                continue;
+            }
+            if widget.is_focusable() {
+                if let Some(tab_index) = widget.tab_index() {
+                    indexed.push((tab_index, handle));
+                } else {
+                    unindexed.push(handle);
+                }
+            }
+            // Reversed to visit children in direct order.
+            for child in widget.children().iter().rev() {
+                stack.push(*child);
+            }
+        }
+
+        // Sort is stable, so nodes with same tab index will keep tree order.
+        indexed.sort_by_key(|(tab_index, _)| *tab_index);
+
+        indexed.into_iter()
+            .map(|(_, handle)| handle)
+            .chain(unindexed.into_iter())
+            .collect()
+    }
+
+    /// Returns position of focused node in given navigation order. Focused node can be
+    /// non-focusable (for example when a text inside a button was clicked), in this case
+    /// closest focusable ancestor is used as current position.
+    fn focused_position(&self, order: &[Handle<UINode<M, C>>]) -> Option<usize> {
+        let mut handle = self.keyboard_focus_node;
+        while self.nodes.is_valid_handle(handle) {
+            if let Some(position) = order.iter().position(|h| *h == handle) {
+                return Some(position);
+            }
+            handle = self.nodes.borrow(handle).widget().parent();
+        }
+        None
+    }
+
+    /// Moves keyboard focus to the nearest focusable node in given direction. Explicit
+    /// navigation target of focused node (see `Widget::set_navigation_target`) has priority
+    /// over geometric search if it is visible focusable node of current focus scope.
+    /// Candidates are taken from current focus scope and compared by their screen bounds,
+    /// nodes that are closer to the axis of movement are preferred. Returns true if focus
+    /// was moved.
+    pub fn move_focus_spatial(&mut self, direction: NavigationDirection) -> bool {
+        let order = self.tab_order(self.focus_scope());
+        if order.is_empty() {
+            return false;
+        }
+
+        let current = match self.focused_position(&order) {
+            Some(position) => order[position],
+            None => {
+                // Nothing is focused yet - start from first node.
+                self.set_focus(order[0]);
+                return true;
+            }
+        };
+
+        let current_widget = self.nodes.borrow(current).widget();
+        let explicit_target = current_widget.navigation_target(direction);
+        let bounds = current_widget.screen_bounds();
+
+        // Order contains only visible focusable nodes of current scope, so target that is
+        // hidden, not focusable or outside of the scope is ignored.
+        if explicit_target.is_some() && order.contains(&explicit_target) {
+            self.set_focus(explicit_target);
+            return true;
+        }
+
+        let center = Vec2::new(bounds.x + bounds.w * 0.5, bounds.y + bounds.h * 0.5);
+
+        let mut closest = Handle::NONE;
+        let mut closest_score = std::f32::MAX;
+        for &candidate in order.iter() {
+            if candidate == current {
+                continue;
+            }
+
+            let candidate_bounds = self.nodes.borrow(candidate).widget().screen_bounds();
+            let candidate_center = Vec2::new(
+                candidate_bounds.x + candidate_bounds.w * 0.5,
+                candidate_bounds.y + candidate_bounds.h * 0.5,
+            );
+
+            let dx = candidate_center.x - center.x;
+            let dy = candidate_center.y - center.y;
+
+            // Distance along the direction of movement and offset from its axis.
+            let (primary, secondary) = match direction {
+                NavigationDirection::Left => (-dx, dy.abs()),
+                NavigationDirection::Right => (dx, dy.abs()),
+                NavigationDirection::Up => (-dy, dx.abs()),
+                NavigationDirection::Down => (dy, dx.abs()),
+            };
+
+            if primary <= std::f32::EPSILON {
+                // Candidate is behind or on the same level.
+                continue;
+            }
+
+            let score = primary + 2.0 * secondary;
+            if score < closest_score {
+                closest_score = score;
+                closest = candidate;
+            }
+        }
+
+        if closest.is_some() {
+            self.set_focus(closest);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Moves keyboard focus to first node in Tab navigation order of given scope, unless
+    /// focus is already inside of the scope. Does nothing if scope is not visible.
+    fn focus_first_tab_stop(&mut self, scope: Handle<UINode<M, C>>) {
+        if !self.nodes.borrow(scope).widget().is_globally_visible() {
+            return;
+        }
+        let order = self.tab_order(scope);
+        if self.focused_position(&order).is_none() {
+            if let Some(first) = order.first() {
+                self.set_focus(*first);
+            }
+        }
+    }
+
+    /// Moves keyboard focus to next or previous focusable node in Tab navigation order.
+    /// Focus cycles inside current focus scope. Returns true if focus was moved.
+    pub fn move_focus(&mut self, direction: FocusDirection) -> bool {
+        let order = self.tab_order(self.focus_scope());
+        if order.is_empty() {
+            return false;
+        }
+
+        let next = match (self.focused_position(&order), direction) {
+            (Some(position), FocusDirection::Forward) => (position + 1) % order.len(),
+            (Some(position), FocusDirection::Backward) => (position + order.len() - 1) % order.len(),
+            (None, FocusDirection::Forward) => 0,
+            (None, FocusDirection::Backward) => order.len() - 1,
+        };
+
+        // Single tab stop in scope that is already focused.
+        if order[next] == self.keyboard_focus_node {
+            return false;
+        }
+
+        self.set_focus(order[next]);
+
+        true
+    }
+
     pub fn flush_messages(&mut self) {
         while let Some(_) = self.poll_message() {}
     }
 
+    fn consume_key(&mut self, key: KeyCode) {
+        if !self.consumed_keys.contains(&key) {
+            self.consumed_keys.push(key);
+        }
+    }
+
+    fn update_modifiers(&mut self, key: KeyCode, state: ButtonState) {
+        let pressed = state == ButtonState::Pressed;
+        match key {
+            KeyCode::LShift | KeyCode::RShift => self.keyboard_modifiers.shift = pressed,
+            KeyCode::LControl | KeyCode::RControl => self.keyboard_modifiers.control = pressed,
+            KeyCode::LAlt | KeyCode::RAlt => self.keyboard_modifiers.alt = pressed,
+            KeyCode::LWin | KeyCode::RWin => self.keyboard_modifiers.system = pressed,
+            _ => ()
+        }
+    }
+
+    /// Searches for a shortcut for given chord, starting from given node up to root and then
+    /// in global shortcuts. If shortcut was found, its message will be posted with source set
+    /// to scope of shortcut.
+    fn try_trigger_shortcut(&mut self, chord: KeyChord, node: Handle<UINode<M, C>>) -> bool {
+        if self.shortcuts.is_empty() || is_modifier_key(chord.code) {
+            return false;
+        }
+
+        let mut scope = node;
+        loop {
+            if let Some(message) = self.shortcuts.make_message(chord, scope) {
+                self.messages.push_back(UiMessage {
+                    handled: false,
+                    data: UiMessageData::User(message),
+                    target: Handle::NONE,
+                    source: scope,
+                    routing: RoutingStrategy::BubbleUp,
+                });
+                return true;
+            }
+            if scope.is_none() {
+                return false;
+            }
+            scope = if self.nodes.is_valid_handle(scope) {
+                self.nodes.borrow(scope).widget().parent()
+            } else {
+                Handle::NONE
+            };
+        }
+    }
+
+    fn is_arrow_navigation_allowed(&self) -> bool {
+        if !self.spatial_navigation {
+            return false;
+        }
+        if self.nodes.is_valid_handle(self.keyboard_focus_node) {
+            if let UINode::TextBox(_) = self.nodes.borrow(self.keyboard_focus_node) {
+                return false;
+            }
+        }
+        true
+    }
+
     /// Translates raw window event into some specific UI message. This is one of the
     /// most important methods of UI. You must call it each time you received a message
     /// from a window.
     pub fn process_os_event(&mut self, event: &OsEvent) -> bool {
+        if let Some(recording) = self.recording.as_mut() {
+            recording.push(RecordedEvent::Os(event.clone()));
+        }
+
         let mut event_processed = false;
 
         match event {
@@ -871,27 +1395,7 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
                     ButtonState::Pressed => {
                         self.picked_node = self.hit_test(self.cursor_position);
 
-                        if self.keyboard_focus_node != self.picked_node {
-                            if self.keyboard_focus_node.is_some() {
-                                self.messages.push_back(UiMessage {
-                                    handled: false,
-                                    data: UiMessageData::Widget(WidgetMessage::LostFocus),
-                                    target: Handle::NONE,
-                                    source: self.keyboard_focus_node,
-                                });
-                            }
-
-                            self.keyboard_focus_node = self.picked_node;
-
-                            if self.keyboard_focus_node.is_some() {
-                                self.messages.push_back(UiMessage {
-                                    handled: false,
-                                    data: UiMessageData::Widget(WidgetMessage::GotFocus),
-                                    target: Handle::NONE,
-                                    source: self.keyboard_focus_node,
-                                });
-                            }
-                        }
+                        self.set_focus(self.picked_node);
 
                         if self.picked_node.is_some() {
                             self.messages.push_back(UiMessage {
@@ -899,9 +1403,11 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
                                 data: UiMessageData::Widget(WidgetMessage::MouseDown {
                                     pos: self.cursor_position,
                                     button: *button,
+                                    modifiers: self.keyboard_modifiers,
                                 }),
                                 target: Handle::NONE,
                                 source: self.picked_node,
+                                routing: RoutingStrategy::BubbleUp,
                             });
                             event_processed = true;
                         }
@@ -913,9 +1419,11 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
                                 data: UiMessageData::Widget(WidgetMessage::MouseUp {
                                     pos: self.cursor_position,
                                     button: *button,
+                                    modifiers: self.keyboard_modifiers,
                                 }),
                                 target: Handle::NONE,
                                 source: self.picked_node,
+                                routing: RoutingStrategy::BubbleUp,
                             });
                             event_processed = true;
                         }
@@ -936,6 +1444,7 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
                             data: UiMessageData::Widget(WidgetMessage::MouseLeave),
                             target: Handle::NONE,
                             source: self.prev_picked_node,
+                            routing: RoutingStrategy::BubbleUp,
                         });
                     }
                 }
@@ -949,6 +1458,7 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
                             data: UiMessageData::Widget(WidgetMessage::MouseEnter),
                             target: Handle::NONE,
                             source: self.picked_node,
+                            routing: RoutingStrategy::BubbleUp,
                         });
                     }
 
@@ -958,6 +1468,7 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
                         data: UiMessageData::Widget(WidgetMessage::MouseMove(self.cursor_position)),
                         target: Handle::NONE,
                         source: self.picked_node,
+                        routing: RoutingStrategy::BubbleUp,
                     });
 
                     event_processed = true;
@@ -970,28 +1481,66 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
                         data: UiMessageData::Widget(WidgetMessage::MouseWheel {
                             pos: self.cursor_position,
                             amount: *y,
+                            modifiers: self.keyboard_modifiers,
                         }),
                         target: Handle::NONE,
                         source: self.picked_node,
+                        routing: RoutingStrategy::BubbleUp,
                     });
 
                     event_processed = true;
                 }
             }
             OsEvent::KeyboardInput { button, state } => {
-                if self.keyboard_focus_node.is_some() {
+                self.update_modifiers(*button, *state);
+
+                let tab_direction = if self.keyboard_modifiers.shift {
+                    FocusDirection::Backward
+                } else {
+                    FocusDirection::Forward
+                };
+
+                let consumed_position = self.consumed_keys.iter().position(|key| *key == *button);
+
+                if let (ButtonState::Released, Some(position)) = (*state, consumed_position) {
+                    self.consumed_keys.remove(position);
+                    event_processed = true;
+                } else if *state == ButtonState::Pressed && self.keyboard_focus_node.is_none() &&
+                    self.try_trigger_shortcut(KeyChord { code: *button, modifiers: self.keyboard_modifiers }, Handle::NONE) {
+                    // Focused node gets the key first, shortcut is triggered only if the node
+                    // did not handle it (see `poll_message`). Without focus only global
+                    // shortcuts can be triggered.
+                    self.consume_key(*button);
+                    event_processed = true;
+                } else if *button == KeyCode::Tab && *state == ButtonState::Pressed && self.move_focus(tab_direction) {
+                    // Tab is consumed only if focus was moved, otherwise focused node gets it.
+                    self.consume_key(*button);
+                    event_processed = true;
+                } else if *state == ButtonState::Pressed && self.is_arrow_navigation_allowed() &&
+                    arrow_key_direction(*button).map_or(false, |direction| self.move_focus_spatial(direction)) {
+                    // Arrow is consumed only if focus was moved, otherwise focused node gets it.
+                    self.consume_key(*button);
+                    event_processed = true;
+                } else if self.keyboard_focus_node.is_some() {
                     let message = UiMessage {
                         handled: false,
                         data: match state {
                             ButtonState::Pressed => {
-                                UiMessageData::Widget(WidgetMessage::KeyDown(*button))
+                                UiMessageData::Widget(WidgetMessage::KeyDown {
+                                    code: *button,
+                                    modifiers: self.keyboard_modifiers,
+                                })
                             }
                             ButtonState::Released => {
-                                UiMessageData::Widget(WidgetMessage::KeyUp(*button))
+                                UiMessageData::Widget(WidgetMessage::KeyUp {
+                                    code: *button,
+                                    modifiers: self.keyboard_modifiers,
+                                })
                             }
                         },
                         target: Handle::NONE,
                         source: self.keyboard_focus_node,
+                        routing: RoutingStrategy::BubbleUp,
                     };
 
                     self.messages.push_back(message);
@@ -999,6 +1548,44 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
                     event_processed = true;
                 }
             }
+            OsEvent::Navigation(input) => {
+                event_processed = match input {
+                    NavigationInput::Up => self.move_focus_spatial(NavigationDirection::Up),
+                    NavigationInput::Down => self.move_focus_spatial(NavigationDirection::Down),
+                    NavigationInput::Left => self.move_focus_spatial(NavigationDirection::Left),
+                    NavigationInput::Right => self.move_focus_spatial(NavigationDirection::Right),
+                    NavigationInput::Accept | NavigationInput::Cancel => {
+                        if self.keyboard_focus_node.is_some() {
+                            self.messages.push_back(UiMessage {
+                                handled: false,
+                                data: UiMessageData::Widget(WidgetMessage::Navigation(*input)),
+                                target: Handle::NONE,
+                                source: self.keyboard_focus_node,
+                                routing: RoutingStrategy::BubbleUp,
+                            });
+                            true
+                        } else {
+                            false
+                        }
+                    }
+                };
+            }
+            OsEvent::KeyboardModifiers(modifiers) => {
+                self.keyboard_modifiers = *modifiers;
+            }
+            OsEvent::Composition(composition) => {
+                if self.keyboard_focus_node.is_some() {
+                    self.messages.push_back(UiMessage {
+                        handled: false,
+                        data: UiMessageData::Widget(WidgetMessage::Composition(composition.clone())),
+                        target: Handle::NONE,
+                        source: self.keyboard_focus_node,
+                        routing: RoutingStrategy::BubbleUp,
+                    });
+
+                    event_processed = true;
+                }
+            }
             OsEvent::Character(unicode) => {
                 if self.keyboard_focus_node.is_some() {
                     let message = UiMessage {
@@ -1006,6 +1593,7 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
                         data: UiMessageData::Widget(WidgetMessage::Text(*unicode)),
                         target: Handle::NONE,
                         source: self.keyboard_focus_node,
+                        routing: RoutingStrategy::BubbleUp,
                     };
 
                     self.messages.push_back(message);
@@ -1104,6 +1692,10 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
                 node.remove_ref(*removed_node);
             }
         }
+
+        for removed_node in removed_nodes {
+            self.shortcuts.remove_scope(removed_node);
+        }
     }
 
     /// Links specified child with specified parent.
@@ -1178,6 +1770,26 @@ impl<M, C: 'static + Control<M, C>> UserInterface<M, C> {
     }
 }
 
+fn is_modifier_key(key: KeyCode) -> bool {
+    match key {
+        KeyCode::LShift | KeyCode::RShift |
+        KeyCode::LControl | KeyCode::RControl |
+        KeyCode::LAlt | KeyCode::RAlt |
+        KeyCode::LWin | KeyCode::RWin => true,
+        _ => false
+    }
+}
+
+fn arrow_key_direction(key: KeyCode) -> Option<NavigationDirection> {
+    match key {
+        KeyCode::Up => Some(NavigationDirection::Up),
+        KeyCode::Down => Some(NavigationDirection::Down),
+        KeyCode::Left => Some(NavigationDirection::Left),
+        KeyCode::Right => Some(NavigationDirection::Right),
+        _ => None
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
@@ -1189,9 +1801,19 @@ mod test {
         UserInterface,
         Control,
         button::ButtonBuilder,
+        stack_panel::StackPanelBuilder,
+        test_driver::TestDriver,
         node::UINode,
-        core::math::vec2::Vec2,
+        message::{UiMessage, UiMessageData, WidgetMessage, WindowMessage, KeyCode, KeyboardModifiers},
+        shortcut::KeyChord,
+        text_box::TextBoxBuilder,
+        serialization::{Visit, Visitor, VisitResult, SerializeNode},
+        FocusDirection,
+        core::{math::vec2::Vec2, pool::Handle},
     };
+    use std::{cell::RefCell, rc::Rc};
+
+    type Ui = UserInterface<StubUiMessage, StubUiNode>;
 
     pub struct StubUiMessage {}
 
@@ -1211,9 +1833,25 @@ mod test {
         }
     }
 
+    impl Visit<StubUiMessage, StubUiNode> for StubUiNode {
+        fn visit(&mut self, _: &mut Visitor<StubUiMessage, StubUiNode>) -> VisitResult {
+            Ok(())
+        }
+    }
+
+    impl SerializeNode<StubUiMessage, StubUiNode> for StubUiNode {
+        fn type_name(&self) -> &str {
+            "Stub"
+        }
+
+        fn create(_: &str) -> Option<Self> {
+            None
+        }
+    }
+
     #[test]
     fn perf_test() {
-        let mut ui = UserInterface::<StubUiMessage, StubUiNode>::new();
+        let mut ui = Ui::new();
 
         GridBuilder::new(WidgetBuilder::new()
             .with_width(1000.0)
@@ -1284,4 +1922,304 @@ mod test {
 
         ui.update(Vec2::new(1000.0, 1000.0), 0.016);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn tab_order() {
+        let mut ui = Ui::new();
+        let a = ButtonBuilder::new(WidgetBuilder::new()).build(&mut ui);
+        let b = ButtonBuilder::new(WidgetBuilder::new().with_tab_index(1)).build(&mut ui);
+        let c = ButtonBuilder::new(WidgetBuilder::new().with_tab_index(0)).build(&mut ui);
+        let d = ButtonBuilder::new(WidgetBuilder::new()).build(&mut ui);
+        StackPanelBuilder::new(WidgetBuilder::new()
+            .with_children(&[a, b, c, d]))
+            .build(&mut ui);
+        ui.update(Vec2::new(300.0, 300.0), 0.0);
+
+        // Nodes with tab index go first, then other nodes in tree order.
+        let mut visited = Vec::new();
+        for _ in 0..5 {
+            assert!(ui.move_focus(FocusDirection::Forward));
+            visited.push(ui.keyboard_focus_node());
+        }
+        assert_eq!(visited, vec![c, b, a, d, c]);
+
+        assert!(ui.move_focus(FocusDirection::Backward));
+        assert_eq!(ui.keyboard_focus_node(), d);
+    }
+
+    #[test]
+    fn tab_is_not_consumed_without_focus_change() {
+        let mut ui = Ui::new();
+        let button = ButtonBuilder::new(WidgetBuilder::new()).build(&mut ui);
+        let mut driver = TestDriver::new(&mut ui, Vec2::new(300.0, 300.0));
+        driver.ui_mut().set_focus(button);
+        driver.advance(0.0).take_messages();
+
+        // Only tab stop is already focused, so Tab goes to it.
+        assert!(!driver.ui_mut().move_focus(FocusDirection::Forward));
+        driver.key_down(KeyCode::Tab).key_up(KeyCode::Tab);
+        let keys = driver.take_messages()
+            .into_iter()
+            .filter(|message| match &message.data {
+                UiMessageData::Widget(WidgetMessage::KeyDown { code: KeyCode::Tab, .. }) => true,
+                UiMessageData::Widget(WidgetMessage::KeyUp { code: KeyCode::Tab, .. }) => true,
+                _ => false,
+            })
+            .count();
+        assert_eq!(keys, 2);
+
+        // With second tab stop focus moves and neither press nor release reaches nodes.
+        let other = ButtonBuilder::new(WidgetBuilder::new()).build(driver.ui_mut());
+        driver.advance(0.0).take_messages();
+        driver.key_down(KeyCode::Tab).key_up(KeyCode::Tab);
+        assert_eq!(driver.ui().keyboard_focus_node(), other);
+        assert!(driver.take_messages().iter().all(|message| match &message.data {
+            UiMessageData::Widget(WidgetMessage::KeyDown { .. }) => false,
+            UiMessageData::Widget(WidgetMessage::KeyUp { .. }) => false,
+            _ => true,
+        }));
+    }
+
+    #[test]
+    fn opened_window_takes_focus() {
+        let mut ui = Ui::new();
+        let outside = ButtonBuilder::new(WidgetBuilder::new()).build(&mut ui);
+        let first = ButtonBuilder::new(WidgetBuilder::new()).build(&mut ui);
+        let second = ButtonBuilder::new(WidgetBuilder::new()).build(&mut ui);
+        let window = WindowBuilder::new(WidgetBuilder::new()
+            .with_width(200.0)
+            .with_height(200.0))
+            .can_close(false)
+            .can_minimize(false)
+            .with_content(StackPanelBuilder::new(WidgetBuilder::new()
+                .with_child(first)
+                .with_child(second))
+                .build(&mut ui))
+            .build(&mut ui);
+
+        let mut driver = TestDriver::new(&mut ui, Vec2::new(300.0, 300.0));
+        driver.ui_mut().set_focus(outside);
+        driver.ui_mut().post_message(UiMessage::targeted(window, UiMessageData::Window(WindowMessage::Opened)));
+        // Focus is moved on update that follows the message.
+        driver.advance(0.0).advance(0.0);
+        assert_eq!(driver.ui().keyboard_focus_node(), first);
+
+        // Tab navigation cycles inside of the window.
+        driver.ui_mut().move_focus(FocusDirection::Forward);
+        assert_eq!(driver.ui().keyboard_focus_node(), second);
+        driver.ui_mut().move_focus(FocusDirection::Forward);
+        assert_eq!(driver.ui().keyboard_focus_node(), first);
+    }
+
+    /// Returns scopes of shortcuts that were triggered, in order of triggering.
+    fn shortcut_scopes(messages: Vec<UiMessage<StubUiMessage, StubUiNode>>) -> Vec<Handle<UINode<StubUiMessage, StubUiNode>>> {
+        messages.into_iter()
+            .filter_map(|message| match message.data {
+                UiMessageData::User(_) => Some(message.source),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn control() -> KeyboardModifiers {
+        KeyboardModifiers { control: true, ..Default::default() }
+    }
+
+    #[test]
+    fn shortcut_chord_matching() {
+        let mut ui = Ui::new();
+        ui.shortcuts_mut().add(KeyChord::new(KeyCode::S).with_control(), Handle::NONE, || StubUiMessage {});
+        let mut driver = TestDriver::new(&mut ui, Vec2::new(300.0, 300.0));
+        driver.take_messages();
+
+        driver.press(KeyCode::S, KeyboardModifiers::default());
+        driver.press(KeyCode::S, KeyboardModifiers { control: true, shift: true, ..Default::default() });
+        driver.press(KeyCode::D, control());
+        assert!(shortcut_scopes(driver.take_messages()).is_empty());
+
+        driver.press(KeyCode::S, control());
+        assert_eq!(shortcut_scopes(driver.take_messages()), vec![Handle::NONE]);
+
+        // Modifier alone is never a shortcut.
+        driver.ui_mut().shortcuts_mut().add(KeyChord::new(KeyCode::LControl).with_control(), Handle::NONE, || StubUiMessage {});
+        driver.press(KeyCode::LControl, control());
+        assert!(shortcut_scopes(driver.take_messages()).is_empty());
+    }
+
+    #[test]
+    fn scoped_shortcut_has_priority_over_global() {
+        let mut ui = Ui::new();
+        let inside = ButtonBuilder::new(WidgetBuilder::new()).build(&mut ui);
+        let panel = StackPanelBuilder::new(WidgetBuilder::new()
+            .with_child(inside))
+            .build(&mut ui);
+        let outside = ButtonBuilder::new(WidgetBuilder::new()).build(&mut ui);
+        let chord = KeyChord::new(KeyCode::S).with_control();
+        ui.shortcuts_mut().add(chord, Handle::NONE, || StubUiMessage {});
+        ui.shortcuts_mut().add(chord, panel, || StubUiMessage {});
+        ui.shortcuts_mut().add(KeyChord::new(KeyCode::F5), Handle::NONE, || StubUiMessage {});
+
+        let mut driver = TestDriver::new(&mut ui, Vec2::new(300.0, 300.0));
+        driver.ui_mut().set_focus(inside);
+        driver.advance(0.0).take_messages();
+        driver.press(KeyCode::S, control());
+        assert_eq!(shortcut_scopes(driver.take_messages()), vec![panel]);
+
+        // Global shortcut still works inside of scope if scope does not override it.
+        driver.press(KeyCode::F5, KeyboardModifiers::default());
+        assert_eq!(shortcut_scopes(driver.take_messages()), vec![Handle::NONE]);
+
+        driver.ui_mut().set_focus(outside);
+        driver.advance(0.0).take_messages();
+        driver.press(KeyCode::S, control());
+        assert_eq!(shortcut_scopes(driver.take_messages()), vec![Handle::NONE]);
+    }
+
+    #[test]
+    fn focused_text_box_keys_precede_shortcuts() {
+        let mut ui = Ui::new();
+        let text_box = TextBoxBuilder::new(WidgetBuilder::new()
+            .with_width(200.0)
+            .with_height(30.0))
+            .with_text("hello".to_owned())
+            .build(&mut ui);
+        ui.shortcuts_mut().add(KeyChord::new(KeyCode::A).with_control(), Handle::NONE, || StubUiMessage {});
+        ui.shortcuts_mut().add(KeyChord::new(KeyCode::S).with_control(), Handle::NONE, || StubUiMessage {});
+
+        let mut driver = TestDriver::new(&mut ui, Vec2::new(300.0, 300.0));
+        driver.ui_mut().set_focus(text_box);
+        driver.advance(0.0).take_messages();
+
+        // Ctrl+A is used by text box, so global shortcut is not triggered.
+        driver.press(KeyCode::A, control());
+        assert!(shortcut_scopes(driver.take_messages()).is_empty());
+        if let UINode::TextBox(text_box) = driver.ui().node(text_box) {
+            assert_eq!(text_box.selected_text(), Some("hello".to_owned()));
+        } else {
+            panic!("expected text box");
+        }
+
+        // Ctrl+S is not used by text box, so it goes to the shortcut.
+        driver.press(KeyCode::S, control());
+        assert_eq!(shortcut_scopes(driver.take_messages()), vec![Handle::NONE]);
+    }
+
+    /// Node that writes every message it receives into shared log and can stop propagation.
+    struct LogNode {
+        widget: Widget<StubUiMessage, LogNode>,
+        name: &'static str,
+        log: Rc<RefCell<Vec<String>>>,
+        handles_in_preview: bool,
+        handles: bool,
+    }
+
+    impl Control<StubUiMessage, LogNode> for LogNode {
+        fn widget(&self) -> &Widget<StubUiMessage, LogNode> {
+            &self.widget
+        }
+
+        fn widget_mut(&mut self) -> &mut Widget<StubUiMessage, LogNode> {
+            &mut self.widget
+        }
+
+        fn raw_copy(&self) -> UINode<StubUiMessage, LogNode> {
+            UINode::User(LogNode {
+                widget: self.widget.raw_copy(),
+                name: self.name,
+                log: self.log.clone(),
+                handles_in_preview: self.handles_in_preview,
+                handles: self.handles,
+            })
+        }
+
+        fn handle_message(&mut self, self_handle: Handle<UINode<StubUiMessage, LogNode>>, ui: &mut UserInterface<StubUiMessage, LogNode>, message: &mut UiMessage<StubUiMessage, LogNode>) {
+            self.widget.handle_message(self_handle, ui, message);
+            if let UiMessageData::User(_) = message.data {
+                self.log.borrow_mut().push(format!("handle {}", self.name));
+                message.handled = self.handles;
+            }
+        }
+
+        fn preview_message(&mut self, _: Handle<UINode<StubUiMessage, LogNode>>, _: &mut UserInterface<StubUiMessage, LogNode>, message: &mut UiMessage<StubUiMessage, LogNode>) {
+            if let UiMessageData::User(_) = message.data {
+                self.log.borrow_mut().push(format!("preview {}", self.name));
+                message.handled = self.handles_in_preview;
+            }
+        }
+    }
+
+    impl Visit<StubUiMessage, LogNode> for LogNode {
+        fn visit(&mut self, _: &mut Visitor<StubUiMessage, LogNode>) -> VisitResult {
+            Ok(())
+        }
+    }
+
+    impl SerializeNode<StubUiMessage, LogNode> for LogNode {
+        fn type_name(&self) -> &str {
+            "Log"
+        }
+
+        fn create(_: &str) -> Option<Self> {
+            None
+        }
+    }
+
+    /// Builds chain of nodes `a` - `b` - `c` where `c` is the deepest one, returns handle of `c`.
+    fn build_log_chain(ui: &mut UserInterface<StubUiMessage, LogNode>, log: &Rc<RefCell<Vec<String>>>, handler: Option<(&str, bool)>) -> Handle<UINode<StubUiMessage, LogNode>> {
+        let mut child = Handle::NONE;
+        let mut deepest = Handle::NONE;
+        for &name in ["c", "b", "a"].iter() {
+            let mut widget_builder = WidgetBuilder::new();
+            if child.is_some() {
+                widget_builder = widget_builder.with_child(child);
+            }
+            let handler = handler.filter(|(handler_name, _)| *handler_name == name);
+            child = ui.add_node(UINode::User(LogNode {
+                widget: widget_builder.build(),
+                name,
+                log: log.clone(),
+                handles_in_preview: handler.map_or(false, |(_, in_preview)| in_preview),
+                handles: handler.map_or(false, |(_, in_preview)| !in_preview),
+            }));
+            if deepest.is_none() {
+                deepest = child;
+            }
+        }
+        deepest
+    }
+
+    fn route(handler: Option<(&str, bool)>) -> Vec<String> {
+        let mut ui = UserInterface::<StubUiMessage, LogNode>::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let c = build_log_chain(&mut ui, &log, handler);
+        ui.post_message(UiMessage::targeted(c, UiMessageData::User(StubUiMessage {})));
+        ui.flush_messages();
+        let log = log.borrow().clone();
+        log
+    }
+
+    #[test]
+    fn routing_order() {
+        assert_eq!(route(None), vec!["preview a", "preview b", "preview c", "handle c", "handle b", "handle a"]);
+    }
+
+    #[test]
+    fn handled_message_stops() {
+        // Handled on tunneling phase, destination never sees the message.
+        assert_eq!(route(Some(("b", true))), vec!["preview a", "preview b"]);
+        // Handled on bubbling phase, ancestors do not see it.
+        assert_eq!(route(Some(("b", false))), vec!["preview a", "preview b", "preview c", "handle c", "handle b"]);
+    }
+
+    #[test]
+    fn message_without_destination_is_broadcast() {
+        let mut ui = UserInterface::<StubUiMessage, LogNode>::new();
+        let log = Rc::new(RefCell::new(Vec::new()));
+        build_log_chain(&mut ui, &log, None);
+        ui.post_message(UiMessage::new(UiMessageData::User(StubUiMessage {})));
+        ui.flush_messages();
+        let mut log = log.borrow().clone();
+        log.sort();
+        assert_eq!(log, vec!["handle a", "handle b", "handle c"]);
+    }
+}
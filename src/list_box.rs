@@ -21,7 +21,8 @@ use crate::{
         color::Color,
     },
     brush::Brush,
-    NodeHandleMapping
+    NodeHandleMapping,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 pub struct ListBox<M: 'static, C: 'static + Control<M, C>> {
@@ -47,7 +48,7 @@ impl<M, C: 'static + Control<M, C>> ListBox<M, C> {
         if old_value.is_none() && new_index.is_some() ||
             old_value.is_some() && new_index.is_none() ||
             old_value.unwrap() != new_index.unwrap() {
-            self.widget.post_message(UiMessage::new(UiMessageData::ItemsControl(ItemsControlMessage::SelectionChanged(self.selected_index))))
+            self.widget.post_message(UiMessage::broadcast(UiMessageData::ItemsControl(ItemsControlMessage::SelectionChanged(self.selected_index))))
         }
     }
 
@@ -96,7 +97,7 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for ListBoxItem<M, C> {
 
         match &message.data {
             UiMessageData::Widget(msg) => {
-                if self.body.is_some() && (message.source == self_handle || self.widget().has_descendant(message.source, ui)) {
+                if self.body.is_some() {
                     let body = ui.node_mut(self.body).widget_mut();
                     match msg {
                         WidgetMessage::MouseLeave => {
@@ -178,6 +179,32 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for ListBox<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for ListBox<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit("selected_index", &mut self.selected_index)?;
+        visitor.visit_handles("items", &mut self.items)
+    }
+}
+
+impl<M, C: 'static + Control<M, C>> Default for ListBoxItem<M, C> {
+    fn default() -> Self {
+        Self {
+            widget: Widget::default(),
+            body: Handle::NONE,
+            index: 0,
+        }
+    }
+}
+
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for ListBoxItem<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit_handle("body", &mut self.body)?;
+        visitor.visit("index", &mut self.index)
+    }
+}
+
 pub struct ListBoxBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     items: Vec<Handle<UINode<M, C>>>,
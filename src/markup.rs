@@ -0,0 +1,836 @@
+// Declarative description of UI. Markup is XML-like text where every element is a node and
+// attributes are properties of a widget or control, for example:
+//
+// <Grid name="root" width="400" height="300" rows="30 *" columns="auto *">
+//     <Text row="0" column="0" text="Name:" vertical_text_alignment="center"/>
+//     <TextBox name="name" row="0" column="1" margin="2"/>
+//     <StackPanel row="1" column="1" orientation="horizontal">
+//         <Button name="ok" width="100" text="OK"/>
+//         <Button name="cancel" width="100" background="#404040">
+//             <Text text="Cancel" horizontal_text_alignment="center"/>
+//         </Button>
+//     </StackPanel>
+// </Grid>
+//
+// Widget attributes: name, width, height, min_size, max_size ("w h"), position ("x y"),
+// margin ("4", "4 2" or "left top right bottom"), horizontal_alignment, vertical_alignment,
+// row, column, background, foreground, visibility, hit_test_visibility, focusable,
+// tab_index, focus_scope and z_index. Brushes are colors ("#rgb", "#rrggbb", "#rrggbbaa")
+// or gradients - "linear(0.5 0; 0.5 1; 0 #555; 1 #444)" and "radial(0.5 0.5; 0 #fff; 1 #000)"
+// where points are in local coordinates of a node followed by stops. Rows and columns of
+// a grid are separated by spaces, a number is strict size, "*" is stretch, "auto" is auto.
+
+use crate::{
+    UserInterface,
+    Control,
+    UINode,
+    Thickness,
+    HorizontalAlignment,
+    VerticalAlignment,
+    widget::WidgetBuilder,
+    brush::{Brush, GradientPoint},
+    formatted_text::{WrapMode, parse_color},
+    scroll_bar::{Orientation, ScrollBarBuilder},
+    grid::{GridBuilder, Row, Column},
+    stack_panel::StackPanelBuilder,
+    canvas::CanvasBuilder,
+    border::BorderBuilder,
+    decorator::DecoratorBuilder,
+    text::TextBuilder,
+    text_box::TextBoxBuilder,
+    button::ButtonBuilder,
+    check_box::CheckBoxBuilder,
+    scroll_viewer::ScrollViewerBuilder,
+    progress_bar::ProgressBarBuilder,
+    window::{WindowBuilder, WindowTitle},
+    core::{
+        pool::Handle,
+        math::vec2::Vec2,
+    },
+};
+use std::{
+    fmt,
+    collections::{HashMap, HashSet},
+};
+
+/// Error that can occur while loading markup, every variant contains number of line
+/// where error has occurred.
+#[derive(Debug, PartialEq)]
+pub enum MarkupError {
+    /// Text is not well-formed, contains description of the problem.
+    Syntax(usize, String),
+    /// Element has unknown name.
+    UnknownElement(usize, String),
+    /// Element has attribute which it does not support.
+    UnknownAttribute(usize, String),
+    /// Attribute has value that cannot be parsed, contains name of attribute.
+    InvalidValue(usize, String),
+    /// Same name is used by two elements.
+    DuplicateName(usize, String),
+    /// Element has more children than it supports, contains name of element.
+    TooManyChildren(usize, String),
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MarkupError::Syntax(line, message) => write!(f, "line {}: {}", line, message),
+            MarkupError::UnknownElement(line, name) => write!(f, "line {}: unknown element '{}'", line, name),
+            MarkupError::UnknownAttribute(line, name) => write!(f, "line {}: unknown attribute '{}'", line, name),
+            MarkupError::InvalidValue(line, name) => write!(f, "line {}: invalid value of attribute '{}'", line, name),
+            MarkupError::DuplicateName(line, name) => write!(f, "line {}: name '{}' is already used", line, name),
+            MarkupError::TooManyChildren(line, name) => write!(f, "line {}: too many children of '{}'", line, name),
+        }
+    }
+}
+
+impl std::error::Error for MarkupError {}
+
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    line: usize,
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, message: &str) -> MarkupError {
+        MarkupError::Syntax(self.line, message.to_owned())
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.text[self.pos..]
+    }
+
+    fn advance(&mut self, count: usize) {
+        let end = self.pos + count;
+        self.line += self.text[self.pos..end].matches('\n').count();
+        self.pos = end;
+    }
+
+    fn skip_whitespace(&mut self) {
+        let count = self.rest().len() - self.rest().trim_start().len();
+        self.advance(count);
+    }
+
+    /// Skips whitespace and comments.
+    fn skip_misc(&mut self) -> Result<(), MarkupError> {
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("<!--") {
+                match self.rest().find("-->") {
+                    Some(end) => self.advance(end + 3),
+                    None => return Err(self.error("unterminated comment")),
+                }
+            } else if self.rest().starts_with("<?") {
+                match self.rest().find("?>") {
+                    Some(end) => self.advance(end + 2),
+                    None => return Err(self.error("unterminated declaration")),
+                }
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), MarkupError> {
+        if self.rest().starts_with(token) {
+            self.advance(token.len());
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", token)))
+        }
+    }
+
+    fn identifier(&mut self) -> Result<&'a str, MarkupError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '.'))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error("expected name"));
+        }
+        self.advance(len);
+        Ok(&rest[..len])
+    }
+
+    fn attribute_value(&mut self) -> Result<String, MarkupError> {
+        let quote = match self.rest().chars().next() {
+            Some(c) if c == '"' || c == '\'' => c,
+            _ => return Err(self.error("expected quoted value")),
+        };
+        self.advance(1);
+        let len = match self.rest().find(quote) {
+            Some(len) => len,
+            None => return Err(self.error("unterminated value")),
+        };
+        let raw = &self.rest()[..len];
+        let value = unescape(raw).ok_or_else(|| self.error("invalid entity"))?;
+        self.advance(len + 1);
+        Ok(value)
+    }
+
+    fn element(&mut self) -> Result<Element, MarkupError> {
+        let line = self.line;
+        self.expect("<")?;
+        let name = self.identifier()?.to_owned();
+        let mut attributes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.advance(2);
+                return Ok(Element { name, attributes, children: Vec::new(), line });
+            }
+            if self.rest().starts_with('>') {
+                self.advance(1);
+                break;
+            }
+            let attribute = self.identifier()?.to_owned();
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+            let value = self.attribute_value()?;
+            attributes.push((attribute, value));
+        }
+
+        let mut children = Vec::new();
+        loop {
+            self.skip_misc()?;
+            if self.rest().starts_with("</") {
+                self.advance(2);
+                let closing_line = self.line;
+                if self.identifier()? != name {
+                    return Err(MarkupError::Syntax(closing_line, format!("expected closing tag of '{}'", name)));
+                }
+                self.skip_whitespace();
+                self.expect(">")?;
+                return Ok(Element { name, attributes, children, line });
+            }
+            if self.rest().is_empty() {
+                return Err(self.error(&format!("element '{}' is not closed", name)));
+            }
+            if !self.rest().starts_with('<') {
+                return Err(self.error("text is not allowed inside of elements, use attributes"));
+            }
+            children.push(self.element()?);
+        }
+    }
+
+    fn document(mut self) -> Result<Element, MarkupError> {
+        self.skip_misc()?;
+        let root = self.element()?;
+        self.skip_misc()?;
+        if self.rest().is_empty() {
+            Ok(root)
+        } else {
+            Err(self.error("markup must have single root element"))
+        }
+    }
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        let end = rest[start..].find(';')? + start;
+        result.push(match &rest[start + 1..end] {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => return None,
+        });
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Some(result)
+}
+
+/// Attributes of an element, every attribute must be taken by the loader, otherwise it is
+/// unknown.
+struct Attributes {
+    values: Vec<(String, String)>,
+    line: usize,
+}
+
+impl Attributes {
+    fn take(&mut self, name: &str) -> Option<String> {
+        let index = self.values.iter().position(|(attribute, _)| attribute == name)?;
+        Some(self.values.remove(index).1)
+    }
+
+    fn parse<T, F>(&mut self, name: &str, parser: F) -> Result<Option<T>, MarkupError>
+        where F: FnOnce(&str) -> Option<T> {
+        match self.take(name) {
+            Some(value) => parser(value.trim())
+                .map(Some)
+                .ok_or_else(|| MarkupError::InvalidValue(self.line, name.to_owned())),
+            None => Ok(None),
+        }
+    }
+
+    fn number<T: std::str::FromStr>(&mut self, name: &str) -> Result<Option<T>, MarkupError> {
+        self.parse(name, |value| value.parse().ok())
+    }
+
+    fn bool(&mut self, name: &str) -> Result<Option<bool>, MarkupError> {
+        self.parse(name, parse_bool)
+    }
+
+    fn brush(&mut self, name: &str) -> Result<Option<Brush>, MarkupError> {
+        self.parse(name, parse_brush)
+    }
+
+    fn finish(self) -> Result<(), MarkupError> {
+        match self.values.into_iter().next() {
+            Some((name, _)) => Err(MarkupError::UnknownAttribute(self.line, name)),
+            None => Ok(()),
+        }
+    }
+}
+
+pub(in crate) fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+fn parse_numbers(value: &str) -> Option<Vec<f32>> {
+    value.split_whitespace().map(|number| number.parse().ok()).collect()
+}
+
+pub(in crate) fn parse_vec2(value: &str) -> Option<Vec2> {
+    match parse_numbers(value)?.as_slice() {
+        [x, y] => Some(Vec2::new(*x, *y)),
+        _ => None,
+    }
+}
+
+pub(in crate) fn parse_thickness(value: &str) -> Option<Thickness> {
+    match parse_numbers(value)?.as_slice() {
+        [v] => Some(Thickness::uniform(*v)),
+        [horizontal, vertical] => Some(Thickness { left: *horizontal, top: *vertical, right: *horizontal, bottom: *vertical }),
+        [left, top, right, bottom] => Some(Thickness { left: *left, top: *top, right: *right, bottom: *bottom }),
+        _ => None,
+    }
+}
+
+pub(in crate) fn parse_brush(value: &str) -> Option<Brush> {
+    let gradient = |prefix: &str| {
+        if !value.starts_with(prefix) {
+            return None;
+        }
+        let args = value[prefix.len()..].trim_start();
+        if !args.starts_with('(') || !args.ends_with(')') || args.len() < 2 {
+            return None;
+        }
+        Some(args[1..args.len() - 1].split(';').map(|arg| arg.trim()).collect::<Vec<&str>>())
+    };
+    let stops = |args: &[&str]| {
+        args.iter()
+            .map(|arg| {
+                let mut words = arg.split_whitespace();
+                let stop = words.next()?.parse().ok()?;
+                let color = parse_color(words.next()?)?;
+                if words.next().is_some() { None } else { Some(GradientPoint { stop, color }) }
+            })
+            .collect::<Option<Vec<GradientPoint>>>()
+    };
+
+    if value.starts_with('#') {
+        parse_color(value).map(Brush::Solid)
+    } else if let Some(args) = gradient("linear") {
+        if args.len() < 2 {
+            return None;
+        }
+        Some(Brush::LinearGradient { from: parse_vec2(args[0])?, to: parse_vec2(args[1])?, stops: stops(&args[2..])? })
+    } else if let Some(args) = gradient("radial") {
+        let (center, rest) = args.split_first()?;
+        Some(Brush::RadialGradient { center: parse_vec2(center)?, stops: stops(rest)? })
+    } else {
+        None
+    }
+}
+
+pub(in crate) fn parse_horizontal_alignment(value: &str) -> Option<HorizontalAlignment> {
+    match value {
+        "stretch" => Some(HorizontalAlignment::Stretch),
+        "left" => Some(HorizontalAlignment::Left),
+        "center" => Some(HorizontalAlignment::Center),
+        "right" => Some(HorizontalAlignment::Right),
+        _ => None,
+    }
+}
+
+pub(in crate) fn parse_vertical_alignment(value: &str) -> Option<VerticalAlignment> {
+    match value {
+        "stretch" => Some(VerticalAlignment::Stretch),
+        "top" => Some(VerticalAlignment::Top),
+        "center" => Some(VerticalAlignment::Center),
+        "bottom" => Some(VerticalAlignment::Bottom),
+        _ => None,
+    }
+}
+
+pub(in crate) fn parse_orientation(value: &str) -> Option<Orientation> {
+    match value {
+        "vertical" => Some(Orientation::Vertical),
+        "horizontal" => Some(Orientation::Horizontal),
+        _ => None,
+    }
+}
+
+pub(in crate) fn parse_wrap(value: &str) -> Option<WrapMode> {
+    match value {
+        "none" => Some(WrapMode::NoWrap),
+        "character" => Some(WrapMode::Character),
+        "word" => Some(WrapMode::Word),
+        _ => None,
+    }
+}
+
+/// Parses list of grid rows or columns.
+fn parse_grid_sizes<T>(value: &str, strict: fn(f32) -> T, stretch: fn() -> T, auto: fn() -> T) -> Option<Vec<T>> {
+    value.split_whitespace()
+        .map(|item| match item {
+            "*" => Some(stretch()),
+            "auto" => Some(auto()),
+            _ => item.parse().ok().map(strict),
+        })
+        .collect()
+}
+
+struct Loader<'a, M: 'static, C: 'static + Control<M, C>> {
+    ui: &'a mut UserInterface<M, C>,
+    names: HashMap<String, Handle<UINode<M, C>>>,
+}
+
+impl<'a, M, C: 'static + Control<M, C>> Loader<'a, M, C> {
+    fn widget_builder(&mut self, attributes: &mut Attributes) -> Result<WidgetBuilder<M, C>, MarkupError> {
+        let mut builder = WidgetBuilder::new();
+        if let Some(name) = attributes.take("name") {
+            builder = builder.with_name(&name);
+        }
+        if let Some(width) = attributes.number("width")? {
+            builder = builder.with_width(width);
+        }
+        if let Some(height) = attributes.number("height")? {
+            builder = builder.with_height(height);
+        }
+        if let Some(size) = attributes.parse("min_size", parse_vec2)? {
+            builder = builder.with_min_size(size);
+        }
+        if let Some(size) = attributes.parse("max_size", parse_vec2)? {
+            builder = builder.with_max_size(size);
+        }
+        if let Some(position) = attributes.parse("position", parse_vec2)? {
+            builder = builder.with_desired_position(position);
+        }
+        if let Some(margin) = attributes.parse("margin", parse_thickness)? {
+            builder = builder.with_margin(margin);
+        }
+        if let Some(alignment) = attributes.parse("horizontal_alignment", parse_horizontal_alignment)? {
+            builder = builder.with_horizontal_alignment(alignment);
+        }
+        if let Some(alignment) = attributes.parse("vertical_alignment", parse_vertical_alignment)? {
+            builder = builder.with_vertical_alignment(alignment);
+        }
+        if let Some(row) = attributes.number("row")? {
+            builder = builder.on_row(row);
+        }
+        if let Some(column) = attributes.number("column")? {
+            builder = builder.on_column(column);
+        }
+        if let Some(brush) = attributes.brush("background")? {
+            builder = builder.with_background(brush);
+        }
+        if let Some(brush) = attributes.brush("foreground")? {
+            builder = builder.with_foreground(brush);
+        }
+        if let Some(visibility) = attributes.bool("visibility")? {
+            builder = builder.with_visibility(visibility);
+        }
+        if let Some(visibility) = attributes.bool("hit_test_visibility")? {
+            builder = builder.with_hit_test_visibility(visibility);
+        }
+        if let Some(focusable) = attributes.bool("focusable")? {
+            builder = builder.with_focusable(focusable);
+        }
+        if let Some(tab_index) = attributes.number("tab_index")? {
+            builder = builder.with_tab_index(tab_index);
+        }
+        if let Some(focus_scope) = attributes.bool("focus_scope")? {
+            builder = builder.with_focus_scope(focus_scope);
+        }
+        if let Some(z_index) = attributes.number("z_index")? {
+            builder = builder.with_z_index(z_index);
+        }
+        Ok(builder)
+    }
+
+    /// Returns single child of an element which is used as content of a control.
+    fn content(&mut self, element: &Element) -> Result<Option<Handle<UINode<M, C>>>, MarkupError> {
+        match element.children.as_slice() {
+            [] => Ok(None),
+            [child] => self.load(child).map(Some),
+            _ => Err(MarkupError::TooManyChildren(element.line, element.name.clone())),
+        }
+    }
+
+    fn no_children(element: &Element) -> Result<(), MarkupError> {
+        if element.children.is_empty() {
+            Ok(())
+        } else {
+            Err(MarkupError::TooManyChildren(element.line, element.name.clone()))
+        }
+    }
+
+    fn load(&mut self, element: &Element) -> Result<Handle<UINode<M, C>>, MarkupError> {
+        let mut attributes = Attributes {
+            values: element.attributes.clone(),
+            line: element.line,
+        };
+        let name = element.attributes
+            .iter()
+            .find(|(attribute, _)| attribute == "name")
+            .map(|(_, value)| value.clone());
+        if let Some(name) = name.as_ref() {
+            if self.names.contains_key(name) {
+                return Err(MarkupError::DuplicateName(element.line, name.clone()));
+            }
+        }
+
+        let handle = match element.name.as_str() {
+            "Canvas" | "Grid" | "StackPanel" | "Border" | "Decorator" => {
+                let mut children = Vec::new();
+                for child in element.children.iter() {
+                    children.push(self.load(child)?);
+                }
+                let widget_builder = self.widget_builder(&mut attributes)?.with_children(&children);
+                match element.name.as_str() {
+                    "Canvas" => CanvasBuilder::new(widget_builder).build(self.ui),
+                    "Grid" => {
+                        let mut builder = GridBuilder::new(widget_builder);
+                        if let Some(rows) = attributes.parse("rows", |v| parse_grid_sizes(v, Row::strict, Row::stretch, Row::auto))? {
+                            builder = builder.add_rows(rows);
+                        }
+                        if let Some(columns) = attributes.parse("columns", |v| parse_grid_sizes(v, Column::strict, Column::stretch, Column::auto))? {
+                            builder = builder.add_columns(columns);
+                        }
+                        if let Some(draw_border) = attributes.bool("draw_border")? {
+                            builder = builder.draw_border(draw_border);
+                        }
+                        if let Some(thickness) = attributes.number("border_thickness")? {
+                            builder = builder.with_border_thickness(thickness);
+                        }
+                        builder.build(self.ui)
+                    }
+                    "StackPanel" => {
+                        let mut builder = StackPanelBuilder::new(widget_builder);
+                        if let Some(orientation) = attributes.parse("orientation", parse_orientation)? {
+                            builder = builder.with_orientation(orientation);
+                        }
+                        builder.build(self.ui)
+                    }
+                    "Border" => {
+                        let mut builder = BorderBuilder::new(widget_builder);
+                        if let Some(thickness) = attributes.parse("stroke_thickness", parse_thickness)? {
+                            builder = builder.with_stroke_thickness(thickness);
+                        }
+                        builder.build(self.ui)
+                    }
+                    _ => {
+                        let mut border_builder = BorderBuilder::new(widget_builder);
+                        if let Some(thickness) = attributes.parse("stroke_thickness", parse_thickness)? {
+                            border_builder = border_builder.with_stroke_thickness(thickness);
+                        }
+                        let mut builder = DecoratorBuilder::new(border_builder);
+                        if let Some(brush) = attributes.brush("normal_brush")? {
+                            builder = builder.with_normal_brush(brush);
+                        }
+                        if let Some(brush) = attributes.brush("hover_brush")? {
+                            builder = builder.with_hover_brush(brush);
+                        }
+                        if let Some(brush) = attributes.brush("pressed_brush")? {
+                            builder = builder.with_pressed_brush(brush);
+                        }
+                        if let Some(brush) = attributes.brush("selected_brush")? {
+                            builder = builder.with_selected_brush(brush);
+                        }
+                        builder.build(self.ui)
+                    }
+                }
+            }
+            "Text" => {
+                Self::no_children(element)?;
+                let mut builder = TextBuilder::new(self.widget_builder(&mut attributes)?);
+                if let Some(text) = attributes.take("text") {
+                    builder = builder.with_text(text);
+                }
+                if let Some(markup) = attributes.take("markup") {
+                    builder = builder.with_markup(markup);
+                }
+                if let Some(size) = attributes.number("font_size")? {
+                    builder = builder.with_font_size(size);
+                }
+                if let Some(wrap) = attributes.parse("wrap", parse_wrap)? {
+                    builder = builder.with_wrap_mode(wrap);
+                }
+                if let Some(ellipsis) = attributes.bool("ellipsis")? {
+                    builder = builder.with_ellipsis(ellipsis);
+                }
+                if let Some(spacing) = attributes.number("line_spacing")? {
+                    builder = builder.with_line_spacing(spacing);
+                }
+                if let Some(alignment) = attributes.parse("horizontal_text_alignment", parse_horizontal_alignment)? {
+                    builder = builder.with_horizontal_text_alignment(alignment);
+                }
+                if let Some(alignment) = attributes.parse("vertical_text_alignment", parse_vertical_alignment)? {
+                    builder = builder.with_vertical_text_alignment(alignment);
+                }
+                builder.build(self.ui)
+            }
+            "TextBox" => {
+                Self::no_children(element)?;
+                let mut builder = TextBoxBuilder::new(self.widget_builder(&mut attributes)?);
+                if let Some(text) = attributes.take("text") {
+                    builder = builder.with_text(text);
+                }
+                if let Some(placeholder) = attributes.take("placeholder") {
+                    builder = builder.with_placeholder(placeholder);
+                }
+                if let Some(read_only) = attributes.bool("read_only")? {
+                    builder = builder.with_read_only(read_only);
+                }
+                if let Some(mask_char) = attributes.parse("mask_char", |v| {
+                    let mut chars = v.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => Some(c),
+                        _ => None,
+                    }
+                })? {
+                    builder = builder.with_mask_char(mask_char);
+                }
+                if let Some(brush) = attributes.brush("caret_brush")? {
+                    builder = builder.with_caret_brush(brush);
+                }
+                if let Some(brush) = attributes.brush("selection_brush")? {
+                    builder = builder.with_selection_brush(brush);
+                }
+                builder.build(self.ui)
+            }
+            "Button" => {
+                let content = self.content(element)?;
+                let mut builder = ButtonBuilder::new(self.widget_builder(&mut attributes)?);
+                if let Some(text) = attributes.take("text") {
+                    builder = builder.with_text(&text);
+                }
+                if let Some(content) = content {
+                    builder = builder.with_content(content);
+                }
+                if let Some(brush) = attributes.brush("hover_brush")? {
+                    builder = builder.with_hover_brush(brush);
+                }
+                if let Some(brush) = attributes.brush("pressed_brush")? {
+                    builder = builder.with_pressed_brush(brush);
+                }
+                builder.build(self.ui)
+            }
+            "CheckBox" => {
+                Self::no_children(element)?;
+                let mut builder = CheckBoxBuilder::new(self.widget_builder(&mut attributes)?);
+                if let Some(checked) = attributes.parse("checked", |v| {
+                    if v == "none" { Some(None) } else { parse_bool(v).map(Some) }
+                })? {
+                    builder = builder.checked(checked);
+                }
+                builder.build(self.ui)
+            }
+            "ScrollBar" => {
+                Self::no_children(element)?;
+                let mut builder = ScrollBarBuilder::new(self.widget_builder(&mut attributes)?);
+                if let Some(min) = attributes.number("min")? {
+                    builder = builder.with_min(min);
+                }
+                if let Some(max) = attributes.number("max")? {
+                    builder = builder.with_max(max);
+                }
+                if let Some(value) = attributes.number("value")? {
+                    builder = builder.with_value(value);
+                }
+                if let Some(step) = attributes.number("step")? {
+                    builder = builder.with_step(step);
+                }
+                if let Some(orientation) = attributes.parse("orientation", parse_orientation)? {
+                    builder = builder.with_orientation(orientation);
+                }
+                if let Some(show_value) = attributes.bool("show_value")? {
+                    builder = builder.show_value(show_value);
+                }
+                if let Some(precision) = attributes.number("value_precision")? {
+                    builder = builder.with_value_precision(precision);
+                }
+                builder.build(self.ui)
+            }
+            "ScrollViewer" => {
+                let content = self.content(element)?;
+                let mut builder = ScrollViewerBuilder::new(self.widget_builder(&mut attributes)?);
+                if let Some(content) = content {
+                    builder = builder.with_content(content);
+                }
+                builder.build(self.ui)
+            }
+            "ProgressBar" => {
+                Self::no_children(element)?;
+                let mut builder = ProgressBarBuilder::new(self.widget_builder(&mut attributes)?);
+                if let Some(progress) = attributes.number("progress")? {
+                    builder = builder.with_progress(progress);
+                }
+                builder.build(self.ui)
+            }
+            "Window" => {
+                let content = self.content(element)?;
+                let title = attributes.take("title");
+                let mut builder = WindowBuilder::new(self.widget_builder(&mut attributes)?);
+                if let Some(title) = title.as_ref() {
+                    builder = builder.with_title(WindowTitle::Text(title));
+                }
+                if let Some(content) = content {
+                    builder = builder.with_content(content);
+                }
+                if let Some(can_close) = attributes.bool("can_close")? {
+                    builder = builder.can_close(can_close);
+                }
+                if let Some(can_minimize) = attributes.bool("can_minimize")? {
+                    builder = builder.can_minimize(can_minimize);
+                }
+                if let Some(open) = attributes.bool("open")? {
+                    builder = builder.open(open);
+                }
+                builder.build(self.ui)
+            }
+            _ => return Err(MarkupError::UnknownElement(element.line, element.name.clone())),
+        };
+
+        attributes.finish()?;
+        if let Some(name) = name {
+            self.names.insert(name, handle);
+        }
+        Ok(handle)
+    }
+}
+
+/// Builds tree of nodes described by markup and returns handles of named nodes. On error
+/// every node built so far is removed, so UI stays as it was.
+pub fn load_markup<M, C: 'static + Control<M, C>>(ui: &mut UserInterface<M, C>, markup: &str) -> Result<HashMap<String, Handle<UINode<M, C>>>, MarkupError> {
+    let root = Parser { text: markup, pos: 0, line: 1 }.document()?;
+    // Every new node is linked to root until it is attached to its parent, so nodes
+    // built by the loader are new children of root and their descendants.
+    let existing: HashSet<Handle<UINode<M, C>>> = ui.node(ui.root()).widget().children().iter().cloned().collect();
+    let mut loader = Loader {
+        ui,
+        names: HashMap::new(),
+    };
+    match loader.load(&root) {
+        Ok(_) => Ok(loader.names),
+        Err(error) => {
+            let built: Vec<Handle<UINode<M, C>>> = loader.ui.node(loader.ui.root())
+                .widget()
+                .children()
+                .iter()
+                .filter(|handle| !existing.contains(handle))
+                .cloned()
+                .collect();
+            for handle in built {
+                loader.ui.remove_node(handle);
+            }
+            Err(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        markup::{load_markup, MarkupError},
+        test::{StubUiMessage, StubUiNode},
+        UserInterface,
+        node::UINode,
+        brush::Brush,
+        scroll_bar::Orientation,
+        core::color::Color,
+    };
+
+    type Ui = UserInterface<StubUiMessage, StubUiNode>;
+
+    #[test]
+    fn load_tree() {
+        let mut ui = Ui::new();
+        let names = load_markup(&mut ui, r##"
+            <?xml version="1.0"?>
+            <!-- Dialog -->
+            <Grid name="root" width="400" height="300" rows="30 * auto" columns="auto *">
+                <Text row="0" text="Name &amp; surname:" vertical_text_alignment="center"/>
+                <TextBox name="name" row="0" column="1" margin="2 1" background="#202020"/>
+                <StackPanel name="buttons" row="1" column="1" orientation='horizontal'>
+                    <Button name="ok" width="100" text="OK"/>
+                    <Button name="cancel" width="100">
+                        <Text text="Cancel"/>
+                    </Button>
+                </StackPanel>
+                <Border name="bar" row="2" background="linear(0.5 0; 0.5 1; 0 #555; 1 #444)"/>
+            </Grid>
+        "##).unwrap();
+
+        assert_eq!(names.len(), 6);
+        let root = names["root"];
+        assert!((ui.node(root).widget().width() - 400.0).abs() < std::f32::EPSILON);
+        assert!(ui.node(root).widget().children().contains(&names["buttons"]));
+        if let UINode::StackPanel(stack_panel) = ui.node(names["buttons"]) {
+            assert_eq!(stack_panel.orientation(), Orientation::Horizontal);
+        } else {
+            panic!("expected stack panel");
+        }
+        if let Brush::Solid(color) = ui.node(names["name"]).widget().background() {
+            assert_eq!(color, Color::opaque(32, 32, 32));
+        } else {
+            panic!("expected solid brush");
+        }
+    }
+
+    fn load_error(markup: &str) -> MarkupError {
+        let mut ui = Ui::new();
+        let error = load_markup(&mut ui, markup).unwrap_err();
+        // Nodes built before the error must be removed.
+        assert!(ui.node(ui.root()).widget().children().is_empty());
+        error
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(load_error("<Grid>\n<Foo/>\n</Grid>"),
+                   MarkupError::UnknownElement(2, "Foo".to_owned()));
+        assert_eq!(load_error("<Border\n thickness=\"1\"/>"),
+                   MarkupError::UnknownAttribute(1, "thickness".to_owned()));
+        assert_eq!(load_error("<Border width=\"wide\"/>"),
+                   MarkupError::InvalidValue(1, "width".to_owned()));
+        assert_eq!(load_error("<Canvas>\n<Text name=\"a\"/>\n<Text name=\"a\"/>\n</Canvas>"),
+                   MarkupError::DuplicateName(3, "a".to_owned()));
+        assert_eq!(load_error("<Button><Text/><Text/></Button>"),
+                   MarkupError::TooManyChildren(1, "Button".to_owned()));
+        match load_error("<Grid>\n</Canvas>") {
+            MarkupError::Syntax(2, _) => (),
+            other => panic!("unexpected error {:?}", other),
+        }
+    }
+}
@@ -17,11 +17,12 @@ use crate::{
     Thickness,
     brush::Brush,
     Control,
+    NavigationDirection,
     popup::Placement
 };
 
 #[derive(Debug)]
-pub enum WidgetProperty {
+pub enum WidgetProperty<M: 'static, C: 'static + Control<M, C>> {
     Background(Brush),
     Foreground(Brush),
     Name(String),
@@ -37,33 +38,53 @@ pub enum WidgetProperty {
     HitTestVisibility(bool),
     Visibility(bool),
     ZIndex(usize),
-    DesiredPosition(Vec2)
+    DesiredPosition(Vec2),
+    Focusable(bool),
+    TabIndex(Option<usize>),
+    FocusScope(bool),
+    NavigationTarget {
+        direction: NavigationDirection,
+        target: Handle<UINode<M, C>>,
+    },
 }
 
 #[derive(Debug)]
-pub enum WidgetMessage {
+pub enum WidgetMessage<M: 'static, C: 'static + Control<M, C>> {
     MouseDown {
         pos: Vec2,
         button: MouseButton,
+        modifiers: KeyboardModifiers,
     },
     MouseUp {
         pos: Vec2,
         button: MouseButton,
+        modifiers: KeyboardModifiers,
     },
     MouseMove(Vec2),
     Text(char),
-    KeyDown(KeyCode),
-    KeyUp(KeyCode),
+    KeyDown {
+        code: KeyCode,
+        modifiers: KeyboardModifiers,
+    },
+    KeyUp {
+        code: KeyCode,
+        modifiers: KeyboardModifiers,
+    },
     MouseWheel {
         pos: Vec2,
         amount: f32,
+        modifiers: KeyboardModifiers,
     },
     GotFocus,
     LostFocus,
     MouseLeave,
     MouseEnter,
     TopMost,
-    Property(WidgetProperty)
+    Property(WidgetProperty<M, C>),
+    /// Accept or Cancel navigation input that was sent to focused node.
+    Navigation(NavigationInput),
+    /// Input method composition event that was sent to focused node.
+    Composition(CompositionEvent),
 }
 
 #[derive(Debug)]
@@ -112,9 +133,19 @@ pub enum PopupMessage<M: 'static, C: 'static + Control<M, C>> {
     Placement(Placement)
 }
 
+#[derive(Debug)]
+pub enum TextBoxMessage {
+    /// Text was changed, contains new text. Posted by text box itself.
+    TextChanged(String),
+    /// Enter was pressed, contains current text. Posted by text box itself.
+    Submitted(String),
+    /// Replaces text of a text box, must be targeted to a text box.
+    Text(String),
+}
+
 #[derive(Debug)]
 pub enum UiMessageData<M: 'static, C: 'static + Control<M, C>> {
-    Widget(WidgetMessage),
+    Widget(WidgetMessage<M, C>),
     Button(ButtonMessage<M, C>),
     ScrollBar(ScrollBarMessage),
     CheckBox(CheckBoxMessage),
@@ -122,15 +153,34 @@ pub enum UiMessageData<M: 'static, C: 'static + Control<M, C>> {
     ItemsControl(ItemsControlMessage<M, C>),
     Popup(PopupMessage<M, C>),
     ScrollViewer(ScrollViewerMessage<M, C>),
+    TextBox(TextBoxMessage),
     User(M),
 }
 
+/// Defines the way a message travels over the tree of nodes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RoutingStrategy {
+    /// Message first goes down from root node to its destination (tunneling), each node
+    /// on the way receives it in `preview_message`. Then it goes up from destination back
+    /// to root (bubbling), each node on the way receives it in `handle_message`. Destination
+    /// is `target` if message has one, otherwise `source`. Message that has neither of them
+    /// is broadcast. Message for a node that no longer exists is not delivered.
+    BubbleUp,
+
+    /// Message will be passed to `handle_message` of *every* node. This is much slower than
+    /// bubbling and should be used only for messages that must be seen by nodes outside of
+    /// the route, for example items which are reacting on selection change of parent
+    /// items control.
+    Broadcast,
+}
+
 /// Event is basic communication element that is used to deliver information to UI nodes
 /// or to user code.
 #[derive(Debug)]
 pub struct UiMessage<M: 'static, C: 'static + Control<M, C>> {
-    /// Useful flag to check if a message was already handled, this flag does *not* affects
-    /// dispatcher.
+    /// Flag that indicates that a message was already handled by some node. Once set,
+    /// message stops propagating over the route, however it still will be returned
+    /// to user code from `poll_message`.
     pub handled: bool,
 
     /// Actual message data. Use pattern matching to get node-specific data.
@@ -146,6 +196,9 @@ pub struct UiMessage<M: 'static, C: 'static + Control<M, C>> {
     /// Source of event. Can be NONE if event is targeted, however if there is source and target
     /// both present, then it means node-to-node communication.
     pub(in crate) source: Handle<UINode<M, C>>,
+
+    /// Defines how message will be delivered to nodes.
+    pub(in crate) routing: RoutingStrategy,
 }
 
 impl<M, C: 'static + Control<M, C>> UiMessage<M, C> {
@@ -156,6 +209,7 @@ impl<M, C: 'static + Control<M, C>> UiMessage<M, C> {
             handled: false,
             source: Handle::NONE,
             target,
+            routing: RoutingStrategy::BubbleUp,
         }
     }
 
@@ -166,6 +220,19 @@ impl<M, C: 'static + Control<M, C>> UiMessage<M, C> {
             handled: false,
             source: Handle::NONE,
             target: Handle::NONE,
+            routing: RoutingStrategy::BubbleUp,
+        }
+    }
+
+    /// Creates new message that will be delivered to every node in the tree.
+    #[inline]
+    pub fn broadcast(kind: UiMessageData<M, C>) -> Self {
+        Self {
+            data: kind,
+            handled: false,
+            source: Handle::NONE,
+            target: Handle::NONE,
+            routing: RoutingStrategy::Broadcast,
         }
     }
 
@@ -178,6 +245,22 @@ impl<M, C: 'static + Control<M, C>> UiMessage<M, C> {
     pub fn source(&self) -> Handle<UINode<M, C>> {
         self.source
     }
+
+    /// Returns handle of a node at which routing of a message starts (bubbling) or ends
+    /// (tunneling).
+    #[inline]
+    pub fn destination(&self) -> Handle<UINode<M, C>> {
+        if self.target.is_some() {
+            self.target
+        } else {
+            self.source
+        }
+    }
+
+    #[inline]
+    pub fn routing(&self) -> RoutingStrategy {
+        self.routing
+    }
 }
 
 #[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
@@ -194,6 +277,55 @@ pub enum MouseButton {
     Other(u8),
 }
 
+/// State of keyboard modifier keys.
+#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy, Default)]
+pub struct KeyboardModifiers {
+    pub alt: bool,
+    pub shift: bool,
+    pub control: bool,
+    /// Windows key on Windows, Command key on macOS.
+    pub system: bool,
+}
+
+impl KeyboardModifiers {
+    /// Returns true if no modifier key is pressed.
+    pub fn is_none(self) -> bool {
+        !self.alt && !self.shift && !self.control && !self.system
+    }
+}
+
+/// Abstract navigation input which host application can map gamepad (or any other
+/// device) onto.
+#[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
+pub enum NavigationInput {
+    Up,
+    Down,
+    Left,
+    Right,
+    Accept,
+    Cancel,
+}
+
+/// Stages of text composition made by input method editor (IME), used to enter text
+/// in languages like Chinese or Japanese.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompositionEvent {
+    /// Composition has started.
+    Start,
+    /// Composition (preedit) text has changed. `cursor` is a position of a cursor inside
+    /// of the text in characters, if input method provides one.
+    Update {
+        text: String,
+        cursor: Option<usize>,
+    },
+    /// Composition is finished and text must be inserted. Host application must not send
+    /// `OsEvent::Character` events for the same text.
+    Commit(String),
+    /// Composition was cancelled, preedit text must be discarded.
+    Cancel,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum OsEvent {
     MouseInput {
         button: MouseButton,
@@ -208,6 +340,13 @@ pub enum OsEvent {
     },
     Character(char),
     MouseWheel(f32, f32),
+    Navigation(NavigationInput),
+    /// State of modifier keys has changed. Host application can send this event if its
+    /// windowing library tracks modifiers by itself, otherwise state of modifiers is tracked
+    /// by `KeyboardInput` events of modifier keys.
+    KeyboardModifiers(KeyboardModifiers),
+    /// Input method composition event, it is delivered to node with keyboard focus.
+    Composition(CompositionEvent),
 }
 
 #[derive(Debug, Hash, Ord, PartialOrd, PartialEq, Eq, Clone, Copy)]
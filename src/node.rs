@@ -37,7 +37,8 @@ use crate::{
     items_control::{ItemsControl, ItemContainer},
     decorator::Decorator,
     NodeHandleMapping,
-    progress_bar::ProgressBar
+    progress_bar::ProgressBar,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 #[allow(clippy::large_enum_variant)]
@@ -142,6 +143,10 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for UINode<M, C> {
         static_dispatch!(self, handle_message, self_handle, ui, message)
     }
 
+    fn preview_message(&mut self, self_handle: Handle<UINode<M, C>>, ui: &mut UserInterface<M, C>, message: &mut UiMessage<M, C>) {
+        static_dispatch!(self, preview_message, self_handle, ui, message)
+    }
+
     fn handle_os_event(&mut self, self_handle: Handle<UINode<M, C>>, ui: &mut UserInterface<M, C>, event: &OsEvent) {
         static_dispatch!(self, handle_os_event, self_handle, ui, event)
     }
@@ -149,6 +154,17 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for UINode<M, C> {
     fn remove_ref(&mut self, handle: Handle<UINode<M, C>>) {
         static_dispatch!(self, remove_ref, handle)
     }
+
+    fn caret_rect(&self) -> Option<Rect<f32>> {
+        static_dispatch!(self, caret_rect,)
+    }
+}
+
+
+impl<M, C: 'static + Control<M, C> + Visit<M, C>> Visit<M, C> for UINode<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        static_dispatch!(self, visit, visitor)
+    }
 }
 
 
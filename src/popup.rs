@@ -13,6 +13,7 @@ use crate::{
         WidgetMessage,
         OsEvent,
         ButtonState,
+        NavigationInput,
     },
     core::{
         pool::Handle,
@@ -20,6 +21,7 @@ use crate::{
     },
     border::BorderBuilder,
     NodeHandleMapping,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 #[derive(Copy, Clone, PartialEq, Debug)]
@@ -145,6 +147,12 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for Popup<M, C> {
                     _ => {}
                 }
             }
+            UiMessageData::Widget(WidgetMessage::Navigation(NavigationInput::Cancel)) => {
+                if self.is_open && !self.stays_open {
+                    self.close();
+                    message.handled = true;
+                }
+            }
             _ => {}
         }
     }
@@ -185,6 +193,30 @@ impl<M, C: 'static + Control<M, C>> Popup<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Default for Popup<M, C> {
+    fn default() -> Self {
+        Self {
+            widget: Widget::default(),
+            placement: Placement::Cursor,
+            stays_open: false,
+            is_open: false,
+            content: Handle::NONE,
+            body: Handle::NONE,
+        }
+    }
+}
+
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for Popup<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit("placement", &mut self.placement)?;
+        visitor.visit("stays_open", &mut self.stays_open)?;
+        visitor.visit("is_open", &mut self.is_open)?;
+        visitor.visit_handle("content", &mut self.content)?;
+        visitor.visit_handle("body", &mut self.body)
+    }
+}
+
 pub struct PopupBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     placement: Placement,
@@ -217,7 +249,11 @@ impl<M, C: 'static + Control<M, C>> PopupBuilder<M, C> {
         self
     }
 
-    pub fn build(self, ui: &mut UserInterface<M, C>) -> Handle<UINode<M, C>> where Self: Sized {
+    pub fn build(mut self, ui: &mut UserInterface<M, C>) -> Handle<UINode<M, C>> where Self: Sized {
+        if self.widget_builder.focus_scope.is_none() {
+            self.widget_builder.focus_scope = Some(true);
+        }
+
         let body = BorderBuilder::new(WidgetBuilder::new()
             .with_child(self.content))
             .build(ui);
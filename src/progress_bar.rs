@@ -20,6 +20,7 @@ use crate::{
         color::Color
     },
     brush::Brush,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 pub struct ProgressBar<M: 'static, C: 'static + Control<M, C>> {
@@ -81,6 +82,26 @@ impl<M: 'static, C: 'static + Control<M, C>> ProgressBar<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Default for ProgressBar<M, C> {
+    fn default() -> Self {
+        Self {
+            widget: Widget::default(),
+            progress: 0.0,
+            indicator: Handle::NONE,
+            body: Handle::NONE,
+        }
+    }
+}
+
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for ProgressBar<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit("progress", &mut self.progress)?;
+        visitor.visit_handle("indicator", &mut self.indicator)?;
+        visitor.visit_handle("body", &mut self.body)
+    }
+}
+
 pub struct ProgressBarBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     body: Option<Handle<UINode<M, C>>>,
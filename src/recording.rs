@@ -0,0 +1,635 @@
+// Recording of input of user interface. Recording contains every OS event passed to
+// `UserInterface::process_os_event` and every `UserInterface::update` with its screen size
+// and time delta, in the order they were made. It can be saved as text and replayed later
+// against freshly built UI, which reproduces same sequence of messages.
+//
+// Text format is line based, first line is a header, every other line is one event:
+//
+// rg3d-ui recording 1
+// update 800 600 0.016
+// cursor 10 20.5
+// mouse left pressed
+// key A released
+// char a
+// wheel 0 -1
+// nav Accept
+// modifiers shift control
+// composition update 2 text
+
+use crate::{
+    UserInterface,
+    Control,
+    message::{
+        OsEvent,
+        UiMessage,
+        ButtonState,
+        MouseButton,
+        KeyCode,
+        KeyboardModifiers,
+        NavigationInput,
+        CompositionEvent,
+    },
+    core::math::vec2::Vec2,
+};
+use std::{
+    fmt,
+    fs,
+    path::Path,
+    str::FromStr,
+};
+
+const HEADER: &str = "rg3d-ui recording 1";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RecordedEvent {
+    /// Call of `UserInterface::update`.
+    Update {
+        screen_size: Vec2,
+        dt: f32,
+    },
+    /// Call of `UserInterface::process_os_event`.
+    Os(OsEvent),
+}
+
+/// Error that can occur while loading a recording.
+#[derive(Debug)]
+pub enum RecordingError {
+    /// Recording file cannot be read or written.
+    Io(std::io::Error),
+    /// Text does not start with recording header.
+    InvalidHeader,
+    /// Line cannot be parsed, contains number of the line starting from 1.
+    InvalidEvent(usize),
+}
+
+impl From<std::io::Error> for RecordingError {
+    fn from(e: std::io::Error) -> Self {
+        RecordingError::Io(e)
+    }
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordingError::Io(e) => write!(f, "unable to read or write recording: {}", e),
+            RecordingError::InvalidHeader => write!(f, "text is not an input recording"),
+            RecordingError::InvalidEvent(line) => write!(f, "invalid event at line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InputRecording {
+    events: Vec<RecordedEvent>,
+}
+
+impl InputRecording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, event: RecordedEvent) {
+        self.events.push(event);
+    }
+
+    pub fn events(&self) -> &[RecordedEvent] {
+        &self.events
+    }
+
+    /// Feeds recorded events to UI and returns every message it has produced. Messages are
+    /// polled after each update, like a game loop does, and once more after last event.
+    pub fn replay<M, C: 'static + Control<M, C>>(&self, ui: &mut UserInterface<M, C>) -> Vec<UiMessage<M, C>> {
+        let mut messages = Vec::new();
+        for event in self.events.iter() {
+            match event {
+                RecordedEvent::Update { screen_size, dt } => {
+                    ui.update(*screen_size, *dt);
+                    while let Some(message) = ui.poll_message() {
+                        messages.push(message);
+                    }
+                }
+                RecordedEvent::Os(event) => {
+                    ui.process_os_event(event);
+                }
+            }
+        }
+        while let Some(message) = ui.poll_message() {
+            messages.push(message);
+        }
+        messages
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), RecordingError> {
+        fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, RecordingError> {
+        fs::read_to_string(path)?.parse()
+    }
+}
+
+pub(in crate) fn escape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+pub(in crate) fn unescape(text: &str) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            result.push(match chars.next()? {
+                '\\' => '\\',
+                'n' => '\n',
+                'r' => '\r',
+                't' => '\t',
+                _ => return None,
+            });
+        } else {
+            result.push(c);
+        }
+    }
+    Some(result)
+}
+
+fn button_state_name(state: ButtonState) -> &'static str {
+    match state {
+        ButtonState::Pressed => "pressed",
+        ButtonState::Released => "released",
+    }
+}
+
+fn parse_button_state(text: &str) -> Option<ButtonState> {
+    match text {
+        "pressed" => Some(ButtonState::Pressed),
+        "released" => Some(ButtonState::Released),
+        _ => None,
+    }
+}
+
+fn parse_mouse_button(text: &str) -> Option<MouseButton> {
+    match text {
+        "left" => Some(MouseButton::Left),
+        "right" => Some(MouseButton::Right),
+        "middle" => Some(MouseButton::Middle),
+        _ => text.parse().ok().map(MouseButton::Other),
+    }
+}
+
+/// Names of key codes in text of recording, same as names of variants.
+const KEY_CODE_NAMES: &[(KeyCode, &str)] = &[
+    (KeyCode::Key1, "Key1"),
+    (KeyCode::Key2, "Key2"),
+    (KeyCode::Key3, "Key3"),
+    (KeyCode::Key4, "Key4"),
+    (KeyCode::Key5, "Key5"),
+    (KeyCode::Key6, "Key6"),
+    (KeyCode::Key7, "Key7"),
+    (KeyCode::Key8, "Key8"),
+    (KeyCode::Key9, "Key9"),
+    (KeyCode::Key0, "Key0"),
+    (KeyCode::A, "A"),
+    (KeyCode::B, "B"),
+    (KeyCode::C, "C"),
+    (KeyCode::D, "D"),
+    (KeyCode::E, "E"),
+    (KeyCode::F, "F"),
+    (KeyCode::G, "G"),
+    (KeyCode::H, "H"),
+    (KeyCode::I, "I"),
+    (KeyCode::J, "J"),
+    (KeyCode::K, "K"),
+    (KeyCode::L, "L"),
+    (KeyCode::M, "M"),
+    (KeyCode::N, "N"),
+    (KeyCode::O, "O"),
+    (KeyCode::P, "P"),
+    (KeyCode::Q, "Q"),
+    (KeyCode::R, "R"),
+    (KeyCode::S, "S"),
+    (KeyCode::T, "T"),
+    (KeyCode::U, "U"),
+    (KeyCode::V, "V"),
+    (KeyCode::W, "W"),
+    (KeyCode::X, "X"),
+    (KeyCode::Y, "Y"),
+    (KeyCode::Z, "Z"),
+    (KeyCode::Escape, "Escape"),
+    (KeyCode::F1, "F1"),
+    (KeyCode::F2, "F2"),
+    (KeyCode::F3, "F3"),
+    (KeyCode::F4, "F4"),
+    (KeyCode::F5, "F5"),
+    (KeyCode::F6, "F6"),
+    (KeyCode::F7, "F7"),
+    (KeyCode::F8, "F8"),
+    (KeyCode::F9, "F9"),
+    (KeyCode::F10, "F10"),
+    (KeyCode::F11, "F11"),
+    (KeyCode::F12, "F12"),
+    (KeyCode::F13, "F13"),
+    (KeyCode::F14, "F14"),
+    (KeyCode::F15, "F15"),
+    (KeyCode::F16, "F16"),
+    (KeyCode::F17, "F17"),
+    (KeyCode::F18, "F18"),
+    (KeyCode::F19, "F19"),
+    (KeyCode::F20, "F20"),
+    (KeyCode::F21, "F21"),
+    (KeyCode::F22, "F22"),
+    (KeyCode::F23, "F23"),
+    (KeyCode::F24, "F24"),
+    (KeyCode::Snapshot, "Snapshot"),
+    (KeyCode::Scroll, "Scroll"),
+    (KeyCode::Pause, "Pause"),
+    (KeyCode::Insert, "Insert"),
+    (KeyCode::Home, "Home"),
+    (KeyCode::Delete, "Delete"),
+    (KeyCode::End, "End"),
+    (KeyCode::PageDown, "PageDown"),
+    (KeyCode::PageUp, "PageUp"),
+    (KeyCode::Left, "Left"),
+    (KeyCode::Up, "Up"),
+    (KeyCode::Right, "Right"),
+    (KeyCode::Down, "Down"),
+    (KeyCode::Backspace, "Backspace"),
+    (KeyCode::Return, "Return"),
+    (KeyCode::Space, "Space"),
+    (KeyCode::Compose, "Compose"),
+    (KeyCode::Caret, "Caret"),
+    (KeyCode::Numlock, "Numlock"),
+    (KeyCode::Numpad0, "Numpad0"),
+    (KeyCode::Numpad1, "Numpad1"),
+    (KeyCode::Numpad2, "Numpad2"),
+    (KeyCode::Numpad3, "Numpad3"),
+    (KeyCode::Numpad4, "Numpad4"),
+    (KeyCode::Numpad5, "Numpad5"),
+    (KeyCode::Numpad6, "Numpad6"),
+    (KeyCode::Numpad7, "Numpad7"),
+    (KeyCode::Numpad8, "Numpad8"),
+    (KeyCode::Numpad9, "Numpad9"),
+    (KeyCode::AbntC1, "AbntC1"),
+    (KeyCode::AbntC2, "AbntC2"),
+    (KeyCode::Add, "Add"),
+    (KeyCode::Apostrophe, "Apostrophe"),
+    (KeyCode::Apps, "Apps"),
+    (KeyCode::At, "At"),
+    (KeyCode::Ax, "Ax"),
+    (KeyCode::Backslash, "Backslash"),
+    (KeyCode::Calculator, "Calculator"),
+    (KeyCode::Capital, "Capital"),
+    (KeyCode::Colon, "Colon"),
+    (KeyCode::Comma, "Comma"),
+    (KeyCode::Convert, "Convert"),
+    (KeyCode::Decimal, "Decimal"),
+    (KeyCode::Divide, "Divide"),
+    (KeyCode::Equals, "Equals"),
+    (KeyCode::Grave, "Grave"),
+    (KeyCode::Kana, "Kana"),
+    (KeyCode::Kanji, "Kanji"),
+    (KeyCode::LAlt, "LAlt"),
+    (KeyCode::LBracket, "LBracket"),
+    (KeyCode::LControl, "LControl"),
+    (KeyCode::LShift, "LShift"),
+    (KeyCode::LWin, "LWin"),
+    (KeyCode::Mail, "Mail"),
+    (KeyCode::MediaSelect, "MediaSelect"),
+    (KeyCode::MediaStop, "MediaStop"),
+    (KeyCode::Minus, "Minus"),
+    (KeyCode::Multiply, "Multiply"),
+    (KeyCode::Mute, "Mute"),
+    (KeyCode::MyComputer, "MyComputer"),
+    (KeyCode::NavigateForward, "NavigateForward"),
+    (KeyCode::NavigateBackward, "NavigateBackward"),
+    (KeyCode::NextTrack, "NextTrack"),
+    (KeyCode::NoConvert, "NoConvert"),
+    (KeyCode::NumpadComma, "NumpadComma"),
+    (KeyCode::NumpadEnter, "NumpadEnter"),
+    (KeyCode::NumpadEquals, "NumpadEquals"),
+    (KeyCode::OEM102, "OEM102"),
+    (KeyCode::Period, "Period"),
+    (KeyCode::PlayPause, "PlayPause"),
+    (KeyCode::Power, "Power"),
+    (KeyCode::PrevTrack, "PrevTrack"),
+    (KeyCode::RAlt, "RAlt"),
+    (KeyCode::RBracket, "RBracket"),
+    (KeyCode::RControl, "RControl"),
+    (KeyCode::RShift, "RShift"),
+    (KeyCode::RWin, "RWin"),
+    (KeyCode::Semicolon, "Semicolon"),
+    (KeyCode::Slash, "Slash"),
+    (KeyCode::Sleep, "Sleep"),
+    (KeyCode::Stop, "Stop"),
+    (KeyCode::Subtract, "Subtract"),
+    (KeyCode::Sysrq, "Sysrq"),
+    (KeyCode::Tab, "Tab"),
+    (KeyCode::Underline, "Underline"),
+    (KeyCode::Unlabeled, "Unlabeled"),
+    (KeyCode::VolumeDown, "VolumeDown"),
+    (KeyCode::VolumeUp, "VolumeUp"),
+    (KeyCode::Wake, "Wake"),
+    (KeyCode::WebBack, "WebBack"),
+    (KeyCode::WebFavorites, "WebFavorites"),
+    (KeyCode::WebForward, "WebForward"),
+    (KeyCode::WebHome, "WebHome"),
+    (KeyCode::WebRefresh, "WebRefresh"),
+    (KeyCode::WebSearch, "WebSearch"),
+    (KeyCode::WebStop, "WebStop"),
+    (KeyCode::Yen, "Yen"),
+    (KeyCode::Copy, "Copy"),
+    (KeyCode::Paste, "Paste"),
+    (KeyCode::Cut, "Cut"),
+];
+
+fn key_code_name(code: KeyCode) -> &'static str {
+    KEY_CODE_NAMES
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map_or("Unknown", |(_, name)| name)
+}
+
+fn parse_key_code(text: &str) -> Option<KeyCode> {
+    KEY_CODE_NAMES
+        .iter()
+        .find(|(_, name)| *name == text)
+        .map(|(code, _)| *code)
+}
+
+fn parse_navigation(text: &str) -> Option<NavigationInput> {
+    match text {
+        "Up" => Some(NavigationInput::Up),
+        "Down" => Some(NavigationInput::Down),
+        "Left" => Some(NavigationInput::Left),
+        "Right" => Some(NavigationInput::Right),
+        "Accept" => Some(NavigationInput::Accept),
+        "Cancel" => Some(NavigationInput::Cancel),
+        _ => None,
+    }
+}
+
+impl fmt::Display for RecordedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let event = match self {
+            RecordedEvent::Update { screen_size, dt } => {
+                return write!(f, "update {} {} {}", screen_size.x, screen_size.y, dt);
+            }
+            RecordedEvent::Os(event) => event,
+        };
+        match event {
+            OsEvent::MouseInput { button, state } => {
+                let state = button_state_name(*state);
+                match button {
+                    MouseButton::Left => write!(f, "mouse left {}", state),
+                    MouseButton::Right => write!(f, "mouse right {}", state),
+                    MouseButton::Middle => write!(f, "mouse middle {}", state),
+                    MouseButton::Other(index) => write!(f, "mouse {} {}", index, state),
+                }
+            }
+            OsEvent::CursorMoved { position } => write!(f, "cursor {} {}", position.x, position.y),
+            OsEvent::KeyboardInput { button, state } => write!(f, "key {} {}", key_code_name(*button), button_state_name(*state)),
+            OsEvent::Character(c) => write!(f, "char {}", escape(&c.to_string())),
+            OsEvent::MouseWheel(x, y) => write!(f, "wheel {} {}", x, y),
+            OsEvent::Navigation(input) => write!(f, "nav {:?}", input),
+            OsEvent::KeyboardModifiers(modifiers) => {
+                write!(f, "modifiers")?;
+                let flags = [
+                    (modifiers.alt, "alt"),
+                    (modifiers.shift, "shift"),
+                    (modifiers.control, "control"),
+                    (modifiers.system, "system"),
+                ];
+                for (_, name) in flags.iter().filter(|(state, _)| *state) {
+                    write!(f, " {}", name)?;
+                }
+                Ok(())
+            }
+            OsEvent::Composition(composition) => match composition {
+                CompositionEvent::Start => write!(f, "composition start"),
+                CompositionEvent::Update { text, cursor: Some(cursor) } => write!(f, "composition update {} {}", cursor, escape(text)),
+                CompositionEvent::Update { text, cursor: None } => write!(f, "composition update - {}", escape(text)),
+                CompositionEvent::Commit(text) => write!(f, "composition commit {}", escape(text)),
+                CompositionEvent::Cancel => write!(f, "composition cancel"),
+            },
+        }
+    }
+}
+
+impl FromStr for RecordedEvent {
+    type Err = ();
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let (kind, args) = match line.find(' ') {
+            Some(pos) => (&line[..pos], &line[pos + 1..]),
+            None => (line, ""),
+        };
+        let mut words = args.split_whitespace();
+        let mut next_f32 = || -> Result<f32, ()> { words.next().ok_or(())?.parse().map_err(|_| ()) };
+
+        let event = match kind {
+            "update" => {
+                let screen_size = Vec2::new(next_f32()?, next_f32()?);
+                return Ok(RecordedEvent::Update { screen_size, dt: next_f32()? });
+            }
+            "cursor" => OsEvent::CursorMoved { position: Vec2::new(next_f32()?, next_f32()?) },
+            "wheel" => OsEvent::MouseWheel(next_f32()?, next_f32()?),
+            "mouse" | "key" => {
+                let mut words = args.split_whitespace();
+                let (button, state) = (words.next().ok_or(())?, words.next().ok_or(())?);
+                let state = parse_button_state(state).ok_or(())?;
+                if kind == "mouse" {
+                    OsEvent::MouseInput { button: parse_mouse_button(button).ok_or(())?, state }
+                } else {
+                    OsEvent::KeyboardInput { button: parse_key_code(button).ok_or(())?, state }
+                }
+            }
+            "char" => {
+                let text = unescape(args).ok_or(())?;
+                let mut chars = text.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => OsEvent::Character(c),
+                    _ => return Err(()),
+                }
+            }
+            "nav" => OsEvent::Navigation(parse_navigation(args.trim()).ok_or(())?),
+            "modifiers" => {
+                let mut modifiers = KeyboardModifiers::default();
+                for name in args.split_whitespace() {
+                    match name {
+                        "alt" => modifiers.alt = true,
+                        "shift" => modifiers.shift = true,
+                        "control" => modifiers.control = true,
+                        "system" => modifiers.system = true,
+                        _ => return Err(()),
+                    }
+                }
+                OsEvent::KeyboardModifiers(modifiers)
+            }
+            "composition" => {
+                let (stage, rest) = match args.find(' ') {
+                    Some(pos) => (&args[..pos], &args[pos + 1..]),
+                    None => (args, ""),
+                };
+                OsEvent::Composition(match stage {
+                    "start" => CompositionEvent::Start,
+                    "cancel" => CompositionEvent::Cancel,
+                    "commit" => CompositionEvent::Commit(unescape(rest).ok_or(())?),
+                    "update" => {
+                        let (cursor, text) = match rest.find(' ') {
+                            Some(pos) => (&rest[..pos], &rest[pos + 1..]),
+                            None => (rest, ""),
+                        };
+                        let cursor = if cursor == "-" { None } else { Some(cursor.parse().map_err(|_| ())?) };
+                        CompositionEvent::Update { text: unescape(text).ok_or(())?, cursor }
+                    }
+                    _ => return Err(()),
+                })
+            }
+            _ => return Err(()),
+        };
+        Ok(RecordedEvent::Os(event))
+    }
+}
+
+impl fmt::Display for InputRecording {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        for event in self.events.iter() {
+            writeln!(f, "{}", event)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for InputRecording {
+    type Err = RecordingError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines();
+        if lines.next().map(|line| line.trim_end_matches('\r')) != Some(HEADER) {
+            return Err(RecordingError::InvalidHeader);
+        }
+
+        let mut recording = InputRecording::new();
+        for (i, line) in lines.enumerate() {
+            let line = line.trim_end_matches('\r');
+            if line.is_empty() {
+                continue;
+            }
+            // Header is line 1, so first event is at line 2.
+            let event = line.parse().map_err(|_| RecordingError::InvalidEvent(i + 2))?;
+            recording.push(event);
+        }
+        Ok(recording)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        recording::{InputRecording, RecordedEvent, KEY_CODE_NAMES},
+        test_driver::TestDriver,
+        button::ButtonBuilder,
+        text_box::TextBoxBuilder,
+        widget::WidgetBuilder,
+        stack_panel::StackPanelBuilder,
+        test::{StubUiMessage, StubUiNode},
+        message::{
+            OsEvent,
+            CompositionEvent,
+            KeyCode,
+            ButtonState,
+            KeyboardModifiers,
+        },
+        node::UINode,
+        UserInterface,
+        core::{
+            math::vec2::Vec2,
+            pool::Handle,
+        },
+    };
+
+    type Ui = UserInterface<StubUiMessage, StubUiNode>;
+
+    fn build_ui(ui: &mut Ui) -> (Handle<UINode<StubUiMessage, StubUiNode>>, Handle<UINode<StubUiMessage, StubUiNode>>) {
+        let button = ButtonBuilder::new(WidgetBuilder::new()
+            .with_height(30.0))
+            .with_text("Button")
+            .build(ui);
+        let text_box = TextBoxBuilder::new(WidgetBuilder::new()
+            .with_height(30.0))
+            .build(ui);
+        StackPanelBuilder::new(WidgetBuilder::new()
+            .with_width(200.0)
+            .with_child(button)
+            .with_child(text_box))
+            .build(ui);
+        (button, text_box)
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let mut recording = InputRecording::new();
+        recording.push(RecordedEvent::Update { screen_size: Vec2::new(800.0, 600.0), dt: 1.0 / 60.0 });
+        recording.push(RecordedEvent::Os(OsEvent::CursorMoved { position: Vec2::new(10.25, -3.0) }));
+        recording.push(RecordedEvent::Os(OsEvent::KeyboardInput { button: KeyCode::Cut, state: ButtonState::Pressed }));
+        recording.push(RecordedEvent::Os(OsEvent::Character(' ')));
+        recording.push(RecordedEvent::Os(OsEvent::Character('\n')));
+        recording.push(RecordedEvent::Os(OsEvent::KeyboardModifiers(KeyboardModifiers { shift: true, control: true, ..Default::default() })));
+        recording.push(RecordedEvent::Os(OsEvent::Composition(CompositionEvent::Update { text: "a \\ b".to_owned(), cursor: None })));
+
+        let parsed = recording.to_string().parse::<InputRecording>().unwrap();
+        assert_eq!(parsed, recording);
+    }
+
+    #[test]
+    fn key_code_names() {
+        // Every key code must have exactly one name which matches name of its variant.
+        assert_eq!(KEY_CODE_NAMES.len(), KeyCode::Cut as usize + 1);
+        for (i, (code, name)) in KEY_CODE_NAMES.iter().enumerate() {
+            assert_eq!(*code as usize, i);
+            assert_eq!(format!("{:?}", code), *name);
+        }
+    }
+
+    #[test]
+    fn replay_reproduces_messages() {
+        let mut ui = Ui::new();
+        let (button, text_box) = build_ui(&mut ui);
+        ui.start_recording();
+        let expected = {
+            let mut driver = TestDriver::new(&mut ui, Vec2::new(300.0, 300.0));
+            driver.click(button)
+                .click(text_box)
+                .type_text("abc")
+                .press(KeyCode::A, KeyboardModifiers { control: true, ..Default::default() })
+                .press(KeyCode::Backspace, KeyboardModifiers::default());
+            driver.take_messages()
+        };
+        let recording = ui.stop_recording().unwrap()
+            .to_string()
+            .parse::<InputRecording>()
+            .unwrap();
+
+        let mut fresh_ui = Ui::new();
+        build_ui(&mut fresh_ui);
+        let replayed = recording.replay(&mut fresh_ui);
+
+        let summary = |message: &crate::message::UiMessage<StubUiMessage, StubUiNode>| {
+            (message.source, message.target, std::mem::discriminant(&message.data))
+        };
+        assert!(!expected.is_empty());
+        assert_eq!(expected.iter().map(summary).collect::<Vec<_>>(), replayed.iter().map(summary).collect::<Vec<_>>());
+    }
+}
@@ -36,6 +36,7 @@ use crate::{
         WidgetMessage,
     },
     NodeHandleMapping,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 pub struct ScrollBar<M: 'static, C: 'static + Control<M, C>> {
@@ -317,6 +318,23 @@ impl<M, C: 'static + Control<M, C>> ScrollBar<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for ScrollBar<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit("min", &mut self.min)?;
+        visitor.visit("max", &mut self.max)?;
+        visitor.visit("value", &mut self.value)?;
+        visitor.visit("step", &mut self.step)?;
+        visitor.visit("orientation", &mut self.orientation)?;
+        visitor.visit_handle("increase", &mut self.increase)?;
+        visitor.visit_handle("decrease", &mut self.decrease)?;
+        visitor.visit_handle("indicator", &mut self.indicator)?;
+        visitor.visit_handle("field", &mut self.field)?;
+        visitor.visit_handle("value_text", &mut self.value_text)?;
+        visitor.visit("value_precision", &mut self.value_precision)
+    }
+}
+
 pub struct ScrollBarBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     min: Option<f32>,
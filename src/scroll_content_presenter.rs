@@ -13,7 +13,8 @@ use crate::{
     UserInterface,
     Control,
     UINode,
-    message::UiMessage
+    message::UiMessage,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 /// Allows user to scroll content
@@ -140,6 +141,15 @@ impl<M, C: 'static + Control<M, C>> ScrollContentPresenter<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for ScrollContentPresenter<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit("scroll", &mut self.scroll)?;
+        visitor.visit("vertical_scroll_allowed", &mut self.vertical_scroll_allowed)?;
+        visitor.visit("horizontal_scroll_allowed", &mut self.horizontal_scroll_allowed)
+    }
+}
+
 pub struct ScrollContentPresenterBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     vertical_scroll_allowed: Option<bool>,
@@ -28,6 +28,7 @@ use crate::{
         math::vec2::Vec2,
     },
     NodeHandleMapping,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 pub struct ScrollViewer<M: 'static, C: 'static + Control<M, C>> {
@@ -128,7 +129,7 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for ScrollViewer<M, C> {
         match &message.data {
             UiMessageData::Widget(msg) => {
                 if let WidgetMessage::MouseWheel { amount, .. } = msg {
-                    if self.v_scroll_bar.is_some() && !message.handled && (message.source == self_handle || self.widget().has_descendant(message.source, ui)) {
+                    if self.v_scroll_bar.is_some() {
                         if let UINode::ScrollBar(v_scroll_bar) = ui.node_mut(self.v_scroll_bar) {
                             v_scroll_bar.scroll(-amount * 10.0);
                             message.handled = true;
@@ -197,6 +198,16 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for ScrollViewer<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for ScrollViewer<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit_handle("content", &mut self.content)?;
+        visitor.visit_handle("content_presenter", &mut self.content_presenter)?;
+        visitor.visit_handle("v_scroll_bar", &mut self.v_scroll_bar)?;
+        visitor.visit_handle("h_scroll_bar", &mut self.h_scroll_bar)
+    }
+}
+
 pub struct ScrollViewerBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     content: Handle<UINode<M, C>>,
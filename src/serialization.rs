@@ -0,0 +1,943 @@
+// Persistence of UI trees. Subtree of nodes is written as text where every node is a block
+// of `key value` lines, for example:
+//
+// rg3d-ui tree 1
+// node Border
+// widget.name panel
+// widget.width 200
+// widget.children 1
+// stroke_thickness 1 1 1 1
+// end
+// node Text
+// widget.parent 0
+// formatted_text.text Hello
+// end
+//
+// Handles of nodes are written as indices of blocks and are mapped to new handles on load,
+// so references that controls hold to their parts survive. Each control describes its fields
+// by implementing `Visit`, user node type plugs in by implementing `Visit` and `SerializeNode`.
+// Fonts, textures, text box filters and transient state such as caret position or drag state
+// are not saved, text spans are saved without their fonts.
+
+use crate::{
+    UserInterface,
+    Control,
+    UINode,
+    Thickness,
+    HorizontalAlignment,
+    VerticalAlignment,
+    brush::{Brush, GradientPoint},
+    formatted_text::{WrapMode, parse_color},
+    scroll_bar::{Orientation, ScrollBar},
+    popup::{Placement, Popup},
+    markup::{
+        parse_bool,
+        parse_vec2,
+        parse_thickness,
+        parse_brush,
+        parse_horizontal_alignment,
+        parse_vertical_alignment,
+        parse_orientation,
+        parse_wrap,
+    },
+    recording::{escape, unescape},
+    widget::Widget,
+    border::Border,
+    button::Button,
+    canvas::Canvas,
+    check_box::CheckBox,
+    grid::Grid,
+    image::Image,
+    items_control::{ItemsControl, ItemContainer},
+    list_box::{ListBox, ListBoxItem},
+    scroll_content_presenter::ScrollContentPresenter,
+    scroll_viewer::ScrollViewer,
+    stack_panel::StackPanel,
+    tab_control::TabControl,
+    text::Text,
+    text_box::TextBox,
+    window::Window,
+    combobox::ComboBox,
+    decorator::Decorator,
+    progress_bar::ProgressBar,
+    core::{
+        pool::Handle,
+        color::Color,
+        math::vec2::Vec2,
+    },
+};
+use std::{
+    fmt,
+    fs,
+    path::Path,
+    collections::{HashMap, HashSet},
+};
+
+const HEADER: &str = "rg3d-ui tree 1";
+
+#[derive(Debug)]
+pub enum SerializationError {
+    /// File cannot be read or written.
+    Io(std::io::Error),
+    /// Text does not start with tree header.
+    InvalidHeader,
+    /// Text contains no nodes.
+    NoNodes,
+    /// Line is not a part of node block, contains number of the line starting from 1.
+    Syntax(usize),
+    /// Type of node is unknown to the library and to user node type.
+    UnknownNodeType(String),
+    /// Value of a field cannot be parsed, contains full name of the field.
+    InvalidValue(String),
+    /// Field refers to a node that is not in the file, contains full name of the field.
+    InvalidHandle(String),
+    /// Parent and children links of nodes do not agree with each other or form a cycle.
+    InvalidHierarchy,
+}
+
+impl From<std::io::Error> for SerializationError {
+    fn from(e: std::io::Error) -> Self {
+        SerializationError::Io(e)
+    }
+}
+
+impl fmt::Display for SerializationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SerializationError::Io(e) => write!(f, "unable to read or write tree: {}", e),
+            SerializationError::InvalidHeader => write!(f, "text is not a saved tree"),
+            SerializationError::NoNodes => write!(f, "saved tree contains no nodes"),
+            SerializationError::Syntax(line) => write!(f, "invalid syntax at line {}", line),
+            SerializationError::UnknownNodeType(name) => write!(f, "unknown node type {}", name),
+            SerializationError::InvalidValue(field) => write!(f, "invalid value of field {}", field),
+            SerializationError::InvalidHandle(field) => write!(f, "field {} refers to unknown node", field),
+            SerializationError::InvalidHierarchy => write!(f, "parent and children links of nodes are inconsistent"),
+        }
+    }
+}
+
+impl std::error::Error for SerializationError {}
+
+pub type VisitResult = Result<(), SerializationError>;
+
+/// Value of a field that can be written as a single line of text.
+pub trait FieldValue: Sized {
+    fn to_text(&self) -> String;
+
+    fn from_text(text: &str) -> Option<Self>;
+}
+
+macro_rules! impl_field_value_via_parse {
+    ($($ty:ty),*) => {
+        $(
+            impl FieldValue for $ty {
+                fn to_text(&self) -> String {
+                    self.to_string()
+                }
+
+                fn from_text(text: &str) -> Option<Self> {
+                    text.parse().ok()
+                }
+            }
+        )*
+    };
+}
+
+impl_field_value_via_parse!(f32, usize, char);
+
+impl FieldValue for bool {
+    fn to_text(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        parse_bool(text)
+    }
+}
+
+impl FieldValue for String {
+    fn to_text(&self) -> String {
+        self.clone()
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        Some(text.to_owned())
+    }
+}
+
+/// `None` is written as "none", `Some` as "some" followed by value.
+impl<T: FieldValue> FieldValue for Option<T> {
+    fn to_text(&self) -> String {
+        match self {
+            Some(value) => format!("some {}", value.to_text()),
+            None => "none".to_owned(),
+        }
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        if text == "none" {
+            Some(None)
+        } else if text.starts_with("some ") {
+            T::from_text(&text["some ".len()..]).map(Some)
+        } else {
+            None
+        }
+    }
+}
+
+impl FieldValue for Vec2 {
+    fn to_text(&self) -> String {
+        format!("{} {}", self.x, self.y)
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        parse_vec2(text)
+    }
+}
+
+impl FieldValue for Color {
+    fn to_text(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        if text.starts_with('#') { parse_color(text) } else { None }
+    }
+}
+
+impl FieldValue for Thickness {
+    fn to_text(&self) -> String {
+        format!("{} {} {} {}", self.left, self.top, self.right, self.bottom)
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        parse_thickness(text)
+    }
+}
+
+/// Brushes use same syntax as markup: "#rrggbbaa", "linear(x y; x y; stop #color; ...)" or
+/// "radial(x y; stop #color; ...)".
+impl FieldValue for Brush {
+    fn to_text(&self) -> String {
+        let stops = |stops: &[GradientPoint]| {
+            stops.iter()
+                .map(|point| format!("; {} {}", point.stop, point.color.to_text()))
+                .collect::<String>()
+        };
+        match self {
+            Brush::Solid(color) => color.to_text(),
+            Brush::LinearGradient { from, to, stops: points } =>
+                format!("linear({}; {}{})", from.to_text(), to.to_text(), stops(points)),
+            Brush::RadialGradient { center, stops: points } =>
+                format!("radial({}{})", center.to_text(), stops(points)),
+        }
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        parse_brush(text)
+    }
+}
+
+impl FieldValue for HorizontalAlignment {
+    fn to_text(&self) -> String {
+        match self {
+            HorizontalAlignment::Stretch => "stretch",
+            HorizontalAlignment::Left => "left",
+            HorizontalAlignment::Center => "center",
+            HorizontalAlignment::Right => "right",
+        }.to_owned()
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        parse_horizontal_alignment(text)
+    }
+}
+
+impl FieldValue for VerticalAlignment {
+    fn to_text(&self) -> String {
+        match self {
+            VerticalAlignment::Stretch => "stretch",
+            VerticalAlignment::Top => "top",
+            VerticalAlignment::Center => "center",
+            VerticalAlignment::Bottom => "bottom",
+        }.to_owned()
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        parse_vertical_alignment(text)
+    }
+}
+
+impl FieldValue for Orientation {
+    fn to_text(&self) -> String {
+        match self {
+            Orientation::Vertical => "vertical",
+            Orientation::Horizontal => "horizontal",
+        }.to_owned()
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        parse_orientation(text)
+    }
+}
+
+impl FieldValue for WrapMode {
+    fn to_text(&self) -> String {
+        match self {
+            WrapMode::NoWrap => "none",
+            WrapMode::Character => "character",
+            WrapMode::Word => "word",
+        }.to_owned()
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        parse_wrap(text)
+    }
+}
+
+impl FieldValue for Placement {
+    fn to_text(&self) -> String {
+        match self {
+            Placement::LeftTop => "left_top".to_owned(),
+            Placement::RightTop => "right_top".to_owned(),
+            Placement::Center => "center".to_owned(),
+            Placement::LeftBottom => "left_bottom".to_owned(),
+            Placement::RightBottom => "right_bottom".to_owned(),
+            Placement::Cursor => "cursor".to_owned(),
+            Placement::Position(position) => format!("position {}", position.to_text()),
+        }
+    }
+
+    fn from_text(text: &str) -> Option<Self> {
+        match text {
+            "left_top" => Some(Placement::LeftTop),
+            "right_top" => Some(Placement::RightTop),
+            "center" => Some(Placement::Center),
+            "left_bottom" => Some(Placement::LeftBottom),
+            "right_bottom" => Some(Placement::RightBottom),
+            "cursor" => Some(Placement::Cursor),
+            _ if text.starts_with("position ") => parse_vec2(&text["position ".len()..]).map(Placement::Position),
+            _ => None,
+        }
+    }
+}
+
+/// Describes fields of a node to visitor. Same method is used to collect nodes of a subtree,
+/// to write fields and to read them back, so reading must mirror writing.
+pub trait Visit<M: 'static, C: 'static + Control<M, C>> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult;
+}
+
+/// Allows user node type to be saved and loaded as a part of a tree.
+pub trait SerializeNode<M: 'static, C: 'static + Control<M, C>>: Visit<M, C> + Sized {
+    /// Returns name of concrete type of the node. Name must not contain whitespace and must
+    /// differ from names of nodes of the library, such as "Button".
+    fn type_name(&self) -> &str;
+
+    /// Creates node of given type in default state, fields are filled by `visit` afterwards.
+    /// Returns `None` if type is unknown.
+    fn create(type_name: &str) -> Option<Self>;
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum VisitMode {
+    /// Only strong references to other nodes are gathered.
+    Collect,
+    Write,
+    Read,
+}
+
+pub struct Visitor<M: 'static, C: 'static + Control<M, C>> {
+    mode: VisitMode,
+    regions: Vec<String>,
+    references: Vec<Handle<UINode<M, C>>>,
+    written_fields: Vec<(String, String)>,
+    read_fields: HashMap<String, String>,
+    indices: HashMap<Handle<UINode<M, C>>, usize>,
+    handles: Vec<Handle<UINode<M, C>>>,
+}
+
+impl<M, C: 'static + Control<M, C>> Visitor<M, C> {
+    fn new(mode: VisitMode) -> Self {
+        Self {
+            mode,
+            regions: Vec::new(),
+            references: Vec::new(),
+            written_fields: Vec::new(),
+            read_fields: HashMap::new(),
+            indices: HashMap::new(),
+            handles: Vec::new(),
+        }
+    }
+
+    pub fn is_reading(&self) -> bool {
+        self.mode == VisitMode::Read
+    }
+
+    /// Makes names of following fields prefixed with name of region, regions can be nested.
+    pub fn enter_region(&mut self, name: &str) {
+        self.regions.push(name.to_owned());
+    }
+
+    pub fn leave_region(&mut self) {
+        self.regions.pop();
+    }
+
+    fn path(&self, name: &str) -> String {
+        let mut path = String::new();
+        for region in self.regions.iter() {
+            path.push_str(region);
+            path.push('.');
+        }
+        path.push_str(name);
+        path
+    }
+
+    /// Writes or reads a field. Fields missing in file are left unchanged on read.
+    pub fn visit<T: FieldValue>(&mut self, name: &str, value: &mut T) -> VisitResult {
+        match self.mode {
+            VisitMode::Collect => (),
+            VisitMode::Write => {
+                let path = self.path(name);
+                self.written_fields.push((path, value.to_text()));
+            }
+            VisitMode::Read => {
+                let path = self.path(name);
+                if let Some(text) = self.read_fields.get(&path) {
+                    *value = T::from_text(text).ok_or(SerializationError::InvalidValue(path))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Same as `visit`, but for values accessible only through getter and setter. Setter is
+    /// called only on read.
+    pub fn visit_property<T: FieldValue, S: FnOnce(T)>(&mut self, name: &str, value: T, setter: S) -> VisitResult {
+        let mut value = value;
+        self.visit(name, &mut value)?;
+        if self.is_reading() {
+            setter(value);
+        }
+        Ok(())
+    }
+
+    /// Writes or reads list of values, every item is a separate field.
+    pub fn visit_vec<T: FieldValue>(&mut self, name: &str, values: &mut Vec<T>) -> VisitResult {
+        if self.is_reading() {
+            let len_path = self.path(&format!("{}.len", name));
+            if let Some(text) = self.read_fields.get(&len_path) {
+                let len = usize::from_text(text).ok_or(SerializationError::InvalidValue(len_path))?;
+                let mut items = Vec::new();
+                for i in 0..len {
+                    let path = self.path(&format!("{}.{}", name, i));
+                    let item = self.read_fields
+                        .get(&path)
+                        .and_then(|text| T::from_text(text))
+                        .ok_or(SerializationError::InvalidValue(path))?;
+                    items.push(item);
+                }
+                *values = items;
+            }
+        } else {
+            let mut len = values.len();
+            self.visit(&format!("{}.len", name), &mut len)?;
+            for (i, value) in values.iter_mut().enumerate() {
+                self.visit(&format!("{}.{}", name, i), value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes or reads length of a list whose items are visited in regions `<name>.<index>`.
+    /// Length read from file is accepted only if every item has field `item_field`, so damaged
+    /// length cannot make huge list.
+    pub fn visit_len(&mut self, name: &str, len: &mut usize, item_field: &str) -> VisitResult {
+        if self.is_reading() {
+            let len_path = self.path(&format!("{}.len", name));
+            if let Some(text) = self.read_fields.get(&len_path) {
+                let count = usize::from_text(text).ok_or_else(|| SerializationError::InvalidValue(len_path.clone()))?;
+                let all_present = (0..count)
+                    .all(|i| self.read_fields.contains_key(&self.path(&format!("{}.{}.{}", name, i, item_field))));
+                if !all_present {
+                    return Err(SerializationError::InvalidValue(len_path));
+                }
+                *len = count;
+            }
+            Ok(())
+        } else {
+            self.visit(&format!("{}.len", name), len)
+        }
+    }
+
+    /// Writes or reads handle of a node that is a part of this node, such as indicator of a
+    /// scroll bar. Referenced node is saved together with this node.
+    pub fn visit_handle(&mut self, name: &str, handle: &mut Handle<UINode<M, C>>) -> VisitResult {
+        if self.mode == VisitMode::Collect && handle.is_some() {
+            self.references.push(*handle);
+        }
+        self.visit_weak_handle(name, handle)
+    }
+
+    /// Writes or reads handle of a node that is not owned by this node, such as parent.
+    /// Referenced node is not saved because of this reference, if it is not saved for other
+    /// reasons the handle is loaded as `NONE`.
+    pub fn visit_weak_handle(&mut self, name: &str, handle: &mut Handle<UINode<M, C>>) -> VisitResult {
+        match self.mode {
+            VisitMode::Collect => (),
+            VisitMode::Write => {
+                let path = self.path(name);
+                let text = match self.indices.get(&*handle) {
+                    Some(index) => index.to_string(),
+                    None => "none".to_owned(),
+                };
+                self.written_fields.push((path, text));
+            }
+            VisitMode::Read => {
+                let path = self.path(name);
+                if let Some(text) = self.read_fields.get(&path) {
+                    *handle = if text == "none" {
+                        Handle::NONE
+                    } else {
+                        self.handle_at(text).ok_or(SerializationError::InvalidHandle(path))?
+                    };
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes or reads list of handles of nodes that are parts of this node, such as children.
+    /// Handles of nodes that are not saved are skipped.
+    pub fn visit_handles(&mut self, name: &str, handles: &mut Vec<Handle<UINode<M, C>>>) -> VisitResult {
+        match self.mode {
+            VisitMode::Collect => {
+                self.references.extend(handles.iter().filter(|handle| handle.is_some()).cloned());
+            }
+            VisitMode::Write => {
+                let path = self.path(name);
+                let text = handles.iter()
+                    .filter_map(|handle| self.indices.get(handle))
+                    .map(|index| index.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" ");
+                self.written_fields.push((path, text));
+            }
+            VisitMode::Read => {
+                let path = self.path(name);
+                if let Some(text) = self.read_fields.get(&path) {
+                    *handles = text.split_whitespace()
+                        .map(|index| self.handle_at(index))
+                        .collect::<Option<Vec<Handle<UINode<M, C>>>>>()
+                        .ok_or(SerializationError::InvalidHandle(path))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_at(&self, index: &str) -> Option<Handle<UINode<M, C>>> {
+        index.parse::<usize>()
+            .ok()
+            .and_then(|index| self.handles.get(index))
+            .cloned()
+    }
+}
+
+struct NodeRecord {
+    type_name: String,
+    fields: HashMap<String, String>,
+}
+
+fn parse_records(text: &str) -> Result<Vec<NodeRecord>, SerializationError> {
+    let mut lines = text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line))
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    match lines.next() {
+        Some((_, line)) if line.trim() == HEADER => (),
+        _ => return Err(SerializationError::InvalidHeader),
+    }
+
+    let mut records = Vec::new();
+    let mut current: Option<NodeRecord> = None;
+    let mut last_line = 1;
+    for (number, line) in lines {
+        last_line = number;
+        let (key, value) = match line.find(' ') {
+            Some(space) => (&line[..space], &line[space + 1..]),
+            None => (line, ""),
+        };
+        match current.take() {
+            None => {
+                if key != "node" || value.is_empty() {
+                    return Err(SerializationError::Syntax(number));
+                }
+                current = Some(NodeRecord { type_name: value.to_owned(), fields: HashMap::new() });
+            }
+            Some(mut record) => {
+                if key == "end" && value.is_empty() {
+                    records.push(record);
+                } else {
+                    let value = unescape(value).ok_or(SerializationError::Syntax(number))?;
+                    record.fields.insert(key.to_owned(), value);
+                    current = Some(record);
+                }
+            }
+        }
+    }
+
+    if current.is_some() {
+        Err(SerializationError::Syntax(last_line))
+    } else if records.is_empty() {
+        Err(SerializationError::NoNodes)
+    } else {
+        Ok(records)
+    }
+}
+
+fn node_type_name<M: 'static, C: 'static + Control<M, C> + SerializeNode<M, C>>(node: &UINode<M, C>) -> &str {
+    match node {
+        UINode::Border(_) => "Border",
+        UINode::Button(_) => "Button",
+        UINode::Canvas(_) => "Canvas",
+        UINode::CheckBox(_) => "CheckBox",
+        UINode::Grid(_) => "Grid",
+        UINode::Image(_) => "Image",
+        UINode::ItemsControl(_) => "ItemsControl",
+        UINode::ItemContainer(_) => "ItemContainer",
+        UINode::ListBox(_) => "ListBox",
+        UINode::ListBoxItem(_) => "ListBoxItem",
+        UINode::ScrollBar(_) => "ScrollBar",
+        UINode::ScrollContentPresenter(_) => "ScrollContentPresenter",
+        UINode::ScrollViewer(_) => "ScrollViewer",
+        UINode::StackPanel(_) => "StackPanel",
+        UINode::TabControl(_) => "TabControl",
+        UINode::Text(_) => "Text",
+        UINode::TextBox(_) => "TextBox",
+        UINode::Window(_) => "Window",
+        UINode::Popup(_) => "Popup",
+        UINode::ComboBox(_) => "ComboBox",
+        UINode::Decorator(_) => "Decorator",
+        UINode::ProgressBar(_) => "ProgressBar",
+        UINode::User(node) => node.type_name(),
+    }
+}
+
+/// Creates node of given type in default state, every handle it holds is `NONE`.
+fn create_node<M: 'static, C: 'static + Control<M, C> + SerializeNode<M, C>>(type_name: &str) -> Option<UINode<M, C>> {
+    let none = Handle::NONE;
+    Some(match type_name {
+        "Border" => UINode::Border(Border::new(Widget::default())),
+        "Button" => UINode::Button(Button::new(Widget::default(), none, none)),
+        "Canvas" => UINode::Canvas(Canvas::new(Widget::default())),
+        "CheckBox" => UINode::CheckBox(CheckBox::new(Widget::default(), none)),
+        "Grid" => UINode::Grid(Grid::new(Widget::default())),
+        "Image" => UINode::Image(Image::new(Widget::default())),
+        "ItemsControl" => UINode::ItemsControl(ItemsControl::new(Widget::default(), Vec::new())),
+        "ItemContainer" => UINode::ItemContainer(ItemContainer::default()),
+        "ListBox" => UINode::ListBox(ListBox::new(Widget::default(), Vec::new())),
+        "ListBoxItem" => UINode::ListBoxItem(ListBoxItem::default()),
+        "ScrollBar" => UINode::ScrollBar(ScrollBar::new(Widget::default(), none, none, none, none, none)),
+        "ScrollContentPresenter" => UINode::ScrollContentPresenter(ScrollContentPresenter::new(Widget::default())),
+        "ScrollViewer" => UINode::ScrollViewer(ScrollViewer::new(Widget::default(), none, none, none, none)),
+        "StackPanel" => UINode::StackPanel(StackPanel::new(Widget::default())),
+        "TabControl" => UINode::TabControl(TabControl::default()),
+        "Text" => UINode::Text(Text::new(Widget::default())),
+        "TextBox" => UINode::TextBox(TextBox::new(Widget::default())),
+        "Window" => UINode::Window(Window::new(Widget::default(), none, none, none, none)),
+        "Popup" => UINode::Popup(Popup::default()),
+        "ComboBox" => UINode::ComboBox(ComboBox::default()),
+        "Decorator" => UINode::Decorator(Decorator::default()),
+        "ProgressBar" => UINode::ProgressBar(ProgressBar::default()),
+        _ => UINode::User(C::create(type_name)?),
+    })
+}
+
+impl<M, C: 'static + Control<M, C> + SerializeNode<M, C>> UserInterface<M, C> {
+    /// Writes node and all its descendants as text. Nodes that saved nodes own but that are
+    /// not descendants, such as popup of a combo box, are written too.
+    pub fn save_subtree(&mut self, root: Handle<UINode<M, C>>) -> Result<String, SerializationError> {
+        let mut nodes = Vec::new();
+        let mut indices = HashMap::new();
+        let mut stack = vec![root];
+        while let Some(handle) = stack.pop() {
+            if indices.contains_key(&handle) {
+                continue;
+            }
+            indices.insert(handle, nodes.len());
+            nodes.push(handle);
+
+            let mut visitor = Visitor::new(VisitMode::Collect);
+            self.node_mut(handle).visit(&mut visitor)?;
+            // Reversed to save children in direct order.
+            stack.extend(visitor.references.into_iter().rev());
+        }
+
+        let mut visitor = Visitor::new(VisitMode::Write);
+        visitor.indices = indices;
+        let mut text = format!("{}\n", HEADER);
+        for handle in nodes {
+            visitor.written_fields.clear();
+            let node = self.node_mut(handle);
+            node.visit(&mut visitor)?;
+            text.push_str(&format!("node {}\n", node_type_name(node)));
+            for (key, value) in visitor.written_fields.iter() {
+                text.push_str(&format!("{} {}\n", key, escape(value)));
+            }
+            text.push_str("end\n");
+        }
+        Ok(text)
+    }
+
+    /// Creates nodes from text written by `save_subtree` and attaches them to root canvas.
+    /// Returns handle of the root of loaded subtree.
+    pub fn load_subtree(&mut self, text: &str) -> Result<Handle<UINode<M, C>>, SerializationError> {
+        let records = parse_records(text)?;
+
+        let mut handles = Vec::with_capacity(records.len());
+        for record in records.iter() {
+            match create_node(&record.type_name) {
+                Some(node) => handles.push(self.nodes_mut().spawn(node)),
+                None => {
+                    self.free_loaded(&handles);
+                    return Err(SerializationError::UnknownNodeType(record.type_name.clone()));
+                }
+            }
+        }
+
+        let mut visitor = Visitor::new(VisitMode::Read);
+        visitor.handles = handles.clone();
+        for (record, handle) in records.into_iter().zip(handles.iter()) {
+            visitor.read_fields = record.fields;
+            if let Err(e) = self.node_mut(*handle).visit(&mut visitor) {
+                self.free_loaded(&handles);
+                return Err(e);
+            }
+        }
+
+        if let Err(e) = self.check_hierarchy(&handles) {
+            self.free_loaded(&handles);
+            return Err(e);
+        }
+
+        // Root of subtree and nodes that were not descendants of any saved node.
+        for handle in handles.iter() {
+            if self.node(*handle).widget().parent().is_none() {
+                self.link_nodes(*handle, self.root());
+            }
+        }
+
+        Ok(handles[0])
+    }
+
+    /// Checks that every child of loaded node refers to it as a parent and vice versa, and
+    /// that every node is reachable from a node without parent, so there are no cycles.
+    fn check_hierarchy(&self, handles: &[Handle<UINode<M, C>>]) -> VisitResult {
+        for handle in handles {
+            let widget = self.node(*handle).widget();
+            let mut unique_children = HashSet::new();
+            for child in widget.children() {
+                if !unique_children.insert(*child) || self.node(*child).widget().parent() != *handle {
+                    return Err(SerializationError::InvalidHierarchy);
+                }
+            }
+            let parent = widget.parent();
+            if parent.is_some() && !self.node(parent).widget().children().contains(handle) {
+                return Err(SerializationError::InvalidHierarchy);
+            }
+        }
+
+        // Links agree with each other, so each node is reached at most once from roots.
+        let mut reached = 0;
+        let mut stack = handles.iter()
+            .filter(|handle| self.node(**handle).widget().parent().is_none())
+            .cloned()
+            .collect::<Vec<Handle<UINode<M, C>>>>();
+        while let Some(handle) = stack.pop() {
+            reached += 1;
+            stack.extend_from_slice(self.node(handle).widget().children());
+        }
+        if reached == handles.len() {
+            Ok(())
+        } else {
+            Err(SerializationError::InvalidHierarchy)
+        }
+    }
+
+    fn free_loaded(&mut self, handles: &[Handle<UINode<M, C>>]) {
+        for handle in handles {
+            self.nodes_mut().free(*handle);
+        }
+    }
+
+    pub fn save_subtree_to_file(&mut self, root: Handle<UINode<M, C>>, path: &Path) -> Result<(), SerializationError> {
+        let text = self.save_subtree(root)?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn load_subtree_from_file(&mut self, path: &Path) -> Result<Handle<UINode<M, C>>, SerializationError> {
+        let text = fs::read_to_string(path)?;
+        self.load_subtree(&text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        UserInterface,
+        UINode,
+        serialization::SerializationError,
+        widget::WidgetBuilder,
+        window::{WindowBuilder, WindowTitle},
+        stack_panel::StackPanelBuilder,
+        scroll_bar::{ScrollBarBuilder, Orientation},
+        text::TextBuilder,
+        combobox::ComboBoxBuilder,
+        test::{StubUiMessage, StubUiNode},
+        brush::Brush,
+        core::{
+            color::Color,
+            math::vec2::Vec2,
+        },
+    };
+
+    #[test]
+    fn round_trip() {
+        let mut ui = UserInterface::<StubUiMessage, StubUiNode>::new();
+        let items = vec![
+            TextBuilder::new(WidgetBuilder::new()).with_text("Low").build(&mut ui),
+            TextBuilder::new(WidgetBuilder::new()).with_text("High").build(&mut ui),
+        ];
+        let window = WindowBuilder::new(WidgetBuilder::new()
+            .with_name("settings")
+            .with_width(300.0))
+            .with_title(WindowTitle::Text("Settings"))
+            .with_content(StackPanelBuilder::new(WidgetBuilder::new()
+                .with_child(TextBuilder::new(WidgetBuilder::new()
+                    .with_name("label"))
+                    .with_text("Volume\n50%")
+                    .build(&mut ui))
+                .with_child(ScrollBarBuilder::new(WidgetBuilder::new()
+                    .with_name("volume")
+                    .with_height(20.0))
+                    .with_orientation(Orientation::Horizontal)
+                    .with_value(30.0)
+                    .build(&mut ui))
+                .with_child(ComboBoxBuilder::new(WidgetBuilder::new()
+                    .with_name("quality"))
+                    .with_items(items)
+                    .build(&mut ui)))
+                .build(&mut ui))
+            .build(&mut ui);
+
+        let text = ui.save_subtree(window).unwrap();
+
+        let mut other = UserInterface::<StubUiMessage, StubUiNode>::new();
+        let loaded = other.load_subtree(&text).unwrap();
+
+        // Same fields and same references between nodes, including popup of combo box which
+        // is not a descendant of the window.
+        assert_eq!(other.save_subtree(loaded).unwrap(), text);
+
+        other.update(Vec2::new(400.0, 400.0), 0.0);
+
+        assert_eq!(other.node(loaded).widget().parent(), other.root());
+        assert_eq!(other.node(loaded).widget().name(), "settings");
+
+        let label = other.find_by_name_down(loaded, "label");
+        if let UINode::Text(label) = other.node(label) {
+            assert_eq!(label.text(), "Volume\n50%");
+        } else {
+            panic!("label must be a text");
+        }
+
+        let volume = other.find_by_name_down(loaded, "volume");
+        if let UINode::ScrollBar(volume) = other.node(volume) {
+            assert!((volume.value() - 30.0).abs() < std::f32::EPSILON);
+        } else {
+            panic!("volume must be a scroll bar");
+        }
+    }
+
+    #[test]
+    fn markup_spans_round_trip() {
+        let mut ui = UserInterface::<StubUiMessage, StubUiNode>::new();
+        let text = TextBuilder::new(WidgetBuilder::new())
+            .with_markup("[color=#ff0000]Red[/color] [size=20][u]big[/u][/size] [s]old[/s]")
+            .build(&mut ui);
+
+        let saved = ui.save_subtree(text).unwrap();
+
+        let mut other = UserInterface::<StubUiMessage, StubUiNode>::new();
+        let loaded = other.load_subtree(&saved).unwrap();
+        assert_eq!(other.save_subtree(loaded).unwrap(), saved);
+
+        if let UINode::Text(loaded) = other.node(loaded) {
+            assert_eq!(loaded.text(), "Red big old");
+            let spans = loaded.spans();
+            assert_eq!(spans.len(), 4);
+            assert_eq!(spans[0].range, 0..3);
+            match spans[0].brush {
+                Some(Brush::Solid(color)) => assert_eq!(color, Color::opaque(255, 0, 0)),
+                _ => panic!("expected solid brush"),
+            }
+            assert_eq!(spans[1].range, 4..7);
+            assert_eq!(spans[1].font_size, Some(20.0));
+            assert_eq!(spans[2].range, 4..7);
+            assert!(spans[2].underline);
+            assert_eq!(spans[3].range, 8..11);
+            assert!(spans[3].strikethrough);
+            assert!(!spans[3].underline);
+        } else {
+            panic!("loaded node must be a text");
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let mut ui = UserInterface::<StubUiMessage, StubUiNode>::new();
+
+        match ui.load_subtree("node Canvas\nend\n") {
+            Err(SerializationError::InvalidHeader) => (),
+            _ => panic!("expected invalid header"),
+        }
+        match ui.load_subtree("rg3d-ui tree 1\n") {
+            Err(SerializationError::NoNodes) => (),
+            _ => panic!("expected no nodes"),
+        }
+        match ui.load_subtree("rg3d-ui tree 1\nnode Canvas\nwidget.width 10\n") {
+            Err(SerializationError::Syntax(3)) => (),
+            _ => panic!("expected syntax error"),
+        }
+        match ui.load_subtree("rg3d-ui tree 1\nnode Canvas\nend\nnode Slider\nend\n") {
+            Err(SerializationError::UnknownNodeType(name)) => assert_eq!(name, "Slider"),
+            _ => panic!("expected unknown node type"),
+        }
+        match ui.load_subtree("rg3d-ui tree 1\nnode Canvas\nwidget.width wide\nend\n") {
+            Err(SerializationError::InvalidValue(field)) => assert_eq!(field, "widget.width"),
+            _ => panic!("expected invalid value"),
+        }
+        match ui.load_subtree("rg3d-ui tree 1\nnode Canvas\nwidget.children 1\nend\n") {
+            Err(SerializationError::InvalidHandle(field)) => assert_eq!(field, "widget.children"),
+            _ => panic!("expected invalid handle"),
+        }
+        match ui.load_subtree("rg3d-ui tree 1\nnode Canvas\nwidget.children 1\nwidget.parent 1\nend\nnode Canvas\nwidget.children 0\nwidget.parent 0\nend\n") {
+            Err(SerializationError::InvalidHierarchy) => (),
+            _ => panic!("expected cycle to be rejected"),
+        }
+        match ui.load_subtree("rg3d-ui tree 1\nnode Canvas\nwidget.children 2\nend\nnode Canvas\nwidget.children 2\nend\nnode Canvas\nwidget.parent 0\nend\n") {
+            Err(SerializationError::InvalidHierarchy) => (),
+            _ => panic!("expected node with two parents to be rejected"),
+        }
+        match ui.load_subtree("rg3d-ui tree 1\nnode TabControl\ntabs.len 18446744073709551615\nend\n") {
+            Err(SerializationError::InvalidValue(field)) => assert_eq!(field, "tabs.len"),
+            _ => panic!("expected tab count without tabs to be rejected"),
+        }
+
+        // Failed loads must not leave nodes behind.
+        assert!(ui.node(ui.root()).widget().children().is_empty());
+    }
+}
@@ -0,0 +1,118 @@
+// Contextual forms of Arabic letters. Each letter is drawn by its isolated, initial,
+// medial or final form depending on whether it joins its neighbours, forms are taken
+// from `GSUB` table of a font.
+
+use crate::bidi;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub(in crate) enum JoiningForm {
+    Isolated,
+    Initial,
+    Medial,
+    Final,
+}
+
+impl JoiningForm {
+    /// Returns OpenType feature that substitutes glyph of this form.
+    pub(in crate) fn feature(self) -> &'static [u8; 4] {
+        match self {
+            JoiningForm::Isolated => b"isol",
+            JoiningForm::Initial => b"init",
+            JoiningForm::Medial => b"medi",
+            JoiningForm::Final => b"fina",
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+enum JoiningType {
+    /// Does not join neighbours.
+    NonJoining,
+    /// Joins only previous character, like alef.
+    Right,
+    /// Joins both neighbours.
+    Dual,
+    /// Invisible for joining, like vowel marks.
+    Transparent,
+}
+
+fn joining_type(code: u32) -> JoiningType {
+    if bidi::is_mark(code) {
+        return JoiningType::Transparent;
+    }
+    match code {
+        0x0622..=0x0625 | 0x0627 | 0x0629 | 0x062F..=0x0632 | 0x0648 | 0x0671..=0x0673 |
+        0x0675..=0x0677 | 0x0688..=0x0699 | 0x06C0 | 0x06C3..=0x06CB | 0x06CD | 0x06CF |
+        0x06D2 | 0x06D3 | 0x06D5 | 0x06EE | 0x06EF => JoiningType::Right,
+        0x0620 | 0x0626 | 0x0628 | 0x062A..=0x062E | 0x0633..=0x063F | 0x0640..=0x0647 |
+        0x0649 | 0x064A | 0x066E | 0x066F | 0x0678..=0x0687 | 0x069A..=0x06BF | 0x06C1 |
+        0x06C2 | 0x06CC | 0x06CE | 0x06D0 | 0x06D1 | 0x06FA..=0x06FC | 0x06FF => JoiningType::Dual,
+        _ => JoiningType::NonJoining
+    }
+}
+
+/// Returns form of each character of text in logical order, `None` for characters that
+/// have no contextual forms.
+pub(in crate) fn joining_forms(text: &[u32]) -> Vec<Option<JoiningForm>> {
+    let types = text.iter().map(|code| joining_type(*code)).collect::<Vec<JoiningType>>();
+    // Nearest character that is not transparent, in given direction.
+    let neighbour = |range: &mut dyn Iterator<Item=usize>| {
+        range
+            .map(|i| types[i])
+            .find(|joining_type| *joining_type != JoiningType::Transparent)
+            .unwrap_or(JoiningType::NonJoining)
+    };
+
+    types
+        .iter()
+        .enumerate()
+        .map(|(i, joining_type)| {
+            if *joining_type == JoiningType::NonJoining || *joining_type == JoiningType::Transparent {
+                return None;
+            }
+            let prev = neighbour(&mut (0..i).rev());
+            let next = neighbour(&mut ((i + 1)..types.len()));
+            let joins_prev = prev == JoiningType::Dual;
+            let joins_next = *joining_type == JoiningType::Dual &&
+                (next == JoiningType::Dual || next == JoiningType::Right);
+            Some(match (joins_prev, joins_next) {
+                (true, true) => JoiningForm::Medial,
+                (false, true) => JoiningForm::Initial,
+                (true, false) => JoiningForm::Final,
+                (false, false) => JoiningForm::Isolated,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::shaping::{joining_forms, JoiningForm, JoiningForm::*};
+
+    fn forms(text: &str) -> Vec<Option<JoiningForm>> {
+        joining_forms(&text.chars().map(|c| c as u32).collect::<Vec<u32>>())
+    }
+
+    #[test]
+    fn dual_joining() {
+        // Beh, yeh, teh.
+        assert_eq!(forms("\u{628}\u{64A}\u{62A}"), vec![Some(Initial), Some(Medial), Some(Final)]);
+        assert_eq!(forms("\u{628}"), vec![Some(Isolated)]);
+    }
+
+    #[test]
+    fn right_joining() {
+        // Alef joins only previous letter, so next beh starts new group.
+        assert_eq!(forms("\u{628}\u{627}\u{628}"), vec![Some(Initial), Some(Final), Some(Isolated)]);
+        // Dal, alef, reh do not join each other.
+        assert_eq!(forms("\u{62F}\u{627}\u{631}"), vec![Some(Isolated), Some(Isolated), Some(Isolated)]);
+    }
+
+    #[test]
+    fn transparent_and_non_joining() {
+        // Fatha between letters does not break joining.
+        assert_eq!(forms("\u{628}\u{64E}\u{62A}"), vec![Some(Initial), None, Some(Final)]);
+        // Space breaks joining.
+        assert_eq!(forms("\u{628} \u{62A}"), vec![Some(Isolated), None, Some(Isolated)]);
+    }
+}
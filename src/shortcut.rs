@@ -0,0 +1,107 @@
+//! Keyboard shortcuts. Shortcut is a key chord (a key plus a set of modifiers, like
+//! Ctrl+Shift+S) which is translated into a user message when pressed. Shortcut can
+//! be global or scoped to a subtree of nodes - such shortcut will work only if keyboard
+//! focus is inside of the subtree. Scoped shortcuts have priority over global ones,
+//! the closer scope is to focused node the higher its priority.
+//!
+//! Pressed key is delivered to focused node first, shortcut is triggered only if neither
+//! the node nor its ancestors marked `KeyDown` message as handled. This way text box keeps
+//! its own Ctrl+C, Ctrl+Z, Ctrl+A and so on even if same chords are registered as global
+//! shortcuts.
+
+use std::collections::HashMap;
+use crate::{
+    core::pool::Handle,
+    message::{
+        KeyCode,
+        KeyboardModifiers,
+    },
+    node::UINode,
+    Control,
+};
+
+/// Combination of a key and state of keyboard modifiers.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyboardModifiers,
+}
+
+impl KeyChord {
+    /// Creates new chord without any modifiers.
+    pub fn new(code: KeyCode) -> Self {
+        Self {
+            code,
+            modifiers: Default::default(),
+        }
+    }
+
+    pub fn with_control(mut self) -> Self {
+        self.modifiers.control = true;
+        self
+    }
+
+    pub fn with_shift(mut self) -> Self {
+        self.modifiers.shift = true;
+        self
+    }
+
+    pub fn with_alt(mut self) -> Self {
+        self.modifiers.alt = true;
+        self
+    }
+
+    pub fn with_system(mut self) -> Self {
+        self.modifiers.system = true;
+        self
+    }
+}
+
+pub struct ShortcutRegistry<M: 'static, C: 'static + Control<M, C>> {
+    shortcuts: HashMap<(KeyChord, Handle<UINode<M, C>>), Box<dyn Fn() -> M>>,
+}
+
+impl<M, C: 'static + Control<M, C>> Default for ShortcutRegistry<M, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M, C: 'static + Control<M, C>> ShortcutRegistry<M, C> {
+    pub fn new() -> Self {
+        Self {
+            shortcuts: Default::default(),
+        }
+    }
+
+    /// Registers new shortcut. `scope` defines a node in which subtree keyboard focus must be
+    /// to make shortcut work, pass `Handle::NONE` to make shortcut global. `message` is called
+    /// each time when shortcut is triggered to produce new user message. Previous shortcut
+    /// with same chord and scope will be replaced.
+    pub fn add<F>(&mut self, chord: KeyChord, scope: Handle<UINode<M, C>>, message: F)
+        where F: Fn() -> M + 'static {
+        self.shortcuts.insert((chord, scope), Box::new(message));
+    }
+
+    /// Removes shortcut with given chord and scope. Returns true if shortcut was registered.
+    pub fn remove(&mut self, chord: KeyChord, scope: Handle<UINode<M, C>>) -> bool {
+        self.shortcuts.remove(&(chord, scope)).is_some()
+    }
+
+    /// Removes every shortcut scoped to given node.
+    pub fn remove_scope(&mut self, scope: Handle<UINode<M, C>>) {
+        self.shortcuts.retain(|(_, s), _| *s != scope);
+    }
+
+    pub fn clear(&mut self) {
+        self.shortcuts.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shortcuts.is_empty()
+    }
+
+    pub(in crate) fn make_message(&self, chord: KeyChord, scope: Handle<UINode<M, C>>) -> Option<M> {
+        self.shortcuts.get(&(chord, scope)).map(|factory| factory())
+    }
+}
@@ -0,0 +1,620 @@
+// CPU rasterizer of drawing context. Renders commands the same way as GPU renderer does:
+// clipping geometry is written to stencil buffer and geometry of a command is drawn only
+// where stencil value equals nesting of the command. Useful for tests and for tools that
+// run without a GPU.
+
+use crate::{
+    draw::{
+        DrawingContext,
+        CommandKind,
+        CommandTexture,
+        Command,
+        Vertex,
+    },
+    brush::{Brush, GradientPoint},
+    ttf::GlyphRasterMode,
+    core::{
+        color::Color,
+        math::vec2::Vec2,
+    },
+};
+
+/// Image with 8-bit RGBA pixels, rows go from top to bottom. Result of rendering is stored
+/// in such image, it is also the only kind of `Texture` software renderer is able to sample,
+/// any other texture is drawn as if it was white.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RgbaImage {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height * 4],
+        }
+    }
+
+    /// Creates image from existing pixels, returns `None` if length of `pixels` does not
+    /// match `width * height * 4`.
+    pub fn from_pixels(width: usize, height: usize, pixels: Vec<u8>) -> Option<Self> {
+        if pixels.len() == width * height * 4 {
+            Some(Self {
+                width,
+                height,
+                pixels,
+            })
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    #[inline]
+    pub fn pixels_mut(&mut self) -> &mut [u8] {
+        &mut self.pixels
+    }
+
+    /// Returns color of pixel, or `None` if pixel is out of bounds.
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<Color> {
+        if x < self.width && y < self.height {
+            let i = (y * self.width + x) * 4;
+            Some(Color::from_rgba(self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]))
+        } else {
+            None
+        }
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+        if x < self.width && y < self.height {
+            let i = (y * self.width + x) * 4;
+            self.pixels[i] = color.r;
+            self.pixels[i + 1] = color.g;
+            self.pixels[i + 2] = color.b;
+            self.pixels[i + 3] = color.a;
+        }
+    }
+
+    pub fn fill(&mut self, color: Color) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+    }
+
+    fn texel(&self, x: usize, y: usize) -> [f32; 4] {
+        let i = (y * self.width + x) * 4;
+        [
+            f32::from(self.pixels[i]) / 255.0,
+            f32::from(self.pixels[i + 1]) / 255.0,
+            f32::from(self.pixels[i + 2]) / 255.0,
+            f32::from(self.pixels[i + 3]) / 255.0,
+        ]
+    }
+}
+
+/// Sampler of texture of a command, atlases of fonts are single-channel.
+enum Sampler<'a> {
+    White,
+    Image(&'a RgbaImage),
+    Atlas {
+        pixels: &'a [u8],
+        size: usize,
+    },
+}
+
+impl<'a> Sampler<'a> {
+    /// Bilinear sample with clamp to edge, `tex_coord` is in [0; 1] range.
+    fn sample(&self, tex_coord: Vec2) -> [f32; 4] {
+        match self {
+            Sampler::White => [1.0; 4],
+            Sampler::Image(image) => {
+                bilinear(image.width, image.height, tex_coord, |x, y| image.texel(x, y))
+            }
+            Sampler::Atlas { pixels, size } => {
+                bilinear(*size, *size, tex_coord, |x, y| {
+                    let value = pixels.get(y * size + x).map_or(0.0, |p| f32::from(*p) / 255.0);
+                    [value; 4]
+                })
+            }
+        }
+    }
+}
+
+fn bilinear<F>(width: usize, height: usize, tex_coord: Vec2, fetch: F) -> [f32; 4]
+    where F: Fn(usize, usize) -> [f32; 4] {
+    if width == 0 || height == 0 {
+        return [0.0; 4];
+    }
+
+    let x = (tex_coord.x * width as f32 - 0.5).max(0.0).min((width - 1) as f32);
+    let y = (tex_coord.y * height as f32 - 0.5).max(0.0).min((height - 1) as f32);
+    let (x0, y0) = (x as usize, y as usize);
+    let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+    let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+
+    let (a, b, c, d) = (fetch(x0, y0), fetch(x1, y0), fetch(x0, y1), fetch(x1, y1));
+    let mut result = [0.0; 4];
+    for (i, value) in result.iter_mut().enumerate() {
+        let top = a[i] + (b[i] - a[i]) * tx;
+        let bottom = c[i] + (d[i] - c[i]) * tx;
+        *value = top + (bottom - top) * ty;
+    }
+    result
+}
+
+fn color_to_rgba(color: Color) -> [f32; 4] {
+    [
+        f32::from(color.r) / 255.0,
+        f32::from(color.g) / 255.0,
+        f32::from(color.b) / 255.0,
+        f32::from(color.a) / 255.0,
+    ]
+}
+
+/// Color of gradient at given position, stops are expected to be sorted by `stop`.
+fn gradient_color(stops: &[GradientPoint], t: f32) -> [f32; 4] {
+    let (first, last) = match (stops.first(), stops.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return [0.0; 4],
+    };
+    if t <= first.stop {
+        return color_to_rgba(first.color);
+    }
+    for pair in stops.windows(2) {
+        let (left, right) = (&pair[0], &pair[1]);
+        if t <= right.stop {
+            let k = if right.stop > left.stop { (t - left.stop) / (right.stop - left.stop) } else { 1.0 };
+            let (a, b) = (color_to_rgba(left.color), color_to_rgba(right.color));
+            let mut result = [0.0; 4];
+            for (i, value) in result.iter_mut().enumerate() {
+                *value = a[i] + (b[i] - a[i]) * k;
+            }
+            return result;
+        }
+    }
+    color_to_rgba(last.color)
+}
+
+/// Color of brush at given point on screen. Points of gradients are in local coordinates
+/// of command bounds - (0, 0) is top left corner, (1, 1) is bottom right corner.
+fn brush_color(brush: &Brush, command: &Command, point: Vec2) -> [f32; 4] {
+    let size = command.max() - command.min();
+    let local = Vec2::new(
+        if size.x > 0.0 { (point.x - command.min().x) / size.x } else { 0.0 },
+        if size.y > 0.0 { (point.y - command.min().y) / size.y } else { 0.0 },
+    );
+    match brush {
+        Brush::Solid(color) => color_to_rgba(*color),
+        Brush::LinearGradient { from, to, stops } => {
+            let dx = to.x - from.x;
+            let dy = to.y - from.y;
+            let sqr_len = dx * dx + dy * dy;
+            let t = if sqr_len > 0.0 {
+                ((local.x - from.x) * dx + (local.y - from.y) * dy) / sqr_len
+            } else {
+                0.0
+            };
+            gradient_color(stops, t.max(0.0).min(1.0))
+        }
+        Brush::RadialGradient { center, stops } => {
+            let dx = local.x - center.x;
+            let dy = local.y - center.y;
+            gradient_color(stops, (dx * dx + dy * dy).sqrt().max(0.0).min(1.0))
+        }
+    }
+}
+
+#[inline]
+fn edge(a: Vec2, b: Vec2, p: Vec2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Top-left fill rule, so pixels on edge shared by two triangles are drawn only once.
+#[inline]
+fn is_top_left(a: Vec2, b: Vec2) -> bool {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    (dy == 0.0 && dx > 0.0) || dy < 0.0
+}
+
+/// Calls `pixel_func` for every pixel which center lies inside of triangle, with barycentric
+/// weights of vertices.
+fn rasterize_triangle<F>(width: usize, height: usize, vertices: [&Vertex; 3], mut pixel_func: F)
+    where F: FnMut(usize, usize, [f32; 3]) {
+    let (mut a, mut b, mut c) = (vertices[0].pos(), vertices[1].pos(), vertices[2].pos());
+    let mut area = edge(a, b, c);
+    let mut swapped = false;
+    if area < 0.0 {
+        std::mem::swap(&mut b, &mut c);
+        area = -area;
+        swapped = true;
+    }
+    if area <= std::f32::EPSILON || width == 0 || height == 0 {
+        return;
+    }
+
+    let min_x = a.x.min(b.x).min(c.x).floor().max(0.0) as usize;
+    let min_y = a.y.min(b.y).min(c.y).floor().max(0.0) as usize;
+    let max_x = (a.x.max(b.x).max(c.x).ceil().max(0.0) as usize).min(width - 1);
+    let max_y = (a.y.max(b.y).max(c.y).ceil().max(0.0) as usize).min(height - 1);
+
+    let (bias_a, bias_b, bias_c) = (is_top_left(b, c), is_top_left(c, a), is_top_left(a, b));
+    let inside = |w: f32, top_left: bool| w > 0.0 || (w == 0.0 && top_left);
+
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let p = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let wa = edge(b, c, p);
+            let wb = edge(c, a, p);
+            let wc = edge(a, b, p);
+            if inside(wa, bias_a) && inside(wb, bias_b) && inside(wc, bias_c) {
+                let (wa, wb, wc) = (wa / area, wb / area, wc / area);
+                pixel_func(x, y, if swapped { [wa, wc, wb] } else { [wa, wb, wc] });
+            }
+        }
+    }
+}
+
+/// Renders drawing context to RGBA image on CPU.
+pub struct SoftwareRenderer {
+    frame: RgbaImage,
+    stencil: Vec<u8>,
+    clear_color: Color,
+}
+
+impl SoftwareRenderer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            frame: RgbaImage::new(width, height),
+            stencil: vec![0; width * height],
+            clear_color: Color::TRANSPARENT,
+        }
+    }
+
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.frame = RgbaImage::new(width, height);
+        self.stencil = vec![0; width * height];
+    }
+
+    /// Sets color which is used to clear frame before rendering.
+    pub fn set_clear_color(&mut self, color: Color) {
+        self.clear_color = color;
+    }
+
+    pub fn clear_color(&self) -> Color {
+        self.clear_color
+    }
+
+    /// Returns result of last rendering.
+    pub fn frame(&self) -> &RgbaImage {
+        &self.frame
+    }
+
+    /// Clears frame and renders all commands of drawing context to it. Textures of commands
+    /// must be `RgbaImage`s to be sampled, fonts are sampled directly from their atlases.
+    pub fn render(&mut self, drawing_context: &DrawingContext) -> &RgbaImage {
+        self.frame.fill(self.clear_color);
+        for value in self.stencil.iter_mut() {
+            *value = 0;
+        }
+
+        for command in drawing_context.get_commands() {
+            match command.get_kind() {
+                CommandKind::Clip => self.draw_clip(drawing_context, command),
+                CommandKind::Geometry => self.draw_geometry(drawing_context, command),
+            }
+        }
+
+        &self.frame
+    }
+
+    fn command_triangles<'a>(drawing_context: &'a DrawingContext, command: &Command) -> impl Iterator<Item=[&'a Vertex; 3]> {
+        let vertices = drawing_context.get_vertices();
+        let start = command.get_start_triangle();
+        drawing_context.get_triangles()
+            .iter()
+            .skip(start)
+            .take(command.get_triangle_count())
+            .filter_map(move |triangle| {
+                Some([
+                    vertices.get(triangle[0] as usize)?,
+                    vertices.get(triangle[1] as usize)?,
+                    vertices.get(triangle[2] as usize)?,
+                ])
+            })
+    }
+
+    /// Clipping geometry of first level resets stencil buffer, any other level is clipped
+    /// by geometry of its parent - stencil is incremented only where it equals to
+    /// previous level.
+    fn draw_clip(&mut self, drawing_context: &DrawingContext, command: &Command) {
+        let nesting = command.get_nesting();
+        if nesting <= 1 {
+            for value in self.stencil.iter_mut() {
+                *value = 0;
+            }
+        }
+        let parent_level = nesting.saturating_sub(1);
+
+        let (width, height) = (self.frame.width, self.frame.height);
+        let stencil = &mut self.stencil;
+        for triangle in Self::command_triangles(drawing_context, command) {
+            rasterize_triangle(width, height, triangle, |x, y, _| {
+                let value = &mut stencil[y * width + x];
+                if *value == parent_level {
+                    *value = value.saturating_add(1);
+                }
+            });
+        }
+    }
+
+    fn draw_geometry(&mut self, drawing_context: &DrawingContext, command: &Command) {
+        let nesting = command.get_nesting();
+
+        // Keep lock of font for whole command, atlas must not change while it is sampled.
+        let font = match command.texture() {
+            CommandTexture::Font { font, .. } => Some(font.lock().unwrap()),
+            _ => None,
+        };
+        let (sampler, spread) = match (command.texture(), font.as_ref()) {
+            (CommandTexture::Font { distance_field, .. }, Some(font)) => {
+                let spread = match font.raster_mode() {
+                    GlyphRasterMode::DistanceField { spread } if *distance_field => Some(spread.max(std::f32::EPSILON)),
+                    _ => None,
+                };
+                (Sampler::Atlas { pixels: font.get_atlas_pixels(), size: font.get_atlas_size().max(0) as usize }, spread)
+            }
+            (CommandTexture::Texture(texture), _) => {
+                match texture.downcast_ref::<RgbaImage>() {
+                    Some(image) => (Sampler::Image(image), None),
+                    None => (Sampler::White, None),
+                }
+            }
+            _ => (Sampler::White, None),
+        };
+        let is_atlas = matches!(sampler, Sampler::Atlas { .. });
+
+        let (width, height) = (self.frame.width, self.frame.height);
+        let stencil = &self.stencil;
+        let pixels = &mut self.frame.pixels;
+        let brush = command.brush();
+        for triangle in Self::command_triangles(drawing_context, command) {
+            // Width of transition between outside and inside of glyph in distance units,
+            // it must be about one pixel on screen.
+            let smoothing = match (spread, &sampler) {
+                (Some(spread), Sampler::Atlas { size, .. }) => {
+                    let texels_per_pixel = texels_per_pixel(triangle, *size as f32);
+                    (0.25 * texels_per_pixel / spread).max(0.001)
+                }
+                _ => 0.0,
+            };
+
+            rasterize_triangle(width, height, triangle, |x, y, weights| {
+                // Geometry of zero nesting level is not clipped.
+                if nesting != 0 && stencil[y * width + x] != nesting {
+                    return;
+                }
+
+                let tex_coord = Vec2::new(
+                    triangle[0].tex_coord().x * weights[0] + triangle[1].tex_coord().x * weights[1] + triangle[2].tex_coord().x * weights[2],
+                    triangle[0].tex_coord().y * weights[0] + triangle[1].tex_coord().y * weights[1] + triangle[2].tex_coord().y * weights[2],
+                );
+                let mut color = brush_color(brush, command, Vec2::new(x as f32 + 0.5, y as f32 + 0.5));
+                let texel = sampler.sample(tex_coord);
+                if is_atlas {
+                    color[3] *= if spread.is_some() {
+                        smoothstep(0.5 - smoothing, 0.5 + smoothing, texel[3])
+                    } else {
+                        texel[3]
+                    };
+                } else {
+                    for (value, texel_value) in color.iter_mut().zip(texel.iter()) {
+                        *value *= texel_value;
+                    }
+                }
+
+                blend(&mut pixels[(y * width + x) * 4..(y * width + x) * 4 + 4], color);
+            });
+        }
+    }
+}
+
+/// Approximate amount of texels of atlas covered by one pixel of triangle on screen.
+fn texels_per_pixel(triangle: [&Vertex; 3], atlas_size: f32) -> f32 {
+    let screen_area = edge(triangle[0].pos(), triangle[1].pos(), triangle[2].pos()).abs();
+    let uv_area = edge(triangle[0].tex_coord(), triangle[1].tex_coord(), triangle[2].tex_coord()).abs() * atlas_size * atlas_size;
+    if screen_area > 0.0 { (uv_area / screen_area).sqrt() } else { 1.0 }
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).max(0.0).min(1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Source-over alpha blending.
+fn blend(dest: &mut [u8], src: [f32; 4]) {
+    let src_alpha = src[3].max(0.0).min(1.0);
+    let dest_alpha = f32::from(dest[3]) / 255.0;
+    let out_alpha = src_alpha + dest_alpha * (1.0 - src_alpha);
+    for (dest_value, src_value) in dest.iter_mut().zip(src.iter()).take(3) {
+        let value = if out_alpha > 0.0 {
+            (src_value * src_alpha + f32::from(*dest_value) / 255.0 * dest_alpha * (1.0 - src_alpha)) / out_alpha
+        } else {
+            0.0
+        };
+        *dest_value = (value.max(0.0).min(1.0) * 255.0).round() as u8;
+    }
+    dest[3] = (out_alpha * 255.0).round() as u8;
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        software_renderer::{SoftwareRenderer, RgbaImage},
+        draw::{DrawingContext, CommandKind, CommandTexture},
+        brush::{Brush, GradientPoint},
+        ttf::Font,
+        core::{
+            color::Color,
+            math::{vec2::Vec2, Rect},
+        },
+    };
+    use std::sync::{Arc, Mutex};
+
+    fn fill_rect(context: &mut DrawingContext, rect: Rect<f32>, brush: Brush) {
+        context.push_rect_filled(&rect, None);
+        context.commit(CommandKind::Geometry, brush, CommandTexture::None);
+    }
+
+    fn assert_channels(image: &RgbaImage, x: usize, y: usize, expected: [u8; 4]) {
+        let color = image.get_pixel(x, y).unwrap();
+        let actual = [color.r, color.g, color.b, color.a];
+        for (a, e) in actual.iter().zip(expected.iter()) {
+            assert!((i32::from(*a) - i32::from(*e)).abs() <= 1, "pixel ({}, {}) is {:?}, expected {:?}", x, y, actual, expected);
+        }
+    }
+
+    #[test]
+    fn nested_clip_is_intersection_with_parent() {
+        let red = Color::opaque(255, 0, 0);
+        let green = Color::opaque(0, 255, 0);
+        let blue = Color::opaque(0, 0, 255);
+        let mut context = DrawingContext::new();
+
+        context.set_nesting(1);
+        context.commit_clip_rect(&Rect::new(0.0, 0.0, 6.0, 6.0));
+        context.set_nesting(2);
+        context.commit_clip_rect(&Rect::new(3.0, 3.0, 6.0, 6.0));
+        fill_rect(&mut context, Rect::new(0.0, 0.0, 10.0, 10.0), Brush::Solid(red));
+        // Parent clip is restored after child, so geometry of parent is clipped by it again.
+        context.revert_clip_geom();
+        context.set_nesting(1);
+        fill_rect(&mut context, Rect::new(0.0, 0.0, 10.0, 1.0), Brush::Solid(green));
+        context.revert_clip_geom();
+        // Geometry of zero nesting is not clipped at all.
+        context.set_nesting(0);
+        fill_rect(&mut context, Rect::new(0.0, 9.0, 10.0, 1.0), Brush::Solid(blue));
+
+        let mut renderer = SoftwareRenderer::new(10, 10);
+        let frame = renderer.render(&context);
+
+        for y in 1..9 {
+            for x in 0..10 {
+                let expected = if (3..6).contains(&x) && (3..6).contains(&y) {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 0, 0, 0]
+                };
+                assert_channels(frame, x, y, expected);
+            }
+        }
+        for x in 0..10 {
+            assert_channels(frame, x, 0, if x < 6 { [0, 255, 0, 255] } else { [0, 0, 0, 0] });
+            assert_channels(frame, x, 9, [0, 0, 255, 255]);
+        }
+    }
+
+    #[test]
+    fn linear_gradient() {
+        let mut context = DrawingContext::new();
+        fill_rect(&mut context, Rect::new(0.0, 0.0, 10.0, 2.0), Brush::LinearGradient {
+            from: Vec2::new(0.0, 0.0),
+            to: Vec2::new(1.0, 0.0),
+            stops: vec![
+                GradientPoint { stop: 0.0, color: Color::opaque(255, 0, 0) },
+                GradientPoint { stop: 1.0, color: Color::opaque(0, 0, 255) },
+            ],
+        });
+
+        let mut renderer = SoftwareRenderer::new(10, 2);
+        let frame = renderer.render(&context);
+
+        for y in 0..2 {
+            for x in 0..10 {
+                // Gradient is sampled at centers of pixels.
+                let t = (x as f32 + 0.5) / 10.0;
+                let red = ((1.0 - t) * 255.0).round() as u8;
+                let blue = (t * 255.0).round() as u8;
+                assert_channels(frame, x, y, [red, 0, blue, 255]);
+            }
+        }
+    }
+
+    #[test]
+    fn radial_gradient() {
+        let mut context = DrawingContext::new();
+        fill_rect(&mut context, Rect::new(0.0, 0.0, 10.0, 10.0), Brush::RadialGradient {
+            center: Vec2::new(0.5, 0.5),
+            stops: vec![
+                GradientPoint { stop: 0.0, color: Color::opaque(255, 255, 255) },
+                GradientPoint { stop: 0.5, color: Color::opaque(0, 0, 0) },
+            ],
+        });
+
+        let mut renderer = SoftwareRenderer::new(10, 10);
+        let frame = renderer.render(&context);
+
+        for y in 0..10 {
+            for x in 0..10 {
+                let dx = (x as f32 + 0.5) / 10.0 - 0.5;
+                let dy = (y as f32 + 0.5) / 10.0 - 0.5;
+                let distance = (dx * dx + dy * dy).sqrt();
+                let value = ((1.0 - (distance / 0.5).min(1.0)) * 255.0).round() as u8;
+                assert_channels(frame, x, y, [value, value, value, 255]);
+            }
+        }
+        // Symmetric around center, darkest in corners.
+        assert_eq!(frame.get_pixel(4, 4), frame.get_pixel(5, 5));
+        assert_channels(frame, 0, 0, [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn font_atlas_is_used_as_alpha() {
+        let font_bytes = std::include_bytes!("built_in_font.ttf").to_vec();
+        let font = Font::from_memory(font_bytes, 20.0, Font::default_char_set()).unwrap();
+        let size = font.get_atlas_size() as usize;
+        assert!(size > 0);
+        let atlas = font.get_atlas_pixels().to_vec();
+        assert!(atlas.iter().any(|value| *value == 255));
+        let font = Arc::new(Mutex::new(font));
+
+        // Whole atlas is drawn texel to pixel, so bilinear sampling hits centers of texels.
+        let mut context = DrawingContext::new();
+        context.push_rect_filled(&Rect::new(0.0, 0.0, size as f32, size as f32), None);
+        context.commit(CommandKind::Geometry, Brush::Solid(Color::opaque(0, 255, 0)), CommandTexture::Font {
+            font,
+            distance_field: false,
+        });
+
+        let mut renderer = SoftwareRenderer::new(size, size);
+        let frame = renderer.render(&context);
+
+        for y in 0..size {
+            for x in 0..size {
+                let alpha = atlas[y * size + x];
+                let pixel = frame.get_pixel(x, y).unwrap();
+                assert!((i32::from(pixel.a) - i32::from(alpha)).abs() <= 1, "pixel ({}, {})", x, y);
+                if alpha == 255 {
+                    assert_channels(frame, x, y, [0, 255, 0, 255]);
+                }
+            }
+        }
+    }
+}
@@ -14,7 +14,8 @@ use crate::{
         },
         pool::Handle,
     },
-    message::UiMessage
+    message::UiMessage,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 pub struct StackPanel<M: 'static, C: 'static + Control<M, C>> {
@@ -172,6 +173,13 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for StackPanel<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for StackPanel<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit("orientation", &mut self.orientation)
+    }
+}
+
 pub struct StackPanelBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     orientation: Option<Orientation>,
@@ -202,4 +210,66 @@ impl<M, C: 'static + Control<M, C>> StackPanelBuilder<M, C> {
 
         handle
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        stack_panel::StackPanelBuilder,
+        border::BorderBuilder,
+        widget::WidgetBuilder,
+        golden::GoldenTest,
+        test::{StubUiMessage, StubUiNode},
+        scroll_bar::Orientation,
+        brush::Brush,
+        node::UINode,
+        Thickness,
+        UserInterface,
+        HorizontalAlignment,
+        core::{
+            color::Color,
+            pool::Handle,
+        },
+    };
+
+    type Ui = UserInterface<StubUiMessage, StubUiNode>;
+
+    fn item(ui: &mut Ui, width: f32, height: f32, color: Color) -> Handle<UINode<StubUiMessage, StubUiNode>> {
+        BorderBuilder::new(WidgetBuilder::new()
+            .with_width(width)
+            .with_height(height)
+            .with_margin(Thickness::uniform(3.0))
+            .with_background(Brush::Solid(color)))
+            .build(ui)
+    }
+
+    fn build_panel(ui: &mut Ui, orientation: Orientation) {
+        let children = [
+            item(ui, 40.0, 30.0, Color::opaque(200, 0, 0)),
+            item(ui, 60.0, 50.0, Color::opaque(0, 200, 0)),
+            item(ui, 20.0, 70.0, Color::opaque(0, 0, 200)),
+        ];
+        StackPanelBuilder::new(WidgetBuilder::new()
+            .with_width(200.0)
+            .with_height(200.0)
+            .with_margin(Thickness::uniform(10.0))
+            .with_horizontal_alignment(HorizontalAlignment::Left)
+            .with_children(&children))
+            .with_orientation(orientation)
+            .build(ui);
+    }
+
+    #[test]
+    fn stack_panel_vertical() {
+        let mut ui = Ui::new();
+        build_panel(&mut ui, Orientation::Vertical);
+        GoldenTest::new("stack_panel_vertical").assert(&mut ui);
+    }
+
+    #[test]
+    fn stack_panel_horizontal() {
+        let mut ui = Ui::new();
+        build_panel(&mut ui, Orientation::Horizontal);
+        GoldenTest::new("stack_panel_horizontal").assert(&mut ui);
+    }
+}
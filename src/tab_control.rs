@@ -23,7 +23,8 @@ use crate::{
     },
     brush::Brush,
     message::ButtonMessage,
-    NodeHandleMapping
+    NodeHandleMapping,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 pub struct Tab<M: 'static, C: 'static + Control<M, C>> {
@@ -90,6 +91,33 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for TabControl<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Default for TabControl<M, C> {
+    fn default() -> Self {
+        Self {
+            widget: Widget::default(),
+            tabs: Vec::new(),
+        }
+    }
+}
+
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for TabControl<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        let mut count = self.tabs.len();
+        visitor.visit_len("tabs", &mut count, "header_button")?;
+        if visitor.is_reading() {
+            self.tabs.resize_with(count, || Tab { header_button: Handle::NONE, content: Handle::NONE });
+        }
+        for (i, tab) in self.tabs.iter_mut().enumerate() {
+            visitor.enter_region(&format!("tabs.{}", i));
+            visitor.visit_handle("header_button", &mut tab.header_button)?;
+            visitor.visit_handle("content", &mut tab.content)?;
+            visitor.leave_region();
+        }
+        Ok(())
+    }
+}
+
 pub struct TabControlBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     tabs: Vec<TabDefinition<M, C>>,
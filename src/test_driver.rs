@@ -0,0 +1,236 @@
+// Scripted interaction with user interface for headless tests. Driver translates high level
+// actions such as click or typing into OS events, resolves positions of nodes from their
+// screen bounds, advances time and collects every message that UI has emitted.
+
+use crate::{
+    UserInterface,
+    Control,
+    UINode,
+    message::{
+        OsEvent,
+        UiMessage,
+        ButtonState,
+        MouseButton,
+        KeyCode,
+        KeyboardModifiers,
+    },
+    core::{
+        pool::Handle,
+        math::vec2::Vec2,
+    },
+};
+
+pub struct TestDriver<'a, M: 'static, C: 'static + Control<M, C>> {
+    ui: &'a mut UserInterface<M, C>,
+    screen_size: Vec2,
+    time_step: f32,
+    drag_steps: usize,
+    messages: Vec<UiMessage<M, C>>,
+}
+
+impl<'a, M, C: 'static + Control<M, C>> TestDriver<'a, M, C> {
+    /// Creates new driver and performs first update, so nodes get their screen bounds.
+    pub fn new(ui: &'a mut UserInterface<M, C>, screen_size: Vec2) -> Self {
+        let mut driver = Self {
+            ui,
+            screen_size,
+            time_step: 1.0 / 60.0,
+            drag_steps: 4,
+            messages: Vec::new(),
+        };
+        driver.advance(0.0);
+        driver
+    }
+
+    /// Sets amount of time that passes after each action, default is 1/60 of a second.
+    pub fn set_time_step(&mut self, time_step: f32) -> &mut Self {
+        self.time_step = time_step;
+        self
+    }
+
+    /// Sets amount of intermediate cursor moves of `drag`.
+    pub fn set_drag_steps(&mut self, steps: usize) -> &mut Self {
+        self.drag_steps = steps.max(1);
+        self
+    }
+
+    pub fn ui(&self) -> &UserInterface<M, C> {
+        self.ui
+    }
+
+    pub fn ui_mut(&mut self) -> &mut UserInterface<M, C> {
+        self.ui
+    }
+
+    /// Returns every message emitted since creation of the driver or since last call of
+    /// `take_messages`.
+    pub fn messages(&self) -> &[UiMessage<M, C>] {
+        &self.messages
+    }
+
+    pub fn take_messages(&mut self) -> Vec<UiMessage<M, C>> {
+        std::mem::take(&mut self.messages)
+    }
+
+    /// Updates UI with given time delta and collects emitted messages. Messages are polled
+    /// after update like a game loop does, so same actions replayed by `InputRecording`
+    /// produce same messages.
+    pub fn advance(&mut self, dt: f32) -> &mut Self {
+        self.ui.update(self.screen_size, dt);
+        self.pump();
+        self
+    }
+
+    /// Sends raw OS event, then advances time by time step.
+    pub fn send(&mut self, event: &OsEvent) -> &mut Self {
+        self.ui.process_os_event(event);
+        self.advance(self.time_step)
+    }
+
+    fn pump(&mut self) {
+        while let Some(message) = self.ui.poll_message() {
+            self.messages.push(message);
+        }
+    }
+
+    /// Returns center of screen bounds of a node.
+    pub fn node_center(&self, node: Handle<UINode<M, C>>) -> Vec2 {
+        let bounds = self.ui.node(node).widget().screen_bounds();
+        Vec2::new(bounds.x + bounds.w * 0.5, bounds.y + bounds.h * 0.5)
+    }
+
+    pub fn move_cursor(&mut self, position: Vec2) -> &mut Self {
+        self.send(&OsEvent::CursorMoved { position })
+    }
+
+    pub fn mouse_down(&mut self, button: MouseButton) -> &mut Self {
+        self.send(&OsEvent::MouseInput { button, state: ButtonState::Pressed })
+    }
+
+    pub fn mouse_up(&mut self, button: MouseButton) -> &mut Self {
+        self.send(&OsEvent::MouseInput { button, state: ButtonState::Released })
+    }
+
+    /// Moves cursor to given position and clicks there with given button.
+    pub fn click_at(&mut self, position: Vec2, button: MouseButton) -> &mut Self {
+        self.move_cursor(position)
+            .mouse_down(button)
+            .mouse_up(button)
+    }
+
+    /// Clicks with left mouse button at center of a node.
+    pub fn click(&mut self, node: Handle<UINode<M, C>>) -> &mut Self {
+        let center = self.node_center(node);
+        self.click_at(center, MouseButton::Left)
+    }
+
+    /// Presses left mouse button at `from`, moves cursor to `to` in few steps and
+    /// releases the button.
+    pub fn drag(&mut self, from: Vec2, to: Vec2) -> &mut Self {
+        self.move_cursor(from).mouse_down(MouseButton::Left);
+        for i in 1..=self.drag_steps {
+            let t = i as f32 / self.drag_steps as f32;
+            self.move_cursor(Vec2::new(from.x + (to.x - from.x) * t, from.y + (to.y - from.y) * t));
+        }
+        self.mouse_up(MouseButton::Left)
+    }
+
+    /// Moves cursor to center of a node and rotates mouse wheel by given amount.
+    pub fn scroll(&mut self, node: Handle<UINode<M, C>>, amount: f32) -> &mut Self {
+        let center = self.node_center(node);
+        self.move_cursor(center)
+            .send(&OsEvent::MouseWheel(0.0, amount))
+    }
+
+    pub fn key_down(&mut self, code: KeyCode) -> &mut Self {
+        self.send(&OsEvent::KeyboardInput { button: code, state: ButtonState::Pressed })
+    }
+
+    pub fn key_up(&mut self, code: KeyCode) -> &mut Self {
+        self.send(&OsEvent::KeyboardInput { button: code, state: ButtonState::Released })
+    }
+
+    /// Presses and releases a key while given modifier keys are held, state of modifiers
+    /// is restored afterwards.
+    pub fn press(&mut self, code: KeyCode, modifiers: KeyboardModifiers) -> &mut Self {
+        let prev_modifiers = self.ui.keyboard_modifiers();
+        self.send(&OsEvent::KeyboardModifiers(modifiers))
+            .key_down(code)
+            .key_up(code)
+            .send(&OsEvent::KeyboardModifiers(prev_modifiers))
+    }
+
+    /// Types text into node with keyboard focus, line breaks are typed as `Return` key.
+    pub fn type_text(&mut self, text: &str) -> &mut Self {
+        for c in text.chars() {
+            if c == '\n' {
+                self.press(KeyCode::Return, KeyboardModifiers::default());
+            } else {
+                self.send(&OsEvent::Character(c));
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        test_driver::TestDriver,
+        button::ButtonBuilder,
+        text_box::TextBoxBuilder,
+        widget::WidgetBuilder,
+        test::{StubUiMessage, StubUiNode},
+        message::{
+            UiMessageData,
+            ButtonMessage,
+            TextBoxMessage,
+        },
+        UserInterface,
+        core::math::vec2::Vec2,
+    };
+
+    #[test]
+    fn click_emits_button_click() {
+        let mut ui = UserInterface::<StubUiMessage, StubUiNode>::new();
+        let button = ButtonBuilder::new(WidgetBuilder::new()
+            .with_width(100.0)
+            .with_height(30.0))
+            .with_text("Click")
+            .build(&mut ui);
+
+        let mut driver = TestDriver::new(&mut ui, Vec2::new(200.0, 200.0));
+        driver.click(button);
+
+        let clicked = driver.messages().iter().any(|message| {
+            if let UiMessageData::Button(ButtonMessage::Click) = message.data {
+                message.source == button
+            } else {
+                false
+            }
+        });
+        assert!(clicked);
+    }
+
+    #[test]
+    fn type_text_into_text_box() {
+        let mut ui = UserInterface::<StubUiMessage, StubUiNode>::new();
+        let text_box = TextBoxBuilder::new(WidgetBuilder::new()
+            .with_width(150.0)
+            .with_height(30.0))
+            .build(&mut ui);
+
+        let mut driver = TestDriver::new(&mut ui, Vec2::new(200.0, 200.0));
+        driver.click(text_box)
+            .type_text("hello\n");
+
+        let submitted = driver.take_messages().into_iter().find_map(|message| {
+            if let UiMessageData::TextBox(TextBoxMessage::Submitted(text)) = message.data {
+                Some(text)
+            } else {
+                None
+            }
+        });
+        assert_eq!(submitted, Some("hello".to_owned()));
+    }
+}
@@ -10,6 +10,8 @@ use crate::{
     formatted_text::{
         FormattedText,
         FormattedTextBuilder,
+        WrapMode,
+        TextSpan,
     },
     widget::{
         Widget,
@@ -20,7 +22,8 @@ use crate::{
     ttf::Font,
     UserInterface,
     brush::Brush,
-    message::UiMessage
+    message::UiMessage,
+    serialization::{Visit, Visitor, VisitResult},
 };
 use std::{
     sync::{
@@ -85,18 +88,80 @@ impl<M, C: 'static + Control<M, C>> Text<M, C> {
         self
     }
 
+    /// Enables or disables wrapping at characters, see `set_wrap_mode`.
     pub fn set_wrap(&mut self, wrap: bool) -> &mut Self {
+        self.set_wrap_mode(if wrap { WrapMode::Character } else { WrapMode::NoWrap })
+    }
+
+    pub fn is_wrap(&self) -> bool {
+        self.formatted_text
+            .borrow()
+            .is_wrap()
+    }
+
+    pub fn set_wrap_mode(&mut self, wrap: WrapMode) -> &mut Self {
         self.formatted_text
             .borrow_mut()
-            .set_wrap(wrap);
+            .set_wrap_mode(wrap);
         self.widget.invalidate_layout();
         self
     }
 
-    pub fn is_wrap(&self) -> bool {
+    pub fn wrap_mode(&self) -> WrapMode {
         self.formatted_text
             .borrow()
-            .is_wrap()
+            .wrap_mode()
+    }
+
+    /// Enables trimming of lines that do not fit in bounds, trimmed line ends with ellipsis.
+    pub fn set_ellipsis(&mut self, ellipsis: bool) -> &mut Self {
+        self.formatted_text
+            .borrow_mut()
+            .set_ellipsis(ellipsis);
+        self.widget.invalidate_layout();
+        self
+    }
+
+    pub fn is_ellipsis(&self) -> bool {
+        self.formatted_text
+            .borrow()
+            .is_ellipsis()
+    }
+
+    pub fn set_line_spacing(&mut self, line_spacing: f32) -> &mut Self {
+        self.formatted_text
+            .borrow_mut()
+            .set_line_spacing(line_spacing);
+        self.widget.invalidate_layout();
+        self
+    }
+
+    pub fn line_spacing(&self) -> f32 {
+        self.formatted_text
+            .borrow()
+            .line_spacing()
+    }
+
+    /// Sets styled ranges of text, later spans override properties of earlier ones.
+    pub fn set_spans(&mut self, spans: Vec<TextSpan>) -> &mut Self {
+        self.formatted_text.borrow_mut().set_spans(spans);
+        self.widget.invalidate_layout();
+        self
+    }
+
+    pub fn spans(&self) -> Vec<TextSpan> {
+        self.formatted_text
+            .borrow()
+            .spans()
+            .to_vec()
+    }
+
+    /// Sets text and spans from markup such as `[color=#f00]red[/color]`. Supported tags
+    /// are `color`, `size`, `u` (underline) and `s` (strikethrough).
+    pub fn set_markup<P: AsRef<str>>(&mut self, markup: P) -> &mut Self {
+        self.formatted_text.borrow_mut().set_markup(markup);
+        self.widget.invalidate_layout();
+        self
     }
 
     pub fn text(&self) -> String {
@@ -120,6 +185,38 @@ impl<M, C: 'static + Control<M, C>> Text<M, C> {
             .unwrap()
     }
 
+    /// Sets fonts which will be used for characters that are missing in primary font.
+    pub fn set_fallback_fonts(&mut self, fonts: Vec<Arc<Mutex<Font>>>) -> &mut Self {
+        self.formatted_text
+            .borrow_mut()
+            .set_fallback_fonts(fonts);
+        self.widget.invalidate_layout();
+        self
+    }
+
+    pub fn fallback_fonts(&self) -> Vec<Arc<Mutex<Font>>> {
+        self.formatted_text
+            .borrow()
+            .fallback_fonts()
+            .to_vec()
+    }
+
+    /// Sets height of text in pixels, `None` means height of font. Use distance field
+    /// font to draw text of size other than height of font.
+    pub fn set_font_size(&mut self, font_size: Option<f32>) -> &mut Self {
+        self.formatted_text
+            .borrow_mut()
+            .set_font_size(font_size);
+        self.widget.invalidate_layout();
+        self
+    }
+
+    pub fn font_size(&self) -> Option<f32> {
+        self.formatted_text
+            .borrow()
+            .font_size()
+    }
+
     pub fn set_vertical_alignment(&mut self, valign: VerticalAlignment) -> &mut Self {
         self.formatted_text
             .borrow_mut()
@@ -149,12 +246,29 @@ impl<M, C: 'static + Control<M, C>> Text<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for Text<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.enter_region("formatted_text");
+        self.formatted_text.get_mut().visit(visitor)?;
+        visitor.leave_region();
+        Ok(())
+    }
+}
+
 pub struct TextBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     text: Option<String>,
     font: Option<Arc<Mutex<Font>>>,
+    fallback_fonts: Vec<Arc<Mutex<Font>>>,
+    font_size: Option<f32>,
     vertical_text_alignment: VerticalAlignment,
     horizontal_text_alignment: HorizontalAlignment,
+    wrap: WrapMode,
+    ellipsis: bool,
+    line_spacing: Option<f32>,
+    spans: Vec<TextSpan>,
+    markup: Option<String>,
 }
 
 impl<M, C: 'static + Control<M, C>> TextBuilder<M, C> {
@@ -163,8 +277,15 @@ impl<M, C: 'static + Control<M, C>> TextBuilder<M, C> {
             widget_builder,
             text: None,
             font: None,
+            fallback_fonts: Vec::new(),
+            font_size: None,
             vertical_text_alignment: VerticalAlignment::Top,
             horizontal_text_alignment: HorizontalAlignment::Left,
+            wrap: WrapMode::NoWrap,
+            ellipsis: false,
+            line_spacing: None,
+            spans: Vec::new(),
+            markup: None,
         }
     }
 
@@ -183,6 +304,44 @@ impl<M, C: 'static + Control<M, C>> TextBuilder<M, C> {
         self
     }
 
+    /// Sets fonts which will be used for characters that are missing in primary font,
+    /// for example emoji or CJK font.
+    pub fn with_fallback_fonts(mut self, fonts: Vec<Arc<Mutex<Font>>>) -> Self {
+        self.fallback_fonts = fonts;
+        self
+    }
+
+    pub fn with_font_size(mut self, font_size: f32) -> Self {
+        self.font_size = Some(font_size);
+        self
+    }
+
+    pub fn with_spans(mut self, spans: Vec<TextSpan>) -> Self {
+        self.spans = spans;
+        self
+    }
+
+    /// Sets text and spans from markup, text and spans set by other methods are ignored.
+    pub fn with_markup<P: AsRef<str>>(mut self, markup: P) -> Self {
+        self.markup = Some(markup.as_ref().to_owned());
+        self
+    }
+
+    pub fn with_wrap_mode(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_ellipsis(mut self, ellipsis: bool) -> Self {
+        self.ellipsis = ellipsis;
+        self
+    }
+
+    pub fn with_line_spacing(mut self, line_spacing: f32) -> Self {
+        self.line_spacing = Some(line_spacing);
+        self
+    }
+
     pub fn with_vertical_text_alignment(mut self, valign: VerticalAlignment) -> Self {
         self.vertical_text_alignment = valign;
         self
@@ -204,13 +363,24 @@ impl<M, C: 'static + Control<M, C>> TextBuilder<M, C> {
             self.widget_builder.foreground = Some(Brush::Solid(Color::opaque(220, 220, 220)));
         }
 
+        let mut formatted_text = FormattedTextBuilder::new()
+            .with_text(self.text.unwrap_or_default())
+            .with_spans(self.spans);
+        if let Some(markup) = self.markup {
+            formatted_text = formatted_text.with_markup(&markup);
+        }
+
         let handle = ui.add_node(UINode::Text(Text {
             widget: self.widget_builder.build(),
-            formatted_text: RefCell::new(FormattedTextBuilder::new()
-                .with_text(self.text.unwrap_or_default())
+            formatted_text: RefCell::new(formatted_text
                 .with_vertical_alignment(self.vertical_text_alignment)
                 .with_horizontal_alignment(self.horizontal_text_alignment)
                 .with_font(font)
+                .with_fallback_fonts(self.fallback_fonts)
+                .with_font_size(self.font_size)
+                .with_wrap_mode(self.wrap)
+                .with_ellipsis(self.ellipsis)
+                .with_line_spacing(self.line_spacing.unwrap_or(1.0))
                 .build()),
         }));
 
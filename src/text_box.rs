@@ -1,7 +1,9 @@
 use std::{
     cmp,
+    ops::Range,
     sync::{Mutex, Arc},
     cell::RefCell,
+    rc::Rc,
 };
 use crate::{
     brush::Brush,
@@ -25,6 +27,7 @@ use crate::{
     formatted_text::{
         FormattedText,
         FormattedTextBuilder,
+        WrapMode,
     },
     UINode,
     Control,
@@ -33,14 +36,24 @@ use crate::{
         UiMessageData,
         MouseButton,
         KeyCode,
+        TextBoxMessage,
+        CompositionEvent,
     },
+    clipboard::Clipboard,
     ttf::Font,
     VerticalAlignment,
     HorizontalAlignment,
     draw::CommandTexture,
     message::WidgetMessage,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
+/// Default maximum amount of steps that can be undone in a text box.
+pub const DEFAULT_HISTORY_DEPTH: usize = 100;
+
+/// Maximum time in seconds between two clicks to treat them as double click.
+const DOUBLE_CLICK_INTERVAL: f32 = 0.5;
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub enum HorizontalDirection {
     Left,
@@ -53,7 +66,7 @@ pub enum VerticalDirection {
     Up,
 }
 
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
 pub struct Position {
     line: usize,
     offset: usize,
@@ -65,6 +78,188 @@ pub struct SelectionRange {
     end: Position,
 }
 
+impl SelectionRange {
+    /// Returns range with begin placed before end. Range can be reversed when
+    /// user selects text from right to left.
+    fn normalized(&self) -> SelectionRange {
+        SelectionRange {
+            begin: cmp::min(self.begin, self.end),
+            end: cmp::max(self.begin, self.end),
+        }
+    }
+}
+
+/// Kind of edit, consecutive edits of same kind are merged into one undo step.
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum EditKind {
+    Typing,
+    Backspace,
+    Delete,
+    Other,
+}
+
+/// Single change of text - `removed` text at `position` was replaced with `inserted` text.
+#[derive(Clone)]
+struct Edit {
+    kind: EditKind,
+    position: usize,
+    removed: String,
+    inserted: String,
+    caret_before: Position,
+    selection_before: Option<SelectionRange>,
+    caret_after: Position,
+}
+
+#[derive(Clone)]
+struct EditHistory {
+    edits: Vec<Edit>,
+    /// Amount of applied edits, everything after it can be redone.
+    current: usize,
+    max_depth: usize,
+    /// Whether next edit can be merged with last one. Caret movement breaks the group.
+    can_merge: bool,
+}
+
+impl EditHistory {
+    fn new(max_depth: usize) -> Self {
+        Self {
+            edits: Vec::new(),
+            current: 0,
+            max_depth,
+            can_merge: false,
+        }
+    }
+
+    fn push(&mut self, edit: Edit) {
+        if self.max_depth == 0 {
+            return;
+        }
+
+        self.edits.truncate(self.current);
+
+        if self.can_merge && edit.kind != EditKind::Other {
+            if let Some(last) = self.edits.last_mut() {
+                if last.kind == edit.kind && last.try_merge(&edit) {
+                    return;
+                }
+            }
+        }
+
+        self.edits.push(edit);
+        if self.edits.len() > self.max_depth {
+            self.edits.remove(0);
+        }
+        self.current = self.edits.len();
+        self.can_merge = true;
+    }
+
+    /// Prevents next edit from being merged with last one.
+    fn seal(&mut self) {
+        self.can_merge = false;
+    }
+
+    fn undo(&mut self) -> Option<Edit> {
+        self.can_merge = false;
+        if self.current > 0 {
+            self.current -= 1;
+            Some(self.edits[self.current].clone())
+        } else {
+            None
+        }
+    }
+
+    fn redo(&mut self) -> Option<Edit> {
+        self.can_merge = false;
+        if self.current < self.edits.len() {
+            self.current += 1;
+            Some(self.edits[self.current - 1].clone())
+        } else {
+            None
+        }
+    }
+
+    fn can_undo(&self) -> bool {
+        self.current > 0
+    }
+
+    fn can_redo(&self) -> bool {
+        self.current < self.edits.len()
+    }
+
+    fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+        if self.edits.len() > max_depth {
+            let excess = self.edits.len() - max_depth;
+            self.edits.drain(0..excess);
+            self.current = self.current.saturating_sub(excess);
+        }
+    }
+
+    fn clear(&mut self) {
+        self.edits.clear();
+        self.current = 0;
+        self.can_merge = false;
+    }
+}
+
+impl Edit {
+    /// Tries to append next edit of same kind to this one, returns false if edits are
+    /// not adjacent.
+    fn try_merge(&mut self, next: &Edit) -> bool {
+        match self.kind {
+            EditKind::Typing => {
+                if next.position == self.position + self.inserted.chars().count() {
+                    self.inserted.push_str(&next.inserted);
+                    self.caret_after = next.caret_after;
+                    return true;
+                }
+            }
+            EditKind::Backspace => {
+                if next.position + next.removed.chars().count() == self.position {
+                    self.position = next.position;
+                    self.removed.insert_str(0, &next.removed);
+                    self.caret_after = next.caret_after;
+                    return true;
+                }
+            }
+            EditKind::Delete => {
+                if next.position == self.position {
+                    self.removed.push_str(&next.removed);
+                    self.caret_after = next.caret_after;
+                    return true;
+                }
+            }
+            EditKind::Other => ()
+        }
+        false
+    }
+}
+
+fn is_word_char(code: u32) -> bool {
+    std::char::from_u32(code).map_or(false, |c| c.is_alphanumeric() || c == '_')
+}
+
+fn filter_control_chars(str: &str) -> String {
+    str.chars()
+        .filter(|c| *c == '\n' || !c.is_control())
+        .collect()
+}
+
+/// Filter that decides whether a character can be inserted into a text box. It receives
+/// a character and text of a text box as it would be right before insertion of the
+/// character. Return false to reject the character.
+pub type TextFilter = Rc<dyn Fn(char, &str) -> bool>;
+
+/// Creates filter that accepts only decimal digits.
+pub fn digits_filter() -> TextFilter {
+    Rc::new(|c: char, _: &str| c.is_ascii_digit())
+}
+
+/// Creates filter that limits length of text to given amount of characters.
+pub fn max_length_filter(max_length: usize) -> TextFilter {
+    Rc::new(move |_: char, text: &str| text.chars().count() < max_length)
+}
+
 pub struct TextBox<M: 'static, C: 'static + Control<M, C>> {
     widget: Widget<M, C>,
     caret_line: usize,
@@ -78,6 +273,20 @@ pub struct TextBox<M: 'static, C: 'static + Control<M, C>> {
     has_focus: bool,
     caret_brush: Brush,
     selection_brush: Brush,
+    history: EditHistory,
+    /// Time left to make second click to select a word.
+    click_timer: f32,
+    last_click: Option<Position>,
+    filter: Option<TextFilter>,
+    read_only: bool,
+    /// Hint that is shown when text box is empty and has no focus.
+    placeholder: RefCell<FormattedText>,
+    placeholder_brush: Brush,
+    /// Whether input method composition is in progress.
+    composing: bool,
+    /// Preedit text of input method composition.
+    composition_text: RefCell<FormattedText>,
+    composition_cursor: Option<usize>,
 }
 
 impl<M, C: 'static + Control<M, C>> TextBox<M, C> {
@@ -97,6 +306,20 @@ impl<M, C: 'static + Control<M, C>> TextBox<M, C> {
             has_focus: false,
             caret_brush: Brush::Solid(Color::WHITE),
             selection_brush: Brush::Solid(Color::opaque(65, 65, 90)),
+            history: EditHistory::new(DEFAULT_HISTORY_DEPTH),
+            click_timer: 0.0,
+            last_click: None,
+            filter: None,
+            read_only: false,
+            placeholder: RefCell::new(FormattedTextBuilder::new()
+                .with_font(crate::DEFAULT_FONT.clone())
+                .build()),
+            placeholder_brush: Brush::Solid(Color::opaque(120, 120, 120)),
+            composing: false,
+            composition_text: RefCell::new(FormattedTextBuilder::new()
+                .with_font(crate::DEFAULT_FONT.clone())
+                .build()),
+            composition_cursor: None,
         }
     }
 
@@ -107,6 +330,7 @@ impl<M, C: 'static + Control<M, C>> TextBox<M, C> {
 
     pub fn move_caret_x(&mut self, mut offset: usize, direction: HorizontalDirection) {
         self.selection_range = None;
+        self.history.seal();
 
         self.reset_blink();
 
@@ -120,29 +344,43 @@ impl<M, C: 'static + Control<M, C>> TextBox<M, C> {
         }
 
         while offset > 0 {
-            match direction {
-                HorizontalDirection::Left => {
-                    if self.caret_offset > 0 {
-                        self.caret_offset -= 1
-                    } else if self.caret_line > 0 {
-                        self.caret_line -= 1;
-                        self.caret_offset = lines[self.caret_line].len();
-                    } else {
-                        self.caret_offset = 0;
-                        break;
-                    }
-                }
-                HorizontalDirection::Right => {
-                    let line = lines.get(self.caret_line).unwrap();
-                    if self.caret_offset < line.len() {
-                        self.caret_offset += 1;
-                    } else if self.caret_line < lines.len() - 1 {
+            // Caret moves in visual order - to the nearest caret position on the left or
+            // on the right, which is not always next character in bidirectional text.
+            let line_len = lines[self.caret_line].len();
+            let caret_offset = cmp::min(self.caret_offset, line_len);
+            let caret_x = text.caret_x(self.caret_line, caret_offset);
+            let nearest = (0..=line_len)
+                .map(|offset| (offset, text.caret_x(self.caret_line, offset)))
+                .filter(|(_, x)| match direction {
+                    HorizontalDirection::Left => *x < caret_x,
+                    HorizontalDirection::Right => *x > caret_x,
+                })
+                .min_by(|(_, a), (_, b)| {
+                    (a - caret_x).abs().partial_cmp(&(b - caret_x).abs()).unwrap_or(cmp::Ordering::Equal)
+                });
+
+            if let Some((offset, _)) = nearest {
+                self.caret_offset = offset;
+            } else {
+                // Caret is on the edge of line, left edge of right-to-left line is its end.
+                let forward = match direction {
+                    HorizontalDirection::Left => text.is_line_rtl(self.caret_line),
+                    HorizontalDirection::Right => !text.is_line_rtl(self.caret_line),
+                };
+                if forward {
+                    if self.caret_line < lines.len() - 1 {
                         self.caret_line += 1;
                         self.caret_offset = 0;
                     } else {
-                        self.caret_offset = line.len();
+                        self.caret_offset = line_len;
                         break;
                     }
+                } else if self.caret_line > 0 {
+                    self.caret_line -= 1;
+                    self.caret_offset = lines[self.caret_line].len();
+                } else {
+                    self.caret_offset = 0;
+                    break;
                 }
             }
             offset -= 1;
@@ -150,6 +388,8 @@ impl<M, C: 'static + Control<M, C>> TextBox<M, C> {
     }
 
     pub fn move_caret_y(&mut self, offset: usize, direction: VerticalDirection) {
+        self.history.seal();
+
         let text = self.formatted_text.borrow();
         let lines = text.get_lines();
 
@@ -177,6 +417,172 @@ impl<M, C: 'static + Control<M, C>> TextBox<M, C> {
         }
     }
 
+    /// Returns caret position clamped to the length of its line.
+    fn caret_position(&self) -> Position {
+        let offset = self.formatted_text
+            .borrow()
+            .get_lines()
+            .get(self.caret_line)
+            .map_or(0, |line| cmp::min(self.caret_offset, line.len()));
+        Position { line: self.caret_line, offset }
+    }
+
+    fn set_caret_char_index(&mut self, index: usize) {
+        let position = self.char_index_to_position(index);
+        self.caret_line = position.line;
+        self.caret_offset = position.offset;
+    }
+
+    /// Moves caret using given function. If `select` is set, selection is extended from
+    /// its anchor (or from old caret position) to new caret position, otherwise selection
+    /// is removed.
+    fn move_caret_selecting<F>(&mut self, select: bool, move_caret: F)
+        where F: FnOnce(&mut Self) {
+        let anchor = self.selection_range
+            .map(|range| range.begin)
+            .unwrap_or_else(|| self.caret_position());
+        move_caret(self);
+        let caret = self.caret_position();
+        self.selection_range = if select && anchor != caret {
+            Some(SelectionRange { begin: anchor, end: caret })
+        } else {
+            None
+        };
+        self.history.seal();
+        self.reset_blink();
+    }
+
+    /// Returns index of the beginning of next or previous word starting from given index.
+    /// Masked text is a single word, so word navigation does not reveal its structure.
+    fn word_boundary(&self, position: usize, direction: HorizontalDirection) -> usize {
+        let text = self.formatted_text.borrow();
+        let text = text.get_raw_text();
+        let mut index = position.min(text.len());
+        if text.is_empty() || self.is_password() {
+            return match direction {
+                HorizontalDirection::Left => 0,
+                HorizontalDirection::Right => text.len(),
+            };
+        }
+        match direction {
+            HorizontalDirection::Left => {
+                while index > 0 && !is_word_char(text[index - 1]) {
+                    index -= 1;
+                }
+                while index > 0 && is_word_char(text[index - 1]) {
+                    index -= 1;
+                }
+            }
+            HorizontalDirection::Right => {
+                while index < text.len() && is_word_char(text[index]) {
+                    index += 1;
+                }
+                while index < text.len() && !is_word_char(text[index]) {
+                    index += 1;
+                }
+            }
+        }
+        index
+    }
+
+    /// Moves caret to the beginning of next or previous word.
+    pub fn move_caret_word(&mut self, direction: HorizontalDirection) {
+        let position = self.get_absolute_position().unwrap_or(0);
+        let index = self.word_boundary(position, direction);
+        self.set_caret_char_index(index);
+        self.history.seal();
+        self.reset_blink();
+    }
+
+    /// Moves caret to the beginning or to the end of current line.
+    pub fn move_caret_to_line_edge(&mut self, direction: HorizontalDirection) {
+        self.caret_offset = match direction {
+            HorizontalDirection::Left => 0,
+            HorizontalDirection::Right => self.formatted_text
+                .borrow()
+                .get_lines()
+                .get(self.caret_line)
+                .map_or(0, |line| line.len()),
+        };
+        self.history.seal();
+        self.reset_blink();
+    }
+
+    /// Moves caret to the beginning or to the end of whole text.
+    pub fn move_caret_to_text_edge(&mut self, direction: HorizontalDirection) {
+        let index = match direction {
+            HorizontalDirection::Left => 0,
+            HorizontalDirection::Right => self.get_text_len(),
+        };
+        self.set_caret_char_index(index);
+        self.history.seal();
+        self.reset_blink();
+    }
+
+    /// Moves caret up or down by amount of lines that fits into text box.
+    pub fn move_caret_page(&mut self, direction: VerticalDirection) {
+        let line_height = {
+            let text = self.formatted_text.borrow();
+            match text.get_lines().first() {
+                Some(line) => line.height,
+                None => text.get_font().map_or(0.0, |font| font.lock().unwrap().get_ascender()),
+            }
+        };
+        let lines_per_page = if line_height > 0.0 {
+            (self.widget.actual_size().y / line_height) as usize
+        } else {
+            0
+        };
+        self.move_caret_y(cmp::max(lines_per_page, 1), direction);
+        self.reset_blink();
+    }
+
+    /// Selects whole text and moves caret to the end of it.
+    pub fn select_all(&mut self) {
+        let end = self.char_index_to_position(self.get_text_len());
+        self.caret_line = end.line;
+        self.caret_offset = end.offset;
+        self.selection_range = Some(SelectionRange {
+            begin: Position { line: 0, offset: 0 },
+            end,
+        });
+        self.history.seal();
+    }
+
+    /// Selects word (or sequence of non-word characters) at given position. Masked text
+    /// is selected as a whole.
+    pub fn select_word_at(&mut self, position: Position) {
+        let index = self.position_to_char_index(position);
+        let is_password = self.is_password();
+        let (begin, end) = {
+            let text = self.formatted_text.borrow();
+            let text = text.get_raw_text();
+            if index >= text.len() {
+                return;
+            }
+            if is_password {
+                (0, text.len())
+            } else {
+                let word = is_word_char(text[index]);
+                let mut begin = index;
+                while begin > 0 && is_word_char(text[begin - 1]) == word {
+                    begin -= 1;
+                }
+                let mut end = index;
+                while end < text.len() && is_word_char(text[end]) == word {
+                    end += 1;
+                }
+                (begin, end)
+            }
+        };
+        let begin = self.char_index_to_position(begin);
+        let end = self.char_index_to_position(end);
+        self.caret_line = end.line;
+        self.caret_offset = end.offset;
+        self.selection_range = Some(SelectionRange { begin, end });
+        self.history.seal();
+    }
+
     pub fn get_absolute_position(&self) -> Option<usize> {
         if let Some(line) = self.formatted_text.borrow().get_lines().get(self.caret_line) {
             Some(line.begin + cmp::min(self.caret_offset, line.len()))
@@ -188,12 +594,271 @@ impl<M, C: 'static + Control<M, C>> TextBox<M, C> {
     /// Inserts given character at current caret position.
     pub fn insert_char(&mut self, c: char) {
         if !c.is_control() {
+            if let Some(range) = self.selection_char_range() {
+                self.replace_range(range, &c.to_string(), EditKind::Other);
+            } else {
+                let position = self.get_absolute_position().unwrap_or(0);
+                self.replace_range(position..position, &c.to_string(), EditKind::Typing);
+            }
+        }
+    }
+
+    /// Inserts given string at current caret position and moves caret to the end of
+    /// inserted text. Control characters except new line are ignored.
+    pub fn insert_str(&mut self, str: &str) {
+        let str = filter_control_chars(str);
+        if !str.is_empty() {
             let position = self.get_absolute_position().unwrap_or(0);
-            self.formatted_text
-                .borrow_mut()
-                .insert_char(c, position)
-                .build();
-            self.move_caret_x(1, HorizontalDirection::Right);
+            self.replace_range(position..position, &str, EditKind::Other);
+        }
+    }
+
+    /// Replaces given range of characters with new text, moves caret to the end of
+    /// inserted text and records the change in edit history.
+    fn replace_range(&mut self, range: Range<usize>, text: &str, kind: EditKind) {
+        if self.read_only {
+            return;
+        }
+
+        let text = self.apply_filter(range.clone(), text);
+        if text.is_empty() && range.start == range.end {
+            return;
+        }
+
+        let removed = self.formatted_text
+            .borrow()
+            .get_raw_text()[range.clone()]
+            .iter()
+            .filter_map(|c| std::char::from_u32(*c))
+            .collect::<String>();
+        let caret_before = Position { line: self.caret_line, offset: self.caret_offset };
+        let selection_before = self.selection_range;
+
+        self.apply_replace(range.start, removed.chars().count(), &text);
+
+        self.history.push(Edit {
+            kind,
+            position: range.start,
+            removed,
+            inserted: text,
+            caret_before,
+            selection_before,
+            caret_after: Position { line: self.caret_line, offset: self.caret_offset },
+        });
+    }
+
+    /// Removes `count` characters starting from `position`, inserts given text in their
+    /// place and moves caret to the end of inserted text. Does not touch edit history.
+    fn apply_replace(&mut self, position: usize, count: usize, text: &str) {
+        self.formatted_text
+            .borrow_mut()
+            .remove_range(position..(position + count))
+            .insert_str(text, position)
+            .build();
+        let caret = self.char_index_to_position(position + text.chars().count());
+        self.caret_line = caret.line;
+        self.caret_offset = caret.offset;
+        self.selection_range = None;
+        self.reset_blink();
+        self.widget.invalidate_layout();
+        self.widget.post_message(UiMessage::new(UiMessageData::TextBox(TextBoxMessage::TextChanged(self.text()))));
+    }
+
+    /// Passes every character of given text through filter (if any) and returns accepted
+    /// characters. `range` is a range of characters which will be replaced with the text.
+    fn apply_filter(&self, range: Range<usize>, text: &str) -> String {
+        let filter = if let Some(filter) = self.filter.as_ref() {
+            filter
+        } else {
+            return text.to_owned();
+        };
+
+        let mut current = {
+            let formatted_text = self.formatted_text.borrow();
+            let raw_text = formatted_text.get_raw_text();
+            raw_text[..range.start]
+                .iter()
+                .chain(raw_text[range.end..].iter())
+                .filter_map(|c| std::char::from_u32(*c))
+                .collect::<Vec<char>>()
+        };
+        let mut position = range.start;
+        let mut accepted = String::new();
+        for c in text.chars() {
+            if filter(c, &current.iter().collect::<String>()) {
+                current.insert(position, c);
+                position += 1;
+                accepted.push(c);
+            }
+        }
+        accepted
+    }
+
+    /// Sets filter that decides which characters can be inserted by user. Filter is not
+    /// applied to text set by `set_text`.
+    pub fn set_filter(&mut self, filter: Option<TextFilter>) -> &mut Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn filter(&self) -> Option<TextFilter> {
+        self.filter.clone()
+    }
+
+    /// Reverts last change of text. Returns false if there is nothing to undo.
+    pub fn undo(&mut self) -> bool {
+        if self.read_only {
+            return false;
+        }
+        if let Some(edit) = self.history.undo() {
+            self.apply_replace(edit.position, edit.inserted.chars().count(), &edit.removed);
+            self.caret_line = edit.caret_before.line;
+            self.caret_offset = edit.caret_before.offset;
+            self.selection_range = edit.selection_before;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Applies last reverted change of text again. Returns false if there is nothing to redo.
+    pub fn redo(&mut self) -> bool {
+        if self.read_only {
+            return false;
+        }
+        if let Some(edit) = self.history.redo() {
+            self.apply_replace(edit.position, edit.removed.chars().count(), &edit.inserted);
+            self.caret_line = edit.caret_after.line;
+            self.caret_offset = edit.caret_after.offset;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// Sets maximum amount of steps that can be undone. Zero disables history.
+    pub fn set_history_depth(&mut self, depth: usize) -> &mut Self {
+        self.history.set_max_depth(depth);
+        self
+    }
+
+    pub fn history_depth(&self) -> usize {
+        self.history.max_depth
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    fn position_to_char_index(&self, position: Position) -> usize {
+        if let Some(line) = self.formatted_text.borrow().get_lines().get(position.line) {
+            line.begin + cmp::min(position.offset, line.len())
+        } else {
+            0
+        }
+    }
+
+    fn char_index_to_position(&self, index: usize) -> Position {
+        let text = self.formatted_text.borrow();
+        for (line_index, line) in text.get_lines().iter().enumerate().rev() {
+            if line.begin <= index {
+                return Position {
+                    line: line_index,
+                    offset: cmp::min(index - line.begin, line.len()),
+                };
+            }
+        }
+        Position { line: 0, offset: 0 }
+    }
+
+    fn selection_char_range(&self) -> Option<Range<usize>> {
+        let range = self.selection_range?.normalized();
+        let begin = self.position_to_char_index(range.begin);
+        let end = self.position_to_char_index(range.end);
+        if begin < end {
+            Some(begin..end)
+        } else {
+            None
+        }
+    }
+
+    /// Returns selected text, if there is any.
+    pub fn selected_text(&self) -> Option<String> {
+        let range = self.selection_char_range()?;
+        Some(self.formatted_text
+            .borrow()
+            .get_raw_text()[range]
+            .iter()
+            .filter_map(|c| std::char::from_u32(*c))
+            .collect())
+    }
+
+    /// Removes selected text and moves caret to the beginning of selection. Returns true
+    /// if anything was removed.
+    pub fn remove_selection(&mut self) -> bool {
+        if let Some(range) = self.selection_char_range() {
+            self.replace_range(range, "", EditKind::Other);
+            true
+        } else {
+            self.selection_range = None;
+            false
+        }
+    }
+
+    /// Puts selected text into clipboard. Returns true if there was anything to copy.
+    pub fn copy(&self, clipboard: &mut dyn Clipboard) -> bool {
+        if self.is_password() {
+            // Never expose real text of a password.
+            return false;
+        }
+        if let Some(text) = self.selected_text() {
+            clipboard.set_text(text);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Puts selected text into clipboard and removes it from text box. Read-only text box
+    /// only copies selected text.
+    pub fn cut(&mut self, clipboard: &mut dyn Clipboard) -> bool {
+        if self.read_only {
+            return self.copy(clipboard);
+        }
+        self.copy(clipboard) && self.remove_selection()
+    }
+
+    /// Replaces selected text (if any) with content of clipboard.
+    pub fn paste(&mut self, clipboard: &mut dyn Clipboard) -> bool {
+        if self.read_only {
+            return false;
+        }
+        if let Some(text) = clipboard.get_text() {
+            self.replace_selection(&text);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Replaces selected text with given text, or inserts it at caret position if there
+    /// is no selection.
+    fn replace_selection(&mut self, text: &str) {
+        let text = filter_control_chars(text);
+        let range = self.selection_char_range().unwrap_or_else(|| {
+            let position = self.get_absolute_position().unwrap_or(0);
+            position..position
+        });
+        if !text.is_empty() || !range.is_empty() {
+            self.replace_range(range, &text, EditKind::Other);
         }
     }
 
@@ -205,57 +870,107 @@ impl<M, C: 'static + Control<M, C>> TextBox<M, C> {
         if let Some(position) = self.get_absolute_position() {
             let text_len = self.get_text_len();
             if text_len != 0 {
-                let position = match direction {
+                match direction {
                     HorizontalDirection::Left => {
                         if position == 0 {
                             return;
                         }
-                        position - 1
+                        self.replace_range((position - 1)..position, "", EditKind::Backspace);
                     }
                     HorizontalDirection::Right => {
                         if position >= text_len {
                             return;
                         }
-                        position
+                        self.replace_range(position..(position + 1), "", EditKind::Delete);
                     }
-                };
-                self.formatted_text.borrow_mut().remove_at(position);
-                self.formatted_text.borrow_mut().build();
-
-                if direction == HorizontalDirection::Left {
-                    self.move_caret_x(1, direction);
                 }
             }
         }
     }
 
-    pub fn screen_pos_to_text_pos(&self, screen_pos: Vec2) -> Option<Position> {
-        let mut caret_pos = self.widget.screen_position;
-        if let Some(font) = self.formatted_text.borrow().get_font() {
+    /// Returns screen position of top-left corner of caret and height of caret.
+    fn caret_screen_position(&self) -> Option<(Vec2, f32)> {
+        let bounds = self.widget.screen_bounds();
+        let text = self.formatted_text.borrow();
+        let font = text.get_font()?;
+        let (ascender, height) = {
             let font = font.lock().unwrap();
-            for (line_index, line) in self.formatted_text.borrow().get_lines().iter().enumerate() {
-                let line_bounds =
-                    Rect::new(caret_pos.x + line.x_offset, caret_pos.y, line.width, font.get_ascender());
-                if line_bounds.contains(screen_pos.x, screen_pos.y) {
-                    let mut x = line_bounds.x;
-                    // Check each character in line.
-                    for (offset, index) in (line.begin..line.end).enumerate() {
-                        let symbol = self.formatted_text.borrow().get_raw_text()[index];
-                        let (width, height, advance) = if let Some(glyph) = font.get_glyph(symbol) {
-                            (glyph.get_bitmap_width(), glyph.get_bitmap_height(), glyph.get_advance())
-                        } else {
-                            // Stub
-                            let h = font.get_height();
-                            (h, h, h)
-                        };
-                        let char_bounds = Rect::new(x, line_bounds.y, width, height);
-                        if char_bounds.contains(screen_pos.x, screen_pos.y) {
-                            return Some(Position { line: line_index, offset });
-                        }
-                        x += advance;
+            (font.get_ascender(), font.get_height())
+        };
+        let mut caret_pos = Vec2::new(
+            bounds.x,
+            bounds.y + self.caret_line as f32 * ascender,
+        );
+        if let Some(line) = text.get_lines().get(self.caret_line) {
+            caret_pos.x += text.caret_x(self.caret_line, cmp::min(self.caret_offset, line.len()));
+            caret_pos.y = bounds.y + line.y_offset;
+        }
+        Some((caret_pos, height))
+    }
+
+    /// Returns offset of caret inside of composition text.
+    fn composition_caret_offset(&self) -> f32 {
+        if self.composing {
+            let text = self.composition_text.borrow();
+            let len = text.get_raw_text().len();
+            text.get_range_width(0..self.composition_cursor.map_or(len, |cursor| cursor.min(len)))
+        } else {
+            0.0
+        }
+    }
+
+    fn handle_composition(&mut self, composition: &CompositionEvent) {
+        if self.read_only {
+            return;
+        }
+        match composition {
+            CompositionEvent::Start => {
+                self.composing = true;
+                self.composition_text.borrow_mut().set_text("");
+                self.composition_cursor = None;
+            }
+            CompositionEvent::Update { text, cursor } => {
+                self.composing = true;
+                self.composition_text.borrow_mut().set_text(text);
+                self.composition_cursor = *cursor;
+            }
+            CompositionEvent::Commit(text) => {
+                self.composing = false;
+                self.composition_text.borrow_mut().set_text("");
+                self.composition_cursor = None;
+                self.replace_selection(text);
+            }
+            CompositionEvent::Cancel => {
+                self.composing = false;
+                self.composition_text.borrow_mut().set_text("");
+                self.composition_cursor = None;
+            }
+        }
+        self.reset_blink();
+    }
+
+    pub fn is_composing(&self) -> bool {
+        self.composing
+    }
+
+    pub fn screen_pos_to_text_pos(&self, screen_pos: Vec2) -> Option<Position> {
+        let caret_pos = self.widget.screen_position;
+        let text = self.formatted_text.borrow();
+        let text_len = text.get_raw_text().len();
+        // Characters are placed in visual order, so their positions are taken from
+        // formatted text.
+        for (line_index, line) in text.get_lines().iter().enumerate() {
+            let line_bounds =
+                Rect::new(caret_pos.x + line.x_offset, caret_pos.y + line.y_offset, line.width, line.height);
+            if line_bounds.contains(screen_pos.x, screen_pos.y) {
+                // Check each character in line.
+                for (offset, index) in (line.begin..line.end.min(text_len)).enumerate() {
+                    let x = caret_pos.x + text.get_char_x(index);
+                    let char_bounds = Rect::new(x, line_bounds.y, text.get_char_advance(index), line_bounds.h);
+                    if char_bounds.contains(screen_pos.x, screen_pos.y) {
+                        return Some(Position { line: line_index, offset });
                     }
                 }
-                caret_pos.y += line_bounds.h;
             }
         }
         None
@@ -264,36 +979,149 @@ impl<M, C: 'static + Control<M, C>> TextBox<M, C> {
     pub fn set_text<P: AsRef<str>>(&mut self, text: P) -> &mut Self {
         self.formatted_text
             .borrow_mut()
-            .set_text(text);
+            .set_text(text);
+        self.history.clear();
+        self.selection_range = None;
+        self.widget.invalidate_layout();
+        self.widget.post_message(UiMessage::new(UiMessageData::TextBox(TextBoxMessage::TextChanged(self.text()))));
+        self
+    }
+
+    pub fn text(&self) -> String {
+        self.formatted_text
+            .borrow()
+            .text()
+    }
+
+    /// Enables or disables wrapping at characters, see `set_wrap_mode`.
+    pub fn set_wrap(&mut self, wrap: bool) -> &mut Self {
+        self.set_wrap_mode(if wrap { WrapMode::Character } else { WrapMode::NoWrap })
+    }
+
+    pub fn is_wrap(&self) -> bool {
+        self.formatted_text
+            .borrow()
+            .is_wrap()
+    }
+
+    pub fn set_wrap_mode(&mut self, wrap: WrapMode) -> &mut Self {
+        self.formatted_text
+            .borrow_mut()
+            .set_wrap_mode(wrap);
+        self.widget.invalidate_layout();
+        self
+    }
+
+    pub fn wrap_mode(&self) -> WrapMode {
+        self.formatted_text
+            .borrow()
+            .wrap_mode()
+    }
+
+    pub fn set_line_spacing(&mut self, line_spacing: f32) -> &mut Self {
+        self.formatted_text
+            .borrow_mut()
+            .set_line_spacing(line_spacing);
+        self.widget.invalidate_layout();
+        self
+    }
+
+    pub fn line_spacing(&self) -> f32 {
+        self.formatted_text
+            .borrow()
+            .line_spacing()
+    }
+
+    pub fn set_font(&mut self, font: Arc<Mutex<Font>>) -> &mut Self {
+        self.composition_text
+            .borrow_mut()
+            .set_font(font.clone());
+        self.placeholder
+            .borrow_mut()
+            .set_font(font.clone());
+        self.formatted_text
+            .borrow_mut()
+            .set_font(font);
+        self.widget.invalidate_layout();
+        self
+    }
+
+    /// Sets fonts which will be used for characters that are missing in primary font.
+    pub fn set_fallback_fonts(&mut self, fonts: Vec<Arc<Mutex<Font>>>) -> &mut Self {
+        self.composition_text
+            .borrow_mut()
+            .set_fallback_fonts(fonts.clone());
+        self.placeholder
+            .borrow_mut()
+            .set_fallback_fonts(fonts.clone());
+        self.formatted_text
+            .borrow_mut()
+            .set_fallback_fonts(fonts);
+        self.widget.invalidate_layout();
+        self
+    }
+
+    pub fn fallback_fonts(&self) -> Vec<Arc<Mutex<Font>>> {
+        self.formatted_text
+            .borrow()
+            .fallback_fonts()
+            .to_vec()
+    }
+
+    /// Sets character which will be drawn instead of every character of text, pass
+    /// `Some('*')` to turn text box into password box. Masked text cannot be copied.
+    pub fn set_mask_char(&mut self, mask_char: Option<char>) -> &mut Self {
+        self.formatted_text
+            .borrow_mut()
+            .set_mask_char(mask_char);
+        self.widget.invalidate_layout();
         self
     }
 
-    pub fn text(&self) -> String {
+    pub fn mask_char(&self) -> Option<char> {
         self.formatted_text
             .borrow()
-            .text()
+            .mask_char()
     }
 
-    pub fn set_wrap(&mut self, wrap: bool) -> &mut Self {
-        self.formatted_text
+    pub fn is_password(&self) -> bool {
+        self.mask_char().is_some()
+    }
+
+    /// Read-only text box does not allow to change its text by user, but its text still
+    /// can be selected and copied.
+    pub fn set_read_only(&mut self, read_only: bool) -> &mut Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Sets text which will be shown in a dimmed brush when text box is empty and not focused.
+    pub fn set_placeholder<P: AsRef<str>>(&mut self, placeholder: P) -> &mut Self {
+        self.placeholder
             .borrow_mut()
-            .set_wrap(wrap);
+            .set_text(placeholder);
         self
     }
 
-    pub fn is_wrap(&self) -> bool {
-        self.formatted_text
+    pub fn placeholder(&self) -> String {
+        self.placeholder
             .borrow()
-            .is_wrap()
+            .text()
     }
 
-    pub fn set_font(&mut self, font: Arc<Mutex<Font>>) -> &mut Self {
-        self.formatted_text
-            .borrow_mut()
-            .set_font(font);
+    pub fn set_placeholder_brush(&mut self, brush: Brush) -> &mut Self {
+        self.placeholder_brush = brush;
         self
     }
 
+    pub fn placeholder_brush(&self) -> Brush {
+        self.placeholder_brush.clone()
+    }
+
     pub fn font(&self) -> Arc<Mutex<Font>> {
         self.formatted_text
             .borrow()
@@ -302,6 +1130,9 @@ impl<M, C: 'static + Control<M, C>> TextBox<M, C> {
     }
 
     pub fn set_vertical_alignment(&mut self, valign: VerticalAlignment) -> &mut Self {
+        self.placeholder
+            .borrow_mut()
+            .set_vertical_alignment(valign);
         self.formatted_text
             .borrow_mut()
             .set_vertical_alignment(valign);
@@ -315,6 +1146,9 @@ impl<M, C: 'static + Control<M, C>> TextBox<M, C> {
     }
 
     pub fn set_horizontal_alignment(&mut self, halign: HorizontalAlignment) -> &mut Self {
+        self.placeholder
+            .borrow_mut()
+            .set_horizontal_alignment(halign);
         self.formatted_text
             .borrow_mut()
             .set_horizontal_alignment(halign);
@@ -346,12 +1180,28 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for TextBox<M, C> {
             blink_timer: self.blink_timer,
             blink_interval: self.blink_interval,
             formatted_text: RefCell::new(FormattedTextBuilder::new()
-                .with_font(self.formatted_text.borrow().get_font().unwrap()).build()),
+                .with_font(self.formatted_text.borrow().get_font().unwrap())
+                .with_fallback_fonts(self.fallback_fonts())
+                .with_mask_char(self.formatted_text.borrow().mask_char())
+                .build()),
             selection_range: self.selection_range,
             selecting: self.selecting,
             selection_brush: self.selection_brush.clone(),
             caret_brush: self.caret_brush.clone(),
             has_focus: false,
+            history: EditHistory::new(self.history.max_depth),
+            click_timer: 0.0,
+            last_click: None,
+            filter: self.filter.clone(),
+            read_only: self.read_only,
+            placeholder: RefCell::new(self.placeholder.borrow().clone()),
+            placeholder_brush: self.placeholder_brush.clone(),
+            composing: false,
+            composition_text: RefCell::new(FormattedTextBuilder::new()
+                .with_font(self.formatted_text.borrow().get_font().unwrap())
+                .with_fallback_fonts(self.fallback_fonts())
+                .build()),
+            composition_cursor: None,
         })
     }
 
@@ -373,48 +1223,32 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for TextBox<M, C> {
             .set_brush(self.widget.foreground())
             .build();
 
-        if let Some(ref selection_range) = self.selection_range {
+        if let Some(selection_range) = self.selection_range {
+            let selection_range = selection_range.normalized();
             let text = self.formatted_text.borrow();
-            let lines = text.get_lines();
-            if selection_range.begin.line == selection_range.end.line {
-                let line = lines[selection_range.begin.line];
-                let begin = selection_range.begin.offset;
-                let end = selection_range.end.offset;
-                // Begin line
-                let offset = text.get_range_width(line.begin..(line.begin + begin));
-                let width = text.get_range_width((line.begin + begin)..(line.begin + end));
-                let bounds = Rect::new(bounds.x + line.x_offset + offset,
-                                       bounds.y + line.y_offset,
-                                       width,
-                                       line.height);
-                drawing_context.push_rect_filled(&bounds, None);
-            } else {
-                for (i, line) in text.get_lines().iter().enumerate() {
-                    if i >= selection_range.begin.line && i <= selection_range.end.line {
-                        let bounds = if i == selection_range.begin.line {
-                            // Begin line
-                            let offset = text.get_range_width(line.begin..(line.begin + selection_range.begin.offset));
-                            let width = text.get_range_width((line.begin + selection_range.begin.offset)..line.end);
-                            Rect::new(bounds.x + line.x_offset + offset,
-                                      bounds.y + line.y_offset,
-                                      width,
-                                      line.height)
-                        } else if i == selection_range.end.line {
-                            // End line
-                            let width = text.get_range_width(line.begin..(line.begin + selection_range.end.offset));
-                            Rect::new(bounds.x + line.x_offset,
-                                      bounds.y + line.y_offset,
-                                      width,
-                                      line.height)
-                        } else {
-                            // Everything between
-                            Rect::new(bounds.x + line.x_offset,
-                                      bounds.y + line.y_offset,
-                                      line.width,
-                                      line.height)
-                        };
-                        drawing_context.push_rect_filled(&bounds, None);
-                    }
+            let text_len = text.get_raw_text().len();
+            // Each selected character is highlighted separately, because selection of
+            // bidirectional text can be discontinuous on screen.
+            for (i, line) in text.get_lines().iter().enumerate() {
+                if i < selection_range.begin.line || i > selection_range.end.line {
+                    continue;
+                }
+                let begin = if i == selection_range.begin.line {
+                    line.begin + selection_range.begin.offset
+                } else {
+                    line.begin
+                };
+                let end = if i == selection_range.end.line {
+                    line.begin + selection_range.end.offset
+                } else {
+                    line.end
+                };
+                for index in begin..end.min(text_len) {
+                    let bounds = Rect::new(bounds.x + text.get_char_x(index),
+                                           bounds.y + line.y_offset,
+                                           text.get_char_advance(index),
+                                           line.height);
+                    drawing_context.push_rect_filled(&bounds, None);
                 }
             }
         }
@@ -423,36 +1257,55 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for TextBox<M, C> {
         let screen_position = Vec2::new(bounds.x, bounds.y);
         drawing_context.draw_text(screen_position, &self.formatted_text.borrow());
 
-        if self.caret_visible {
-            let text = self.formatted_text.borrow();
-            if let Some(font) = text.get_font() {
-                let font = font.lock().unwrap();
-                if let Some(line) = text.get_lines().get(self.caret_line) {
-                    let text = text.get_raw_text();
-                    let mut caret_pos = Vec2::new(
-                        screen_position.x,
-                        screen_position.y + self.caret_line as f32 * font.get_ascender(),
-                    );
-                    for (offset, char_index) in (line.begin..line.end).enumerate() {
-                        if offset >= self.caret_offset {
-                            break;
-                        }
-                        if let Some(glyph) = font.get_glyph(text[char_index]) {
-                            caret_pos.x += glyph.get_advance();
-                        } else {
-                            caret_pos.x += font.get_height();
-                        }
-                    }
+        if !self.has_focus && self.get_text_len() == 0 {
+            let mut placeholder = self.placeholder.borrow_mut();
+            if !placeholder.get_raw_text().is_empty() {
+                placeholder
+                    .set_constraint(Vec2::new(bounds.w, bounds.h))
+                    .set_brush(self.placeholder_brush.clone())
+                    .build();
+                drawing_context.draw_text(screen_position, &placeholder);
+            }
+        }
+
+        if self.composing {
+            let mut composition_text = self.composition_text.borrow_mut();
+            if !composition_text.get_raw_text().is_empty() {
+                if let Some((position, height)) = self.caret_screen_position() {
+                    let size = composition_text
+                        .set_constraint(Vec2::new(std::f32::INFINITY, bounds.h))
+                        .set_brush(self.widget.foreground())
+                        .build();
+
+                    // Hide text under composition text.
+                    drawing_context.push_rect_filled(&Rect::new(position.x, position.y, size.x, height), None);
+                    drawing_context.commit(CommandKind::Geometry, self.widget.background(), CommandTexture::None);
 
-                    let caret_bounds = Rect::new(caret_pos.x, caret_pos.y, 2.0, font.get_height());
-                    drawing_context.push_rect_filled(&caret_bounds, None);
-                    drawing_context.commit(CommandKind::Geometry, self.caret_brush.clone(), CommandTexture::None);
+                    drawing_context.draw_text(position, &composition_text);
+
+                    // Underline
+                    drawing_context.push_rect_filled(&Rect::new(position.x, position.y + height - 1.0, size.x, 1.0), None);
+                    drawing_context.commit(CommandKind::Geometry, self.widget.foreground(), CommandTexture::None);
                 }
             }
         }
+
+        if self.caret_visible {
+            if let Some(caret_bounds) = self.caret_rect() {
+                drawing_context.push_rect_filled(&caret_bounds, None);
+                drawing_context.commit(CommandKind::Geometry, self.caret_brush.clone(), CommandTexture::None);
+            }
+        }
+    }
+
+    fn caret_rect(&self) -> Option<Rect<f32>> {
+        let (position, height) = self.caret_screen_position()?;
+        Some(Rect::new(position.x + self.composition_caret_offset(), position.y, 2.0, height))
     }
 
     fn update(&mut self, dt: f32) {
+        self.click_timer = (self.click_timer - dt).max(0.0);
+
         if self.has_focus {
             self.blink_timer += dt;
             if self.blink_timer >= self.blink_interval {
@@ -467,48 +1320,148 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for TextBox<M, C> {
     fn handle_message(&mut self, self_handle: Handle<UINode<M, C>>, ui: &mut UserInterface<M, C>, message: &mut UiMessage<M, C>) {
         self.widget.handle_message(self_handle, ui, message);
 
+        // Pressed key that was used by text box is marked as handled, so it won't trigger
+        // a shortcut.
+        let mut key_handled = false;
+
         if let UiMessageData::Widget(msg) = &message.data {
-            if message.source == self_handle || self.widget().has_descendant(message.source, ui) {
-                match msg {
-                    WidgetMessage::Text(symbol) => {
-                        self.insert_char(*symbol);
-                    }
-                    WidgetMessage::KeyDown(code) => {
-                        match code {
-                            KeyCode::Up => {
-                                self.move_caret_y(1, VerticalDirection::Up);
-                            }
-                            KeyCode::Down => {
-                                self.move_caret_y(1, VerticalDirection::Down);
-                            }
-                            KeyCode::Right => {
-                                self.move_caret_x(1, HorizontalDirection::Right);
+            match msg {
+                WidgetMessage::Text(symbol) => {
+                    self.insert_char(*symbol);
+                }
+                WidgetMessage::Composition(composition) => {
+                    self.handle_composition(composition);
+                }
+                // Keys are consumed by input method while composition is in progress.
+                WidgetMessage::KeyDown { .. } if self.composing => {
+                    key_handled = true;
+                }
+                WidgetMessage::KeyDown { code, modifiers } => {
+                    key_handled = true;
+                    match code {
+                        KeyCode::Z if modifiers.control && modifiers.shift => {
+                            self.redo();
+                        }
+                        KeyCode::Z if modifiers.control => {
+                            self.undo();
+                        }
+                        KeyCode::Y if modifiers.control => {
+                            self.redo();
+                        }
+                        KeyCode::C if modifiers.control => {
+                            self.copy(ui.clipboard_mut());
+                        }
+                        KeyCode::X if modifiers.control => {
+                            self.cut(ui.clipboard_mut());
+                        }
+                        KeyCode::V if modifiers.control => {
+                            self.paste(ui.clipboard_mut());
+                        }
+                        KeyCode::Insert if modifiers.control => {
+                            self.copy(ui.clipboard_mut());
+                        }
+                        KeyCode::Insert if modifiers.shift => {
+                            self.paste(ui.clipboard_mut());
+                        }
+                        KeyCode::Delete if modifiers.shift => {
+                            self.cut(ui.clipboard_mut());
+                        }
+                        KeyCode::Copy => {
+                            self.copy(ui.clipboard_mut());
+                        }
+                        KeyCode::Cut => {
+                            self.cut(ui.clipboard_mut());
+                        }
+                        KeyCode::Paste => {
+                            self.paste(ui.clipboard_mut());
+                        }
+                        KeyCode::A if modifiers.control => {
+                            self.select_all();
+                        }
+                        KeyCode::Up => {
+                            self.move_caret_selecting(modifiers.shift, |tb| tb.move_caret_y(1, VerticalDirection::Up));
+                        }
+                        KeyCode::Down => {
+                            self.move_caret_selecting(modifiers.shift, |tb| tb.move_caret_y(1, VerticalDirection::Down));
+                        }
+                        KeyCode::PageUp => {
+                            self.move_caret_selecting(modifiers.shift, |tb| tb.move_caret_page(VerticalDirection::Up));
+                        }
+                        KeyCode::PageDown => {
+                            self.move_caret_selecting(modifiers.shift, |tb| tb.move_caret_page(VerticalDirection::Down));
+                        }
+                        KeyCode::Right | KeyCode::Left => {
+                            let direction = if *code == KeyCode::Left {
+                                HorizontalDirection::Left
+                            } else {
+                                HorizontalDirection::Right
+                            };
+                            if modifiers.control {
+                                self.move_caret_selecting(modifiers.shift, |tb| tb.move_caret_word(direction));
+                            } else if !modifiers.shift && self.selection_range.is_some() {
+                                // Collapse selection to its edge instead of moving caret.
+                                let range = self.selection_range.unwrap().normalized();
+                                let edge = if direction == HorizontalDirection::Left { range.begin } else { range.end };
+                                self.move_caret_selecting(false, |tb| {
+                                    tb.caret_line = edge.line;
+                                    tb.caret_offset = edge.offset;
+                                });
+                            } else {
+                                self.move_caret_selecting(modifiers.shift, |tb| tb.move_caret_x(1, direction));
                             }
-                            KeyCode::Left => {
-                                self.move_caret_x(1, HorizontalDirection::Left);
+                        }
+                        KeyCode::Home | KeyCode::End => {
+                            let direction = if *code == KeyCode::Home {
+                                HorizontalDirection::Left
+                            } else {
+                                HorizontalDirection::Right
+                            };
+                            if modifiers.control {
+                                self.move_caret_selecting(modifiers.shift, |tb| tb.move_caret_to_text_edge(direction));
+                            } else {
+                                self.move_caret_selecting(modifiers.shift, |tb| tb.move_caret_to_line_edge(direction));
                             }
-                            KeyCode::Delete => {
+                        }
+                        KeyCode::Return | KeyCode::NumpadEnter => {
+                            self.widget.post_message(UiMessage::new(UiMessageData::TextBox(TextBoxMessage::Submitted(self.text()))));
+                        }
+                        KeyCode::Delete => {
+                            if !self.remove_selection() {
                                 self.remove_char(HorizontalDirection::Right);
                             }
-                            KeyCode::Backspace => {
+                        }
+                        KeyCode::Backspace => {
+                            if !self.remove_selection() {
                                 self.remove_char(HorizontalDirection::Left);
                             }
-                            _ => ()
                         }
+                        _ => key_handled = false
                     }
-                    WidgetMessage::GotFocus => {
-                        self.reset_blink();
-                        self.has_focus = true;
-                    }
-                    WidgetMessage::LostFocus => {
-                        self.has_focus = false;
-                    }
-                    WidgetMessage::MouseDown { pos, button } => {
-                        if *button == MouseButton::Left {
-                            self.selection_range = None;
-                            self.selecting = true;
+                }
+                WidgetMessage::GotFocus => {
+                    self.reset_blink();
+                    self.has_focus = true;
+                }
+                WidgetMessage::LostFocus => {
+                    self.has_focus = false;
+                }
+                WidgetMessage::MouseDown { pos, button, .. } => {
+                    if *button == MouseButton::Left {
+                        self.selection_range = None;
+                        self.selecting = true;
+
+                        self.history.seal();
+
+                        if let Some(position) = self.screen_pos_to_text_pos(*pos) {
+                            if self.click_timer > 0.0 && self.last_click == Some(position) {
+                                // Double click - select whole word.
+                                self.click_timer = 0.0;
+                                self.selecting = false;
+                                self.select_word_at(position);
+                            } else {
+                                self.click_timer = DOUBLE_CLICK_INTERVAL;
+                                self.last_click = Some(position);
 
-                            if let Some(position) = self.screen_pos_to_text_pos(*pos) {
                                 self.caret_line = position.line;
                                 self.caret_offset = position.offset;
 
@@ -517,37 +1470,72 @@ impl<M, C: 'static + Control<M, C>> Control<M, C> for TextBox<M, C> {
                                     end: position,
                                 })
                             }
-
-                            ui.capture_mouse(self_handle);
                         }
+
+                        ui.capture_mouse(self_handle);
                     }
-                    WidgetMessage::MouseMove(pos) => {
-                        if self.selecting {
-                            if let Some(position) = self.screen_pos_to_text_pos(*pos) {
-                                if let Some(ref mut sel_range) = self.selection_range {
-                                    sel_range.end = position;
-                                }
+                }
+                WidgetMessage::MouseMove(pos) => {
+                    if self.selecting {
+                        if let Some(position) = self.screen_pos_to_text_pos(*pos) {
+                            if let Some(ref mut sel_range) = self.selection_range {
+                                sel_range.end = position;
                             }
+                            self.caret_line = position.line;
+                            self.caret_offset = position.offset;
                         }
                     }
-                    WidgetMessage::MouseUp { .. } => {
-                        self.selecting = false;
+                }
+                WidgetMessage::MouseUp { .. } => {
+                    self.selecting = false;
 
-                        ui.release_mouse_capture();
-                    }
-                    _ => {}
+                    ui.release_mouse_capture();
                 }
+                _ => {}
+            }
+        }
+
+        if key_handled {
+            message.handled = true;
+        }
+
+        if let UiMessageData::TextBox(TextBoxMessage::Text(text)) = &message.data {
+            if message.target == self_handle {
+                self.set_text(text);
             }
         }
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for TextBox<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.enter_region("formatted_text");
+        self.formatted_text.get_mut().visit(visitor)?;
+        visitor.leave_region();
+        visitor.visit("caret_brush", &mut self.caret_brush)?;
+        visitor.visit("selection_brush", &mut self.selection_brush)?;
+        visitor.visit("read_only", &mut self.read_only)?;
+        visitor.enter_region("placeholder");
+        self.placeholder.get_mut().visit(visitor)?;
+        visitor.leave_region();
+        visitor.visit("placeholder_brush", &mut self.placeholder_brush)
+    }
+}
+
 pub struct TextBoxBuilder<M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     font: Option<Arc<Mutex<Font>>>,
+    fallback_fonts: Vec<Arc<Mutex<Font>>>,
     text: String,
     caret_brush: Brush,
     selection_brush: Brush,
+    history_depth: usize,
+    filter: Option<TextFilter>,
+    mask_char: Option<char>,
+    read_only: bool,
+    placeholder: String,
+    placeholder_brush: Brush,
 }
 
 impl<M, C: 'static + Control<M, C>> TextBoxBuilder<M, C> {
@@ -555,9 +1543,16 @@ impl<M, C: 'static + Control<M, C>> TextBoxBuilder<M, C> {
         Self {
             widget_builder,
             font: None,
+            fallback_fonts: Vec::new(),
             text: "".to_owned(),
             caret_brush: Brush::Solid(Color::WHITE),
             selection_brush: Brush::Solid(Color::opaque(65, 65, 90)),
+            history_depth: DEFAULT_HISTORY_DEPTH,
+            filter: None,
+            mask_char: None,
+            read_only: false,
+            placeholder: "".to_owned(),
+            placeholder_brush: Brush::Solid(Color::opaque(120, 120, 120)),
         }
     }
 
@@ -566,6 +1561,12 @@ impl<M, C: 'static + Control<M, C>> TextBoxBuilder<M, C> {
         self
     }
 
+    /// Sets fonts which will be used for characters that are missing in primary font.
+    pub fn with_fallback_fonts(mut self, fonts: Vec<Arc<Mutex<Font>>>) -> Self {
+        self.fallback_fonts = fonts;
+        self
+    }
+
     pub fn with_text(mut self, text: String) -> Self {
         self.text = text;
         self
@@ -581,10 +1582,50 @@ impl<M, C: 'static + Control<M, C>> TextBoxBuilder<M, C> {
         self
     }
 
+    /// Sets maximum amount of steps that can be undone. Zero disables history.
+    pub fn with_history_depth(mut self, depth: usize) -> Self {
+        self.history_depth = depth;
+        self
+    }
+
+    /// Sets filter that decides which characters can be inserted by user.
+    pub fn with_filter<F>(mut self, filter: F) -> Self
+        where F: Fn(char, &str) -> bool + 'static {
+        self.filter = Some(Rc::new(filter));
+        self
+    }
+
+    /// Sets character which will be drawn instead of every character of text, use it
+    /// to create password box.
+    pub fn with_mask_char(mut self, mask_char: char) -> Self {
+        self.mask_char = Some(mask_char);
+        self
+    }
+
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn with_placeholder(mut self, placeholder: String) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    pub fn with_placeholder_brush(mut self, brush: Brush) -> Self {
+        self.placeholder_brush = brush;
+        self
+    }
+
     pub fn build(mut self, ui: &mut UserInterface<M, C>) -> Handle<UINode<M, C>> {
         if self.widget_builder.foreground.is_none() {
             self.widget_builder.foreground = Some(Brush::Solid(Color::opaque(220, 220, 220)));
         }
+        if self.widget_builder.focusable.is_none() {
+            self.widget_builder.focusable = Some(true);
+        }
+
+        let font = self.font.unwrap_or_else(|| crate::DEFAULT_FONT.clone());
 
         let text_box = TextBox {
             widget: self.widget_builder.build(),
@@ -595,13 +1636,32 @@ impl<M, C: 'static + Control<M, C>> TextBoxBuilder<M, C> {
             blink_interval: 0.5,
             formatted_text: RefCell::new(FormattedTextBuilder::new()
                 .with_text(self.text)
-                .with_font(self.font.unwrap_or_else(|| crate::DEFAULT_FONT.clone()))
+                .with_font(font.clone())
+                .with_fallback_fonts(self.fallback_fonts.clone())
+                .with_mask_char(self.mask_char)
                 .build()),
             selection_range: None,
             selecting: false,
             selection_brush: self.selection_brush,
             caret_brush: self.caret_brush,
             has_focus: false,
+            history: EditHistory::new(self.history_depth),
+            click_timer: 0.0,
+            last_click: None,
+            filter: self.filter,
+            read_only: self.read_only,
+            placeholder: RefCell::new(FormattedTextBuilder::new()
+                .with_text(self.placeholder)
+                .with_font(font.clone())
+                .with_fallback_fonts(self.fallback_fonts.clone())
+                .build()),
+            placeholder_brush: self.placeholder_brush,
+            composing: false,
+            composition_text: RefCell::new(FormattedTextBuilder::new()
+                .with_font(font)
+                .with_fallback_fonts(self.fallback_fonts)
+                .build()),
+            composition_cursor: None,
         };
 
         let handle = ui.add_node(UINode::TextBox(text_box));
@@ -610,4 +1670,557 @@ impl<M, C: 'static + Control<M, C>> TextBoxBuilder<M, C> {
 
         handle
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        text_box::{
+            EditHistory, Edit, EditKind, Position, TextBox, TextBoxBuilder, HorizontalDirection,
+            digits_filter, max_length_filter,
+        },
+        widget::WidgetBuilder,
+        test::{StubUiMessage, StubUiNode},
+        node::UINode,
+        clipboard::{Clipboard, MemoryClipboard},
+        message::{
+            KeyCode, KeyboardModifiers, MouseButton, UiMessage, UiMessageData, TextBoxMessage,
+            CompositionEvent, OsEvent,
+        },
+        test_driver::TestDriver,
+        UserInterface,
+        core::{math::vec2::Vec2, pool::Handle},
+    };
+
+    type Ui = UserInterface<StubUiMessage, StubUiNode>;
+
+    fn build_text_box(ui: &mut Ui, text: &str) -> Handle<UINode<StubUiMessage, StubUiNode>> {
+        TextBoxBuilder::new(WidgetBuilder::new()
+            .with_width(200.0)
+            .with_height(30.0))
+            .with_text(text.to_owned())
+            .build(ui)
+    }
+
+    fn text_box_mut(ui: &mut Ui, handle: Handle<UINode<StubUiMessage, StubUiNode>>) -> &mut TextBox<StubUiMessage, StubUiNode> {
+        if let UINode::TextBox(text_box) = ui.node_mut(handle) {
+            text_box
+        } else {
+            panic!("expected text box")
+        }
+    }
+
+    fn control() -> KeyboardModifiers {
+        KeyboardModifiers { control: true, ..Default::default() }
+    }
+
+    fn shift() -> KeyboardModifiers {
+        KeyboardModifiers { shift: true, ..Default::default() }
+    }
+
+    fn none() -> KeyboardModifiers {
+        KeyboardModifiers::default()
+    }
+
+    /// Builds text box and focuses it, so driver input goes to the text box.
+    fn focused_text_box<'a>(ui: &'a mut Ui, text: &str) -> (TestDriver<'a, StubUiMessage, StubUiNode>, Handle<UINode<StubUiMessage, StubUiNode>>) {
+        let handle = build_text_box(ui, text);
+        let mut driver = TestDriver::new(ui, Vec2::new(300.0, 300.0));
+        driver.ui_mut().set_focus(handle);
+        driver.advance(0.0);
+        (driver, handle)
+    }
+
+    fn selected_text(driver: &mut TestDriver<StubUiMessage, StubUiNode>, handle: Handle<UINode<StubUiMessage, StubUiNode>>) -> Option<String> {
+        text_box_mut(driver.ui_mut(), handle).selected_text()
+    }
+
+    /// Returns texts of `TextChanged` and `Submitted` messages of given text box, submitted
+    /// text is prefixed with `submit:`.
+    fn text_box_events(messages: Vec<UiMessage<StubUiMessage, StubUiNode>>, handle: Handle<UINode<StubUiMessage, StubUiNode>>) -> Vec<String> {
+        messages.into_iter()
+            .filter(|message| message.source == handle)
+            .filter_map(|message| match message.data {
+                UiMessageData::TextBox(TextBoxMessage::TextChanged(text)) => Some(text),
+                UiMessageData::TextBox(TextBoxMessage::Submitted(text)) => Some(format!("submit:{}", text)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn caret_index(driver: &mut TestDriver<StubUiMessage, StubUiNode>, handle: Handle<UINode<StubUiMessage, StubUiNode>>) -> Option<usize> {
+        text_box_mut(driver.ui_mut(), handle).get_absolute_position()
+    }
+
+    fn typing(position: usize, text: &str) -> Edit {
+        Edit {
+            kind: EditKind::Typing,
+            position,
+            removed: String::new(),
+            inserted: text.to_owned(),
+            caret_before: Position { line: 0, offset: position },
+            selection_before: None,
+            caret_after: Position { line: 0, offset: position + text.chars().count() },
+        }
+    }
+
+    #[test]
+    fn typing_is_merged() {
+        let mut history = EditHistory::new(10);
+        history.push(typing(0, "a"));
+        history.push(typing(1, "b"));
+        history.push(typing(2, "c"));
+        // Not adjacent to previous typing, so it starts new step.
+        history.push(typing(0, "d"));
+
+        assert_eq!(history.edits.len(), 2);
+        assert_eq!(history.edits[0].inserted, "abc");
+        assert!(history.edits[0].caret_after == Position { line: 0, offset: 3 });
+
+        // Sealed history does not merge even adjacent typing.
+        history.seal();
+        history.push(typing(1, "e"));
+        assert_eq!(history.edits.len(), 3);
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut history = EditHistory::new(10);
+        history.push(typing(0, "a"));
+        history.seal();
+        history.push(typing(1, "b"));
+        history.seal();
+        history.push(typing(2, "c"));
+
+        assert_eq!(history.undo().unwrap().inserted, "c");
+        assert_eq!(history.undo().unwrap().inserted, "b");
+        assert!(history.can_redo());
+        assert_eq!(history.redo().unwrap().inserted, "b");
+
+        // New edit drops everything that could be redone.
+        history.push(typing(2, "x"));
+        assert!(!history.can_redo());
+        assert!(history.redo().is_none());
+        assert_eq!(history.edits.iter().map(|edit| edit.inserted.as_str()).collect::<Vec<_>>(), vec!["a", "b", "x"]);
+
+        assert_eq!(history.undo().unwrap().inserted, "x");
+        assert_eq!(history.undo().unwrap().inserted, "b");
+        assert_eq!(history.undo().unwrap().inserted, "a");
+        assert!(history.undo().is_none());
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn password_is_one_word() {
+        let mut ui = UserInterface::<StubUiMessage, StubUiNode>::new();
+        let text_box = TextBoxBuilder::new(WidgetBuilder::new()
+            .with_width(200.0)
+            .with_height(30.0))
+            .with_text("ab cd".to_owned())
+            .with_mask_char('*')
+            .build(&mut ui);
+        ui.update(Vec2::new(300.0, 300.0), 0.0);
+
+        if let UINode::TextBox(text_box) = ui.node_mut(text_box) {
+            text_box.set_caret_char_index(1);
+            text_box.move_caret_word(HorizontalDirection::Right);
+            assert_eq!(text_box.get_absolute_position(), Some(5));
+
+            text_box.select_word_at(Position { line: 0, offset: 4 });
+            let range = text_box.selection_range.unwrap().normalized();
+            assert!(range.begin == Position { line: 0, offset: 0 });
+            assert!(range.end == Position { line: 0, offset: 5 });
+        } else {
+            panic!("expected text box");
+        }
+    }
+
+    #[test]
+    fn copy_cut_paste() {
+        let mut ui = Ui::new();
+        let handle = build_text_box(&mut ui, "hello world");
+        ui.update(Vec2::new(300.0, 300.0), 0.0);
+        let mut clipboard = MemoryClipboard::new();
+        let text_box = text_box_mut(&mut ui, handle);
+
+        // Nothing is selected.
+        assert!(!text_box.copy(&mut clipboard));
+        assert!(!text_box.cut(&mut clipboard));
+        assert_eq!(clipboard.get_text(), None);
+
+        text_box.select_word_at(Position { line: 0, offset: 1 });
+        assert!(text_box.copy(&mut clipboard));
+        assert_eq!(clipboard.get_text(), Some("hello".to_owned()));
+        assert_eq!(text_box.text(), "hello world");
+
+        assert!(text_box.cut(&mut clipboard));
+        assert_eq!(text_box.text(), " world");
+
+        text_box.set_caret_char_index(6);
+        assert!(text_box.paste(&mut clipboard));
+        assert_eq!(text_box.text(), " worldhello");
+
+        // Pasted text replaces selection.
+        clipboard.set_text("bye".to_owned());
+        text_box.select_all();
+        assert!(text_box.paste(&mut clipboard));
+        assert_eq!(text_box.text(), "bye");
+    }
+
+    #[test]
+    fn read_only_cut_copies() {
+        let mut ui = Ui::new();
+        let handle = build_text_box(&mut ui, "hello");
+        ui.update(Vec2::new(300.0, 300.0), 0.0);
+        let mut clipboard = MemoryClipboard::new();
+        let text_box = text_box_mut(&mut ui, handle);
+        text_box.set_read_only(true);
+        text_box.select_all();
+
+        assert!(text_box.cut(&mut clipboard));
+        assert_eq!(clipboard.get_text(), Some("hello".to_owned()));
+        assert_eq!(text_box.text(), "hello");
+
+        clipboard.set_text("other".to_owned());
+        assert!(!text_box.paste(&mut clipboard));
+        assert_eq!(text_box.text(), "hello");
+    }
+
+    #[test]
+    fn password_is_not_copied() {
+        let mut ui = Ui::new();
+        let handle = build_text_box(&mut ui, "secret");
+        ui.update(Vec2::new(300.0, 300.0), 0.0);
+        let mut clipboard = MemoryClipboard::new();
+        let text_box = text_box_mut(&mut ui, handle);
+        text_box.set_mask_char(Some('*'));
+        text_box.select_all();
+
+        assert!(!text_box.copy(&mut clipboard));
+        assert!(!text_box.cut(&mut clipboard));
+        assert_eq!(clipboard.get_text(), None);
+        assert_eq!(text_box.text(), "secret");
+    }
+
+    #[test]
+    fn clipboard_shortcuts() {
+        let mut ui = Ui::new();
+        let handle = build_text_box(&mut ui, "hello");
+        let mut driver = TestDriver::new(&mut ui, Vec2::new(300.0, 300.0));
+        driver.ui_mut().set_focus(handle);
+        driver.advance(0.0);
+
+        driver.press(KeyCode::A, control())
+            .press(KeyCode::C, control())
+            .press(KeyCode::End, KeyboardModifiers::default())
+            .press(KeyCode::V, control());
+        assert_eq!(driver.ui_mut().clipboard_mut().get_text(), Some("hello".to_owned()));
+        assert_eq!(text_box_mut(driver.ui_mut(), handle).text(), "hellohello");
+
+        driver.press(KeyCode::A, control())
+            .press(KeyCode::X, control());
+        assert_eq!(text_box_mut(driver.ui_mut(), handle).text(), "");
+        driver.press(KeyCode::V, control());
+        assert_eq!(text_box_mut(driver.ui_mut(), handle).text(), "hellohello");
+    }
+
+    #[test]
+    fn shift_arrows_extend_selection() {
+        let mut ui = Ui::new();
+        let (mut driver, handle) = focused_text_box(&mut ui, "hello world");
+
+        driver.press(KeyCode::End, none());
+        for _ in 0..3 {
+            driver.press(KeyCode::Left, shift());
+        }
+        assert_eq!(selected_text(&mut driver, handle), Some("rld".to_owned()));
+        driver.press(KeyCode::Right, shift());
+        assert_eq!(selected_text(&mut driver, handle), Some("ld".to_owned()));
+
+        // Arrow without shift collapses selection to its edge.
+        driver.press(KeyCode::Left, none());
+        assert_eq!(selected_text(&mut driver, handle), None);
+        assert_eq!(caret_index(&mut driver, handle), Some(9));
+    }
+
+    #[test]
+    fn shift_home_end_select_to_line_edges() {
+        let mut ui = Ui::new();
+        let (mut driver, handle) = focused_text_box(&mut ui, "hello world");
+
+        driver.press(KeyCode::Home, none());
+        for _ in 0..5 {
+            driver.press(KeyCode::Right, none());
+        }
+        driver.press(KeyCode::End, shift());
+        assert_eq!(selected_text(&mut driver, handle), Some(" world".to_owned()));
+        // Selection keeps its anchor.
+        driver.press(KeyCode::Home, shift());
+        assert_eq!(selected_text(&mut driver, handle), Some("hello".to_owned()));
+        assert_eq!(caret_index(&mut driver, handle), Some(0));
+    }
+
+    #[test]
+    fn ctrl_a_selects_all() {
+        let mut ui = Ui::new();
+        let (mut driver, handle) = focused_text_box(&mut ui, "hello world");
+
+        driver.press(KeyCode::A, control());
+        assert_eq!(selected_text(&mut driver, handle), Some("hello world".to_owned()));
+        assert_eq!(caret_index(&mut driver, handle), Some(11));
+    }
+
+    #[test]
+    fn ctrl_arrows_move_by_words() {
+        let mut ui = Ui::new();
+        let (mut driver, handle) = focused_text_box(&mut ui, "hello world foo");
+
+        driver.press(KeyCode::Home, control());
+        driver.press(KeyCode::Right, control());
+        assert_eq!(caret_index(&mut driver, handle), Some(6));
+        driver.press(KeyCode::Right, control());
+        assert_eq!(caret_index(&mut driver, handle), Some(12));
+        driver.press(KeyCode::Right, control());
+        assert_eq!(caret_index(&mut driver, handle), Some(15));
+        driver.press(KeyCode::Left, control());
+        assert_eq!(caret_index(&mut driver, handle), Some(12));
+
+        driver.press(KeyCode::Left, KeyboardModifiers { control: true, shift: true, ..Default::default() });
+        assert_eq!(caret_index(&mut driver, handle), Some(6));
+        assert_eq!(selected_text(&mut driver, handle), Some("world ".to_owned()));
+    }
+
+    #[test]
+    fn page_up_down_move_by_visible_lines() {
+        let mut ui = Ui::new();
+        let text = (0..20).map(|i| i.to_string()).collect::<Vec<_>>().join("\n");
+        let handle = TextBoxBuilder::new(WidgetBuilder::new()
+            .with_width(200.0)
+            .with_height(100.0))
+            .with_text(text)
+            .build(&mut ui);
+        let mut driver = TestDriver::new(&mut ui, Vec2::new(300.0, 300.0));
+        driver.ui_mut().set_focus(handle);
+        driver.advance(0.0);
+
+        let per_page = {
+            let text_box = text_box_mut(driver.ui_mut(), handle);
+            let line_height = text_box.formatted_text.borrow().get_lines()[0].height;
+            (text_box.widget.actual_size().y / line_height) as usize
+        };
+        assert!(per_page >= 1 && per_page < 10);
+        let caret_line = |driver: &mut TestDriver<StubUiMessage, StubUiNode>| text_box_mut(driver.ui_mut(), handle).caret_position().line;
+
+        driver.press(KeyCode::Home, control());
+        driver.press(KeyCode::PageDown, none());
+        assert_eq!(caret_line(&mut driver), per_page);
+
+        driver.press(KeyCode::PageDown, shift());
+        assert_eq!(caret_line(&mut driver), 2 * per_page);
+        let range = text_box_mut(driver.ui_mut(), handle).selection_range.unwrap().normalized();
+        assert!(range.begin == Position { line: per_page, offset: 0 });
+        assert!(range.end == Position { line: 2 * per_page, offset: 0 });
+
+        driver.press(KeyCode::PageUp, none());
+        assert_eq!(caret_line(&mut driver), per_page);
+        assert_eq!(selected_text(&mut driver, handle), None);
+
+        // Caret stops at first and last lines.
+        for _ in 0..20 {
+            driver.press(KeyCode::PageDown, none());
+        }
+        assert_eq!(caret_line(&mut driver), 19);
+        for _ in 0..20 {
+            driver.press(KeyCode::PageUp, none());
+        }
+        assert_eq!(caret_line(&mut driver), 0);
+    }
+
+    #[test]
+    fn double_click_selects_word() {
+        let mut ui = Ui::new();
+        let (mut driver, handle) = focused_text_box(&mut ui, "hello world");
+
+        let char_center = {
+            let text_box = text_box_mut(driver.ui_mut(), handle);
+            let origin = text_box.widget.screen_position;
+            let text = text_box.formatted_text.borrow();
+            let line = text.get_lines()[0];
+            Vec2::new(
+                origin.x + text.get_char_x(7) + text.get_char_advance(7) * 0.5,
+                origin.y + line.y_offset + line.height * 0.5,
+            )
+        };
+
+        // Single click only moves caret.
+        driver.click_at(char_center, MouseButton::Left);
+        assert_eq!(selected_text(&mut driver, handle), None);
+        assert_eq!(caret_index(&mut driver, handle), Some(7));
+
+        driver.click_at(char_center, MouseButton::Left);
+        assert_eq!(selected_text(&mut driver, handle), Some("world".to_owned()));
+    }
+
+    #[test]
+    fn text_changed_and_submitted_are_emitted() {
+        let mut ui = Ui::new();
+        let (mut driver, handle) = focused_text_box(&mut ui, "");
+        driver.take_messages();
+
+        driver.type_text("ab");
+        assert_eq!(text_box_events(driver.take_messages(), handle), vec!["a", "b"]);
+
+        driver.press(KeyCode::Return, none());
+        assert_eq!(text_box_events(driver.take_messages(), handle), vec!["submit:ab"]);
+
+        driver.press(KeyCode::Backspace, none());
+        assert_eq!(text_box_events(driver.take_messages(), handle), vec!["a"]);
+
+        // Keys that do not change text emit nothing.
+        driver.press(KeyCode::Left, none());
+        assert!(text_box_events(driver.take_messages(), handle).is_empty());
+        driver.press(KeyCode::Delete, none());
+        assert_eq!(text_box_events(driver.take_messages(), handle), vec![""]);
+        driver.press(KeyCode::Backspace, none());
+        assert!(text_box_events(driver.take_messages(), handle).is_empty());
+    }
+
+    #[test]
+    fn digits_filter_rejects_other_characters() {
+        let mut ui = Ui::new();
+        let (mut driver, handle) = focused_text_box(&mut ui, "");
+        text_box_mut(driver.ui_mut(), handle).set_filter(Some(digits_filter()));
+        driver.take_messages();
+
+        driver.type_text("a1b2");
+        assert_eq!(text_box_mut(driver.ui_mut(), handle).text(), "12");
+        // Rejected characters do not change text, so they emit nothing.
+        assert_eq!(text_box_events(driver.take_messages(), handle), vec!["1", "12"]);
+
+        // Pasted text is filtered too.
+        driver.ui_mut().clipboard_mut().set_text("3x4".to_owned());
+        driver.press(KeyCode::V, control());
+        assert_eq!(text_box_mut(driver.ui_mut(), handle).text(), "1234");
+    }
+
+    #[test]
+    fn max_length_filter_limits_text() {
+        let mut ui = Ui::new();
+        let (mut driver, handle) = focused_text_box(&mut ui, "");
+        text_box_mut(driver.ui_mut(), handle).set_filter(Some(max_length_filter(3)));
+
+        driver.type_text("abcde");
+        assert_eq!(text_box_mut(driver.ui_mut(), handle).text(), "abc");
+
+        // Replaced selection does not count towards the limit.
+        driver.press(KeyCode::Left, shift())
+            .type_text("xy");
+        assert_eq!(text_box_mut(driver.ui_mut(), handle).text(), "abx");
+    }
+
+    fn compose(driver: &mut TestDriver<StubUiMessage, StubUiNode>, composition: CompositionEvent) {
+        driver.send(&OsEvent::Composition(composition));
+    }
+
+    fn update(text: &str, cursor: Option<usize>) -> CompositionEvent {
+        CompositionEvent::Update { text: text.to_owned(), cursor }
+    }
+
+    #[test]
+    fn composition_is_inserted_on_commit() {
+        let mut ui = Ui::new();
+        let (mut driver, handle) = focused_text_box(&mut ui, "");
+        driver.type_text("ab");
+        driver.take_messages();
+
+        compose(&mut driver, CompositionEvent::Start);
+        assert!(text_box_mut(driver.ui_mut(), handle).is_composing());
+        compose(&mut driver, update("ka", Some(2)));
+        assert!(text_box_mut(driver.ui_mut(), handle).is_composing());
+        // Preedit text is not a part of text until it is committed.
+        assert_eq!(text_box_mut(driver.ui_mut(), handle).text(), "ab");
+
+        // Keys are consumed by input method while composing.
+        driver.press(KeyCode::Left, none());
+        driver.press(KeyCode::Backspace, none());
+        assert_eq!(caret_index(&mut driver, handle), Some(2));
+        assert!(text_box_events(driver.take_messages(), handle).is_empty());
+
+        compose(&mut driver, CompositionEvent::Commit("\u{304B}".to_owned()));
+        assert!(!text_box_mut(driver.ui_mut(), handle).is_composing());
+        assert_eq!(text_box_events(driver.take_messages(), handle), vec!["ab\u{304B}"]);
+        assert_eq!(caret_index(&mut driver, handle), Some(3));
+
+        // Committed text is undone as single edit.
+        driver.press(KeyCode::Z, control());
+        assert_eq!(text_box_mut(driver.ui_mut(), handle).text(), "ab");
+    }
+
+    #[test]
+    fn cancelled_composition_is_discarded() {
+        let mut ui = Ui::new();
+        let (mut driver, handle) = focused_text_box(&mut ui, "");
+        driver.type_text("ab");
+        driver.take_messages();
+
+        compose(&mut driver, CompositionEvent::Start);
+        compose(&mut driver, update("ka", None));
+        compose(&mut driver, CompositionEvent::Cancel);
+        assert!(!text_box_mut(driver.ui_mut(), handle).is_composing());
+        assert_eq!(text_box_mut(driver.ui_mut(), handle).text(), "ab");
+        assert!(text_box_events(driver.take_messages(), handle).is_empty());
+
+        // Keys work again after composition is finished.
+        driver.press(KeyCode::Backspace, none());
+        assert_eq!(text_box_mut(driver.ui_mut(), handle).text(), "a");
+    }
+
+    #[test]
+    fn read_only_text_box_ignores_composition() {
+        let mut ui = Ui::new();
+        let (mut driver, handle) = focused_text_box(&mut ui, "ab");
+        text_box_mut(driver.ui_mut(), handle).set_read_only(true);
+
+        compose(&mut driver, CompositionEvent::Start);
+        assert!(!text_box_mut(driver.ui_mut(), handle).is_composing());
+        compose(&mut driver, CompositionEvent::Commit("c".to_owned()));
+        assert_eq!(text_box_mut(driver.ui_mut(), handle).text(), "ab");
+    }
+
+    #[test]
+    fn caret_rect_follows_caret_and_composition_cursor() {
+        let mut ui = Ui::new();
+        let handle = build_text_box(&mut ui, "");
+        let mut driver = TestDriver::new(&mut ui, Vec2::new(300.0, 300.0));
+        // Nothing accepts text input without focus.
+        assert!(driver.ui().caret_rect().is_none());
+
+        driver.ui_mut().set_focus(handle);
+        driver.advance(0.0);
+        driver.type_text("ab");
+        let caret_x = |driver: &TestDriver<StubUiMessage, StubUiNode>| driver.ui().caret_rect().unwrap().x;
+
+        let end = driver.ui().caret_rect().unwrap();
+        let bounds = driver.ui().node(handle).widget().screen_bounds();
+        assert_eq!(end.w, 2.0);
+        assert!(end.h > 0.0 && end.x > bounds.x && end.y >= bounds.y);
+        driver.press(KeyCode::Home, none());
+        let home_x = caret_x(&driver);
+        let text_width = text_box_mut(driver.ui_mut(), handle).formatted_text.borrow().get_range_width(0..2);
+        assert!((end.x - home_x - text_width).abs() < 0.001);
+
+        // Caret is placed at cursor of composition text, or at its end without cursor.
+        driver.press(KeyCode::End, none());
+        compose(&mut driver, CompositionEvent::Start);
+        compose(&mut driver, update("ka", Some(0)));
+        assert!((caret_x(&driver) - end.x).abs() < 0.001);
+        compose(&mut driver, update("ka", Some(1)));
+        let inside_x = caret_x(&driver);
+        assert!(inside_x > end.x);
+        compose(&mut driver, update("ka", None));
+        assert!(caret_x(&driver) > inside_x);
+
+        compose(&mut driver, CompositionEvent::Cancel);
+        assert!((caret_x(&driver) - end.x).abs() < 0.001);
+    }
+}
@@ -1,8 +1,6 @@
 // TTF loader, rasterizer, atlas builder.
 // This code is straight port of C TTF loader from DmitrysEngine
-// There are a lot of unsafe places, should be replaced by some native rust font library.
-
-#![allow(unsafe_code)]
+// All reads of font data are bounds-checked, so malformed fonts fail to load with `FontError`.
 
 use crate::{
     core::{
@@ -12,11 +10,13 @@ use crate::{
         },
         pool::{Pool, Handle},
     },
-    draw::Texture
+    draw::Texture,
+    cff,
 };
 use std::{
     cmp::Ordering,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    fmt,
     path::Path,
     fs::File,
     io::Read,
@@ -27,6 +27,55 @@ use std::sync::Arc;
 const ON_CURVE_POINT: u8 = 1;
 const REPEAT_FLAG: u8 = 8;
 
+// Flags of components of composite glyph.
+const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+const ARGS_ARE_XY_VALUES: u16 = 0x0002;
+const WE_HAVE_A_SCALE: u16 = 0x0008;
+const MORE_COMPONENTS: u16 = 0x0020;
+const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+/// Max nesting of composite glyphs, protects from cyclic references in broken fonts.
+const MAX_COMPOSITE_DEPTH: usize = 8;
+
+/// Error that can occur while loading a font.
+#[derive(Debug)]
+pub enum FontError {
+    /// Font file cannot be read.
+    Io(std::io::Error),
+    /// Font has no table which is required to load it, contains tag of the table.
+    MissingTable(&'static str),
+    /// `cmap` table has no subtable of supported format (0, 4 or 6).
+    UnsupportedCmapFormat,
+    /// Table or record ends outside of font data, contains tag of the table.
+    TruncatedData(&'static str),
+    /// Table contains inconsistent data, contains tag of the table.
+    InvalidData(&'static str),
+    /// Glyph does not fit into atlas of max size.
+    AtlasOverflow,
+}
+
+impl From<std::io::Error> for FontError {
+    fn from(e: std::io::Error) -> Self {
+        FontError::Io(e)
+    }
+}
+
+impl fmt::Display for FontError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FontError::Io(e) => write!(f, "unable to read font: {}", e),
+            FontError::MissingTable(tag) => write!(f, "font has no '{}' table", tag),
+            FontError::UnsupportedCmapFormat => write!(f, "font has no 'cmap' subtable of supported format"),
+            FontError::TruncatedData(tag) => write!(f, "'{}' table is truncated", tag),
+            FontError::InvalidData(tag) => write!(f, "'{}' table contains invalid data", tag),
+            FontError::AtlasOverflow => write!(f, "glyph does not fit into atlas of max size"),
+        }
+    }
+}
+
+impl std::error::Error for FontError {}
+
 #[derive(Copy, Clone, Debug)]
 struct Point {
     x: f32,
@@ -49,6 +98,8 @@ pub struct FontGlyph {
     advance: f32,
     has_outline: bool,
     tex_coords: [Vec2; 4],
+    /// Location of glyph in atlas in pixels.
+    atlas_bounds: Rect<i32>,
 }
 
 #[derive(Debug)]
@@ -68,15 +119,24 @@ struct TtfGlyph {
 
 struct TrueType {
     data: Vec<u8>,
-    cmap_table: *const u8,
-    loca_table: *const u8,
-    maxp_table: *const u8,
-    head_table: *const u8,
-    glyf_table: *const u8,
-    hhea_table: *const u8,
-    hmtx_table: *const u8,
-    kern_table: *const u8,
+    cmap_table: TableLocation,
+    /// Offset of used subtable inside of `cmap` table.
+    cmap_subtable: usize,
+    cmap_format: u16,
+    loca_table: Option<TableLocation>,
+    glyf_table: Option<TableLocation>,
+    hmtx_table: TableLocation,
+    kern_table: Option<TableLocation>,
+    /// `CFF ` table replaces `glyf` and `loca` in OpenType fonts with PostScript outlines.
+    cff_table: Option<TableLocation>,
+    /// `GSUB` table with glyph substitutions that are used for text shaping.
+    gsub_table: Option<TableLocation>,
     num_glyphs: u16,
+    units_per_em: u16,
+    index_to_loc_format: i16,
+    num_of_long_hor_metrics: u16,
+    ascender: i16,
+    descender: i16,
     glyphs: Vec<TtfGlyph>,
 }
 
@@ -91,14 +151,60 @@ struct Bitmap {
     height: usize,
 }
 
+/// Minimal size of glyph atlas, atlas of this size will be created for a font without
+/// preloaded characters.
+const MIN_ATLAS_SIZE: i32 = 128;
+
+/// Max size of glyph atlas, glyph that does not fit into atlas of this size cannot be cached.
+const MAX_ATLAS_SIZE: i32 = 8192;
+
+/// Defines what is stored in glyph atlas of a font.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GlyphRasterMode {
+    /// Antialiased coverage of glyph. Text looks sharp only when drawn at height of font.
+    Bitmap,
+    /// Signed distance to glyph outline, 0.5 (127 in atlas) is exactly on outline, greater
+    /// values are inside glyph. `spread` defines max distance in pixels that is encoded.
+    /// Such atlas must be drawn with distance field shader, but it can be used to draw text
+    /// of many sizes and to draw outlines and glow.
+    DistanceField {
+        spread: f32
+    },
+}
+
+impl Default for GlyphRasterMode {
+    fn default() -> Self {
+        GlyphRasterMode::Bitmap
+    }
+}
+
 pub struct Font {
     height: f32,
+    ttf: TrueType,
+    /// Scale to convert font units to pixels.
+    scale: f32,
+    /// Rasterized glyphs, only glyphs that were requested at least once are rasterized.
     glyphs: Vec<FontGlyph>,
+    /// Maps index of glyph in font file to index in `glyphs`.
+    glyph_map: HashMap<usize, usize>,
+    /// Glyphs that could not be rasterized or put into atlas, they are drawn as empty
+    /// space and are not retried.
+    failed_glyphs: HashSet<usize>,
     ascender: f32,
     descender: f32,
+    /// Maps unicode to index of glyph in font file.
     char_map: HashMap<u32, usize>,
+    /// Horizontal kerning for pairs of glyph indices, in pixels.
+    kerning: HashMap<(usize, usize), f32>,
+    substitutions: Substitutions,
     atlas: Vec<u8>,
     atlas_size: i32,
+    packer: RectPacker,
+    raster_mode: GlyphRasterMode,
+    /// Region of atlas that was changed since last call of `take_dirty_region`.
+    dirty_region: Option<Rect<i32>>,
+    /// Texture of atlas created by renderer. Renderer must re-upload dirty region of atlas
+    /// into it and re-create it if size of atlas has changed.
     pub texture: Option<Arc<Texture>>
 }
 
@@ -124,22 +230,27 @@ impl RectPackNode {
 
 struct RectPacker {
     nodes: Pool<RectPackNode>,
-    root: Handle<RectPackNode>,
+    roots: Vec<Handle<RectPackNode>>,
 }
 
 impl RectPacker {
     fn new(w: i32, h: i32) -> RectPacker {
-        let mut nodes = Pool::new();
-        let root = nodes.spawn(RectPackNode::new(Rect::new(0, 0, w, h)));
-        RectPacker {
-            nodes,
-            root,
-        }
+        let mut packer = RectPacker {
+            nodes: Pool::new(),
+            roots: Vec::new(),
+        };
+        packer.add_free_region(Rect::new(0, 0, w, h));
+        packer
+    }
+
+    /// Adds new free region, used when packing area grows.
+    fn add_free_region(&mut self, bounds: Rect<i32>) {
+        let root = self.nodes.spawn(RectPackNode::new(bounds));
+        self.roots.push(root);
     }
 
     fn find_free(&mut self, w: i32, h: i32) -> Option<Rect<i32>> {
-        let mut unvisited: Vec<Handle<RectPackNode>> = Vec::new();
-        unvisited.push(self.root);
+        let mut unvisited: Vec<Handle<RectPackNode>> = self.roots.iter().rev().cloned().collect();
         while let Some(node_handle) = unvisited.pop() {
             let left_bounds;
             let right_bounds;
@@ -221,89 +332,254 @@ impl Bitmap {
     }
 }
 
-unsafe fn get_u16(p: *const u8) -> u16
-{
-    let b0 = u32::from(*p);
-    let b1 = u32::from(*p.offset(1));
-    (b0 * 256 + b1) as u16
+/// View of a table of font, all reads are bounds-checked and values are big-endian.
+#[derive(Copy, Clone)]
+struct Table<'a> {
+    tag: &'static str,
+    data: &'a [u8],
 }
 
-unsafe fn get_i16(p: *const u8) -> i16
-{
-    let b0 = i32::from(*p);
-    let b1 = i32::from(*p.offset(1));
-    (b0 * 256 + b1) as i16
+impl<'a> Table<'a> {
+    fn bytes(&self, pos: usize, len: usize) -> Result<&'a [u8], FontError> {
+        pos.checked_add(len)
+            .and_then(|end| self.data.get(pos..end))
+            .ok_or(FontError::TruncatedData(self.tag))
+    }
+
+    /// Returns part of table that starts at given position.
+    fn sub_table(&self, pos: usize) -> Result<Table<'a>, FontError> {
+        match self.data.get(pos..) {
+            Some(data) => Ok(Table { tag: self.tag, data }),
+            None => Err(FontError::TruncatedData(self.tag)),
+        }
+    }
+
+    fn sub_range(&self, range: Range<usize>) -> Result<Table<'a>, FontError> {
+        match self.data.get(range) {
+            Some(data) => Ok(Table { tag: self.tag, data }),
+            None => Err(FontError::TruncatedData(self.tag)),
+        }
+    }
+
+    fn get_u8(&self, pos: usize) -> Result<u8, FontError> {
+        Ok(self.bytes(pos, 1)?[0])
+    }
+
+    fn get_u16(&self, pos: usize) -> Result<u16, FontError> {
+        let bytes = self.bytes(pos, 2)?;
+        Ok(u16::from(bytes[0]) << 8 | u16::from(bytes[1]))
+    }
+
+    fn get_i16(&self, pos: usize) -> Result<i16, FontError> {
+        Ok(self.get_u16(pos)? as i16)
+    }
+
+    fn get_u32(&self, pos: usize) -> Result<u32, FontError> {
+        let bytes = self.bytes(pos, 4)?;
+        Ok((u32::from(bytes[0]) << 24) | (u32::from(bytes[1]) << 16) | (u32::from(bytes[2]) << 8) | u32::from(bytes[3]))
+    }
+
+    /// Reads signed fixed point 2.14 number.
+    fn get_f2dot14(&self, pos: usize) -> Result<f32, FontError> {
+        Ok(f32::from(self.get_i16(pos)?) / 16384.0)
+    }
 }
 
-unsafe fn get_u32(p: *const u8) -> u32
-{
-    let b0 = u32::from(*p);
-    let b1 = u32::from(*p.offset(1));
-    let b2 = u32::from(*p.offset(2));
-    let b3 = u32::from(*p.offset(3));
-    (b0 << 24) + (b1 << 16) + (b2 << 8) + b3
+/// Location of a table in font data.
+#[derive(Clone)]
+struct TableLocation {
+    tag: &'static str,
+    range: Range<usize>,
 }
 
 fn fourcc(d: u8, c: u8, b: u8, a: u8) -> u32 {
     (u32::from(d) << 24) | (u32::from(c) << 16) | (u32::from(b) << 8) | (u32::from(a))
 }
 
-fn segmented_mapping(subtable: *const u8, unicode: u32) -> usize {
-    unsafe {
-        let segment_count = u32::from(get_u16(subtable.offset(6)) / 2);
-        let end_codes = subtable.offset(14);
-        let start_codes = subtable.offset((16 + 2 * segment_count) as isize);
-        let id_delta = subtable.offset((16 + 4 * segment_count) as isize);
-        let id_range_offset = subtable.offset((16 + 6 * segment_count) as isize);
-
-        let mut segment = 0;
-        while segment < segment_count {
-            if u32::from(get_u16(end_codes.offset(2 * segment as isize))) >= unicode {
-                break;
+/// Lookup types of `GSUB` table.
+const GSUB_SINGLE: u16 = 1;
+const GSUB_LIGATURE: u16 = 4;
+const GSUB_EXTENSION: u16 = 7;
+
+/// Features of single substitutions that are used to pick contextual forms of Arabic letters.
+const SINGLE_SUBSTITUTION_FEATURES: [&[u8; 4]; 4] = [b"isol", b"init", b"medi", b"fina"];
+/// Features of ligature substitutions, required ligatures and standard ligatures.
+const LIGATURE_FEATURES: [&[u8; 4]; 2] = [b"rlig", b"liga"];
+
+fn feature_tag(feature: &[u8; 4]) -> u32 {
+    fourcc(feature[0], feature[1], feature[2], feature[3])
+}
+
+/// Glyph substitutions from `GSUB` table that are used by text shaping.
+#[derive(Default)]
+struct Substitutions {
+    /// Single substitutions of glyph indices for each feature tag.
+    single: HashMap<u32, HashMap<usize, usize>>,
+    /// Ligatures by first glyph: rest of components and glyph of ligature.
+    ligatures: HashMap<usize, Vec<(Vec<usize>, usize)>>,
+}
+
+/// Max total amount of glyphs in coverage tables of `GSUB`, protects from tables that refer to
+/// same huge coverage many times.
+const MAX_COVERAGE_GLYPHS: usize = 1 << 20;
+
+/// Limits of coverage tables: amount of glyphs in font and amount of glyphs that can still be
+/// read from coverage tables of `GSUB`.
+struct CoverageLimits {
+    num_glyphs: usize,
+    budget: usize,
+}
+
+/// Returns glyphs of coverage table in order of coverage indices. Ranges are clamped to
+/// glyphs of the font, coverage that lists more glyphs than font has is invalid.
+fn read_coverage(coverage: Table, limits: &mut CoverageLimits) -> Result<Vec<usize>, FontError> {
+    let num_glyphs = limits.num_glyphs;
+    let mut glyphs = Vec::new();
+    match coverage.get_u16(0)? {
+        1 => {
+            let glyph_count = coverage.get_u16(2)? as usize;
+            if glyph_count > num_glyphs {
+                return Err(FontError::InvalidData("GSUB"));
+            }
+            for i in 0..glyph_count {
+                glyphs.push(coverage.get_u16(4 + i * 2)? as usize);
             }
-            segment += 1;
         }
-
-        if segment != segment_count {
-            let start_code = u32::from(get_u16(start_codes.offset(2 * segment as isize)));
-            if start_code <= unicode {
-                let range_offset = u32::from(get_u16(id_range_offset.offset(2 * segment as isize)) / 2);
-                if range_offset == 0 {
-                    return ((unicode + u32::from(get_u16(id_delta.offset(2 * segment as isize)))) & 0xFFFF) as usize;
-                } else {
-                    let offset = 2 * (segment + range_offset + (unicode - start_code));
-                    return get_u16(id_range_offset.offset(offset as isize)) as usize;
+        2 => {
+            let range_count = coverage.get_u16(2)? as usize;
+            for i in 0..range_count {
+                let record = 4 + i * 6;
+                let start = coverage.get_u16(record)? as usize;
+                let end = coverage.get_u16(record + 2)? as usize;
+                if end < start {
+                    return Err(FontError::InvalidData("GSUB"));
                 }
+                if start >= num_glyphs {
+                    continue;
+                }
+                let end = end.min(num_glyphs - 1);
+                if glyphs.len() + (end - start + 1) > num_glyphs {
+                    return Err(FontError::InvalidData("GSUB"));
+                }
+                glyphs.extend(start..=end);
             }
         }
+        _ => return Err(FontError::InvalidData("GSUB")),
+    }
+    if glyphs.len() > limits.budget {
+        return Err(FontError::InvalidData("GSUB"));
+    }
+    limits.budget -= glyphs.len();
+    Ok(glyphs)
+}
 
-        0
+fn read_single_substitution(subtable: Table, limits: &mut CoverageLimits, map: &mut HashMap<usize, usize>) -> Result<(), FontError> {
+    let coverage = read_coverage(subtable.sub_table(subtable.get_u16(2)? as usize)?, limits)?;
+    match subtable.get_u16(0)? {
+        1 => {
+            let delta = i32::from(subtable.get_i16(4)?);
+            for glyph in coverage {
+                let substitute = (glyph as i32 + delta).rem_euclid(0x10000) as usize;
+                map.entry(glyph).or_insert(substitute);
+            }
+        }
+        2 => {
+            for (i, glyph) in coverage.into_iter().enumerate() {
+                let substitute = subtable.get_u16(6 + i * 2)? as usize;
+                map.entry(glyph).or_insert(substitute);
+            }
+        }
+        _ => return Err(FontError::InvalidData("GSUB")),
     }
+    Ok(())
 }
 
-fn direct_mapping(subtable: *const u8, unicode: u32) -> usize {
-    unsafe {
-        if unicode < 256 {
-            let indices = subtable.offset(6);
-            return indices.offset(unicode as isize).read() as usize;
+fn read_ligature_substitution(subtable: Table, limits: &mut CoverageLimits, ligatures: &mut HashMap<usize, Vec<(Vec<usize>, usize)>>) -> Result<(), FontError> {
+    if subtable.get_u16(0)? != 1 {
+        return Err(FontError::InvalidData("GSUB"));
+    }
+    let coverage = read_coverage(subtable.sub_table(subtable.get_u16(2)? as usize)?, limits)?;
+    for (i, first) in coverage.into_iter().enumerate() {
+        let ligature_set = subtable.sub_table(subtable.get_u16(6 + i * 2)? as usize)?;
+        let ligature_count = ligature_set.get_u16(0)? as usize;
+        for k in 0..ligature_count {
+            let ligature = ligature_set.sub_table(ligature_set.get_u16(2 + k * 2)? as usize)?;
+            let glyph = ligature.get_u16(0)? as usize;
+            let component_count = ligature.get_u16(2)? as usize;
+            let mut components = Vec::with_capacity(component_count);
+            for n in 1..component_count {
+                components.push(ligature.get_u16(4 + (n - 1) * 2)? as usize);
+            }
+            ligatures.entry(first).or_insert_with(Vec::new).push((components, glyph));
         }
     }
+    Ok(())
+}
 
-    0
+/// Finds first subtable of `cmap` of supported format, returns its offset and format.
+fn find_cmap_subtable(cmap: Table) -> Result<(usize, u16), FontError> {
+    let subtable_count = cmap.get_u16(2)? as usize;
+    for i in 0..subtable_count {
+        let subtable_offset = cmap.get_u32(4 + 8 * i + 4)? as usize;
+        let format = cmap.get_u16(subtable_offset)?;
+        match format {
+            0 | 4 | 6 => return Ok((subtable_offset, format)),
+            _ => () // TODO: Add more mappings
+        }
+    }
+    Err(FontError::UnsupportedCmapFormat)
 }
 
-fn dense_mapping(subtable: *const u8, unicode: u32) -> usize {
-    unsafe {
-        let first = u32::from(get_u16(subtable.offset(6)));
-        let entry_count = u32::from(get_u16(subtable.offset(8)));
-        let indices = subtable.offset(10);
+fn segmented_mapping(subtable: Table, unicode: u32) -> Result<usize, FontError> {
+    let segment_count = (subtable.get_u16(6)? / 2) as usize;
+    let end_codes = 14;
+    let start_codes = 16 + 2 * segment_count;
+    let id_delta = 16 + 4 * segment_count;
+    let id_range_offset = 16 + 6 * segment_count;
+
+    let mut segment = 0;
+    while segment < segment_count {
+        if u32::from(subtable.get_u16(end_codes + 2 * segment)?) >= unicode {
+            break;
+        }
+        segment += 1;
+    }
 
-        if unicode > first && unicode < first + entry_count {
-            return get_u16(indices.offset(2 * (unicode - first) as isize)) as usize;
+    if segment != segment_count {
+        let start_code = u32::from(subtable.get_u16(start_codes + 2 * segment)?);
+        if start_code <= unicode {
+            let delta = u32::from(subtable.get_u16(id_delta + 2 * segment)?);
+            let range_offset = subtable.get_u16(id_range_offset + 2 * segment)? as usize;
+            if range_offset == 0 {
+                return Ok(((unicode + delta) & 0xFFFF) as usize);
+            } else {
+                let offset = id_range_offset + 2 * segment + range_offset + 2 * (unicode - start_code) as usize;
+                let index = u32::from(subtable.get_u16(offset)?);
+                return Ok(if index != 0 { ((index + delta) & 0xFFFF) as usize } else { 0 });
+            }
         }
     }
 
-    0
+    Ok(0)
+}
+
+fn direct_mapping(subtable: Table, unicode: u32) -> Result<usize, FontError> {
+    if unicode < 256 {
+        Ok(subtable.get_u8(6 + unicode as usize)? as usize)
+    } else {
+        Ok(0)
+    }
+}
+
+fn dense_mapping(subtable: Table, unicode: u32) -> Result<usize, FontError> {
+    let first = u32::from(subtable.get_u16(6)?);
+    let entry_count = u32::from(subtable.get_u16(8)?);
+
+    if unicode >= first && unicode < first + entry_count {
+        Ok(subtable.get_u16(10 + 2 * (unicode - first) as usize)? as usize)
+    } else {
+        Ok(0)
+    }
 }
 
 fn line_line_intersection(a: &Line2, b: &Line2) -> Option<Point> {
@@ -447,101 +723,187 @@ fn raster_scanlines(w: usize, h: usize, lines: &[Line2]) -> Bitmap {
     out_bitmap
 }
 
+fn point_segment_distance(px: f32, py: f32, a: &Point, b: &Point) -> f32 {
+    let dx = b.x - a.x;
+    let dy = b.y - a.y;
+    let len_sqr = dx * dx + dy * dy;
+    let t = if len_sqr > 0.0 {
+        (((px - a.x) * dx + (py - a.y) * dy) / len_sqr).max(0.0).min(1.0)
+    } else {
+        0.0
+    };
+    let cx = a.x + t * dx - px;
+    let cy = a.y + t * dy - py;
+    (cx * cx + cy * cy).sqrt()
+}
+
+/// Generates signed distance field of glyph from its contours. Contours are sets of line
+/// segments in font units, field has `padding` pixels on each side so distance could
+/// fade out outside of glyph.
+fn raster_distance_field(w: usize, h: usize, polys: &[Polygon], scale: f32, spread: f32, padding: usize) -> Bitmap {
+    let mut bitmap = Bitmap::new(w + 2 * padding, h + 2 * padding);
+
+    let mut segments = Vec::new();
+    for poly in polys {
+        for j in (0..poly.points.len()).step_by(2) {
+            if let (Some(begin), Some(end)) = (poly.points.get(j), poly.points.get(j + 1)) {
+                segments.push((
+                    Point { x: begin.x * scale, y: begin.y * scale, flags: 0 },
+                    Point { x: end.x * scale, y: end.y * scale, flags: 0 }
+                ));
+            }
+        }
+    }
+
+    for row in 0..bitmap.height {
+        for col in 0..bitmap.width {
+            let px = col as f32 + 0.5 - padding as f32;
+            let py = row as f32 + 0.5 - padding as f32;
+
+            let mut distance = std::f32::MAX;
+            let mut inside = false;
+            for (a, b) in segments.iter() {
+                distance = distance.min(point_segment_distance(px, py, a, b));
+
+                // Even-odd rule, same as scanline rasterizer uses.
+                if (a.y > py) != (b.y > py) && px < a.x + (py - a.y) * (b.x - a.x) / (b.y - a.y) {
+                    inside = !inside;
+                }
+            }
+
+            let signed_distance = if inside { distance } else { -distance };
+            let value = (0.5 + 0.5 * signed_distance / spread).max(0.0).min(1.0);
+            bitmap.set_pixel(col, row, (255.0 * value) as u8);
+        }
+    }
+
+    bitmap
+}
+
 impl TrueType {
-    fn new(data: Vec<u8>) -> TrueType {
+    fn new(data: Vec<u8>) -> Result<TrueType, FontError> {
+        let mut tables = HashMap::new();
+        {
+            let file = Table { tag: "offset table", data: &data };
+            let num_tables = file.get_u16(4)? as usize;
+            for i in 0..num_tables {
+                let record = 12 + i * 16;
+                let tag = file.get_u32(record)?;
+                let offset = file.get_u32(record + 8)? as usize;
+                let length = file.get_u32(record + 12)? as usize;
+                // Make sure that table is inside of font data.
+                file.bytes(offset, length)?;
+                tables.insert(tag, offset..(offset + length));
+            }
+        }
+
+        let find_table = |tag: &'static str| {
+            let bytes = tag.as_bytes();
+            tables.get(&fourcc(bytes[0], bytes[1], bytes[2], bytes[3]))
+                .map(|range| TableLocation { tag, range: range.clone() })
+        };
+        let require_table = |tag: &'static str| find_table(tag).ok_or(FontError::MissingTable(tag));
+        let table = |location: &TableLocation| Table { tag: location.tag, data: &data[location.range.clone()] };
+
+        let head = table(&require_table("head")?);
+        let units_per_em = head.get_u16(18)?;
+        let index_to_loc_format = head.get_i16(50)?;
+        if units_per_em == 0 {
+            return Err(FontError::InvalidData("head"));
+        }
+
+        let num_glyphs = table(&require_table("maxp")?).get_u16(4)?;
+
+        let hhea = table(&require_table("hhea")?);
+        let ascender = hhea.get_i16(4)?;
+        let descender = hhea.get_i16(6)?;
+        let num_of_long_hor_metrics = hhea.get_u16(34)?;
+        if num_of_long_hor_metrics == 0 {
+            return Err(FontError::InvalidData("hhea"));
+        }
+
+        let cmap_table = require_table("cmap")?;
+        let (cmap_subtable, cmap_format) = find_cmap_subtable(table(&cmap_table))?;
+
         let mut ttf = TrueType {
+            cmap_table,
+            cmap_subtable,
+            cmap_format,
+            loca_table: find_table("loca"),
+            glyf_table: find_table("glyf"),
+            hmtx_table: require_table("hmtx")?,
+            kern_table: find_table("kern"),
+            cff_table: find_table("CFF "),
+            gsub_table: find_table("GSUB"),
             data,
-            cmap_table: std::ptr::null(),
-            loca_table: std::ptr::null(),
-            maxp_table: std::ptr::null(),
-            head_table: std::ptr::null(),
-            glyf_table: std::ptr::null(),
-            hhea_table: std::ptr::null(),
-            hmtx_table: std::ptr::null(),
-            kern_table: std::ptr::null(),
-            num_glyphs: 0,
+            num_glyphs,
+            units_per_em,
+            index_to_loc_format,
+            num_of_long_hor_metrics,
+            ascender,
+            descender,
             glyphs: Vec::new(),
         };
 
-        ttf.find_tables();
-        ttf.read_glyphs();
+        ttf.glyphs = ttf.read_glyphs()?;
 
-        ttf
+        Ok(ttf)
     }
 
-    fn find_tables(&mut self) {
-        unsafe {
-            let data = self.data.as_ptr();
-            let num_tables = get_u16(data.offset(4)) as isize;
-
-            for i in 0..num_tables {
-                let subtable = data.offset(12 + i * 16);
-                let tag = get_u32(subtable.offset(0));
-                let offset = get_u32(subtable.offset(8));
-                let table_location = data.offset(offset as isize);
-
-                if tag == fourcc(b'c', b'm', b'a', b'p') {
-                    self.cmap_table = table_location;
-                } else if tag == fourcc(b'l', b'o', b'c', b'a') {
-                    self.loca_table = table_location;
-                } else if tag == fourcc(b'm', b'a', b'x', b'p') {
-                    self.maxp_table = table_location;
-                } else if tag == fourcc(b'h', b'e', b'a', b'd') {
-                    self.head_table = table_location;
-                } else if tag == fourcc(b'g', b'l', b'y', b'f') {
-                    self.glyf_table = table_location;
-                } else if tag == fourcc(b'h', b'h', b'e', b'a') {
-                    self.hhea_table = table_location;
-                } else if tag == fourcc(b'h', b'm', b't', b'x') {
-                    self.hmtx_table = table_location;
-                } else if tag == fourcc(b'k', b'e', b'r', b'n') {
-                    self.kern_table = table_location;
-                }
-            }
-            self.num_glyphs = get_u16(self.maxp_table.offset(4));
+    fn table(&self, location: &TableLocation) -> Table {
+        Table {
+            tag: location.tag,
+            data: &self.data[location.range.clone()],
         }
     }
 
     fn unicode_to_glyph_index(&self, unicode: u32) -> usize {
-        unsafe {
-            let subtable_count = get_u16(self.cmap_table.offset(2));
-            for i in 0..subtable_count {
-                let subtable_offset = get_u32(self.cmap_table.offset((4 + 4 * i + 4) as isize));
-                let subtable = self.cmap_table.offset(subtable_offset as isize);
-                let format = get_u16(subtable);
-                match format {
-                    0 => return direct_mapping(subtable, unicode),
-                    4 => return segmented_mapping(subtable, unicode),
-                    6 => return dense_mapping(subtable, unicode),
-                    _ => () // TODO: Add more mappings
-                }
-            }
+        let index = self.table(&self.cmap_table)
+            .sub_table(self.cmap_subtable)
+            .and_then(|subtable| match self.cmap_format {
+                0 => direct_mapping(subtable, unicode),
+                4 => segmented_mapping(subtable, unicode),
+                _ => dense_mapping(subtable, unicode),
+            });
+
+        // Broken mapping or index of non-existing glyph means that there is no glyph.
+        match index {
+            Ok(index) if index < self.glyphs.len() => index,
+            _ => 0
         }
+    }
 
-        0
+    fn get_glyph_offset(&self, index: usize) -> Result<usize, FontError> {
+        let loca = self.table(self.loca_table.as_ref().ok_or(FontError::MissingTable("loca"))?);
+        if self.index_to_loc_format & 1 != 0 {
+            Ok(loca.get_u32(4 * index)? as usize)
+        } else {
+            Ok(2 * loca.get_u16(2 * index)? as usize)
+        }
     }
 
-    fn get_glyph_offset(&self, index: usize) -> isize {
-        unsafe {
-            let index_to_loc_format = get_i16(self.head_table.offset(50));
-            if index_to_loc_format & 1 != 0 {
-                get_u32(self.loca_table.offset(4 * index as isize)) as isize
-            } else {
-                (get_u16(self.loca_table.offset(2 * index as isize)) * 2) as isize
-            }
+    /// Returns data of glyph from `glyf` table, `None` means that glyph has no outline.
+    fn get_glyph_data(&self, index: usize) -> Result<Option<Table>, FontError> {
+        let glyf = self.table(self.glyf_table.as_ref().ok_or(FontError::MissingTable("glyf"))?);
+        let offset = self.get_glyph_offset(index)?;
+        let next_offset = self.get_glyph_offset(index + 1)?;
+        if next_offset < offset {
+            Err(FontError::InvalidData("loca"))
+        } else if next_offset == offset {
+            Ok(None)
+        } else {
+            Ok(Some(glyf.sub_range(offset..next_offset)?))
         }
     }
 
     fn em_to_pixels(&self, pixels: f32) -> f32 {
-        unsafe {
-            let units_per_em = f32::from(get_u16(self.head_table.offset(18)));
-            pixels / units_per_em
-        }
+        pixels / f32::from(self.units_per_em)
     }
 
-    fn convert_glyph(&self, glyph: &TtfGlyph, scale: f32) -> FontGlyph {
-        if glyph.num_contours < 0 {
-            return FontGlyph {
+    fn convert_glyph(&self, glyph: &TtfGlyph, scale: f32, mode: GlyphRasterMode) -> Result<FontGlyph, FontError> {
+        // Composite glyph that was failed to assemble.
+        if glyph.num_contours < 0 && glyph.contours.is_empty() {
+            return Ok(FontGlyph {
                 bitmap_top: 0.0,
                 bitmap_left: 0.0,
                 bitmap_width: 0.0,
@@ -550,152 +912,359 @@ impl TrueType {
                 advance: 0.0,
                 has_outline: false,
                 tex_coords: [Vec2::ZERO; 4],
-            };
+                atlas_bounds: Rect::new(0, 0, 0, 0),
+            });
         }
 
-        let height = ((scale * f32::from(glyph.y_max - glyph.y_min)) + 1.0) as usize;
-        let width = ((scale * f32::from(glyph.x_max - glyph.x_min)) + 1.0) as usize;
+        let glyph_width = f32::from(glyph.x_max) - f32::from(glyph.x_min);
+        let glyph_height = f32::from(glyph.y_max) - f32::from(glyph.y_min);
 
-        let lines = polygons_to_scanlines(
-            &glyph.contours,
-            f32::from(glyph.x_max - glyph.x_min),
-            f32::from(glyph.y_max - glyph.y_min),
-            scale,
-        );
+        let height = ((scale * glyph_height) + 1.0) as usize;
+        let width = ((scale * glyph_width) + 1.0) as usize;
+        if width > MAX_ATLAS_SIZE as usize || height > MAX_ATLAS_SIZE as usize {
+            return Err(FontError::AtlasOverflow);
+        }
+
+        let (final_bitmap, padding) = match mode {
+            GlyphRasterMode::Bitmap => {
+                let lines = polygons_to_scanlines(&glyph.contours, glyph_width, glyph_height, scale);
+
+                (raster_scanlines(width, height, &lines), 0.0)
+            }
+            GlyphRasterMode::DistanceField { spread } => {
+                let padding = spread.ceil() as usize;
+                (raster_distance_field(width, height, &glyph.contours, scale, spread, padding), padding as f32)
+            }
+        };
 
-        let final_bitmap = raster_scanlines(width, height, &lines);
-        FontGlyph {
+        Ok(FontGlyph {
             pixels: final_bitmap.pixels,
             bitmap_width: final_bitmap.width as f32,
             bitmap_height: final_bitmap.height as f32,
             advance: f32::from(glyph.advance) * scale,
-            bitmap_left: f32::from(glyph.x_min) * scale,
-            bitmap_top: f32::from(glyph.y_min) * scale,
+            bitmap_left: f32::from(glyph.x_min) * scale - padding,
+            bitmap_top: f32::from(glyph.y_min) * scale - padding,
             has_outline: glyph.has_outline,
             tex_coords: [Vec2::ZERO; 4],
-        }
+            atlas_bounds: Rect::new(0, 0, 0, 0),
+        })
     }
 
-    fn get_ascender(&self) -> i16 {
-        unsafe {
-            get_i16(self.hhea_table.offset(4))
+    /// Reads horizontal kerning pairs from format 0 subtables of `kern` table. Both
+    /// Microsoft (version 0) and Apple (version 1) table headers are supported.
+    fn read_kerning(&self) -> Result<HashMap<(usize, usize), i16>, FontError> {
+        let mut pairs = HashMap::new();
+
+        let kern = match self.kern_table {
+            Some(ref kern_table) => self.table(kern_table),
+            None => return Ok(pairs),
+        };
+
+        let version = kern.get_u16(0)?;
+        let (table_count, mut subtable) = if version == 0 {
+            (u32::from(kern.get_u16(2)?), 4)
+        } else if version == 1 {
+            (kern.get_u32(4)?, 8)
+        } else {
+            return Ok(pairs);
+        };
+
+        for _ in 0..table_count {
+            let (length, format, horizontal, header_size) = if version == 0 {
+                let coverage = kern.get_u16(subtable + 4)?;
+                // Skip cross-stream and minimum value subtables.
+                let horizontal = coverage & 0x1 != 0 && coverage & 0x6 == 0;
+                (u32::from(kern.get_u16(subtable + 2)?), coverage >> 8, horizontal, 6)
+            } else {
+                let coverage = kern.get_u16(subtable + 4)?;
+                // Skip vertical, cross-stream and variation subtables.
+                let horizontal = coverage & 0xE000 == 0;
+                (kern.get_u32(subtable)?, coverage & 0xFF, horizontal, 8)
+            };
+
+            if length == 0 {
+                return Err(FontError::InvalidData("kern"));
+            }
+
+            if format == 0 && horizontal {
+                let pair_count = kern.get_u16(subtable + header_size)?;
+                let mut pair = subtable + header_size + 8;
+                for _ in 0..pair_count {
+                    let left = kern.get_u16(pair)? as usize;
+                    let right = kern.get_u16(pair + 2)? as usize;
+                    let value = kern.get_i16(pair + 4)?;
+                    pairs.insert((left, right), value);
+                    pair += 6;
+                }
+            }
+
+            subtable += length as usize;
         }
+
+        Ok(pairs)
     }
 
-    fn get_descender(&self) -> i16 {
-        unsafe {
-            get_i16(self.hhea_table.offset(6))
-        }
-    }
-
-    fn read_glyphs(&mut self) {
-        unsafe {
-            self.glyphs = Vec::with_capacity(self.num_glyphs as usize);
-            for i in 0..(self.num_glyphs as usize) {
-                let offset = self.get_glyph_offset(i);
-                let next_offset = self.get_glyph_offset(i + 1);
-                let glyph_data = self.glyf_table.offset(offset);
-
-                let mut glyph = TtfGlyph {
-                    num_contours: get_i16(glyph_data),
-                    x_min: get_i16(glyph_data.offset(2)),
-                    y_min: get_i16(glyph_data.offset(4)),
-                    x_max: get_i16(glyph_data.offset(6)),
-                    y_max: get_i16(glyph_data.offset(8)),
-                    has_outline: (next_offset - offset) != 0,
-                    advance: 0,
-                    left_side_bearing: 0,
-                    end_points: Vec::new(),
-                    raw_contours: Vec::new(),
-                    contours: Vec::new(),
-                };
+    /// Reads single and ligature substitutions of features that are used by text shaping.
+    /// Script and language systems are not taken into account, substitutions of every
+    /// script are merged.
+    fn read_substitutions(&self) -> Result<Substitutions, FontError> {
+        let mut substitutions = Substitutions::default();
 
-                if glyph.num_contours < 0 {
-                    /* TODO: Implement compound glyph support. */
-                } else {
-                    /* Simple glyph */
-                    /* Read end contour points */
-                    let mut point_count = 0;
-                    for j in 0..glyph.num_contours {
-                        let end_point = get_u16(glyph_data.offset((10 + j * 2) as isize));
-                        glyph.end_points.push(end_point);
-                        if end_point > point_count {
-                            point_count = end_point;
-                        }
-                    }
-                    point_count += 1;
+        let gsub = match self.gsub_table {
+            Some(ref gsub_table) => self.table(gsub_table),
+            None => return Ok(substitutions),
+        };
 
-                    /* Alloc points */
-                    let mut points: Vec<Point> = Vec::new();
-                    for _ in 0..point_count {
-                        points.push(Point { x: 0.0, y: 0.0, flags: 0 });
-                    }
+        let mut limits = CoverageLimits {
+            num_glyphs: self.num_glyphs as usize,
+            budget: MAX_COVERAGE_GLYPHS,
+        };
 
-                    /* TODO: Skip instructions for now. Simple interpreter would be nice. */
-                    let instructions = get_u16(glyph_data.offset(10 + 2 * glyph.num_contours as isize)) as isize;
-
-                    /* Read flags for each point */
-                    let mut flags = glyph_data.offset(10 + 2 * glyph.num_contours as isize + 2 + instructions);
-
-                    let mut j = 0;
-                    while j < points.len() {
-                        let pt_flag = flags.read();
-                        points.get_mut(j).unwrap().flags = pt_flag;
-                        flags = flags.offset(1);
-                        if (pt_flag & REPEAT_FLAG) != 0 {
-                            let repeat_count = *flags as usize;
-                            flags = flags.offset(1);
-                            let mut k = 1;
-                            while k <= repeat_count {
-                                points.get_mut(j + k).unwrap().flags = pt_flag;
-                                k += 1;
-                            }
-                            j += repeat_count;
-                        }
-                        j += 1;
-                    }
+        let feature_list = gsub.sub_table(gsub.get_u16(6)? as usize)?;
+        let lookup_list = gsub.sub_table(gsub.get_u16(8)? as usize)?;
+        let lookup_count = lookup_list.get_u16(0)? as usize;
+
+        let feature_count = feature_list.get_u16(0)? as usize;
+        for i in 0..feature_count {
+            let record = 2 + i * 6;
+            let tag = feature_list.get_u32(record)?;
+            let is_single = SINGLE_SUBSTITUTION_FEATURES.iter().any(|feature| feature_tag(feature) == tag);
+            let is_ligature = LIGATURE_FEATURES.iter().any(|feature| feature_tag(feature) == tag);
+            if !is_single && !is_ligature {
+                continue;
+            }
 
-                    /* Read x-coordinates for each point */
-                    let mut coords = flags;
-                    let mut x = 0;
-                    for j in 0..(point_count as usize) {
-                        let pt = points.get_mut(j).unwrap();
-                        if (pt.flags & 2) != 0 {
-                            let dx = i32::from(*coords);
-                            coords = coords.offset(1);
-                            x += if (pt.flags & 16) != 0 { dx } else { -dx };
-                        } else if (pt.flags & 16) == 0 {
-                            x += i32::from(get_i16(coords));
-                            coords = coords.offset(2);
-                        }
-                        pt.x = x as f32;
+            let feature = feature_list.sub_table(feature_list.get_u16(record + 4)? as usize)?;
+            let lookup_index_count = feature.get_u16(2)? as usize;
+            for k in 0..lookup_index_count {
+                let lookup_index = feature.get_u16(4 + k * 2)? as usize;
+                if lookup_index >= lookup_count {
+                    return Err(FontError::InvalidData("GSUB"));
+                }
+                let lookup = lookup_list.sub_table(lookup_list.get_u16(2 + lookup_index * 2)? as usize)?;
+                let lookup_type = lookup.get_u16(0)?;
+                let subtable_count = lookup.get_u16(4)? as usize;
+                for n in 0..subtable_count {
+                    let mut subtable = lookup.sub_table(lookup.get_u16(6 + n * 2)? as usize)?;
+                    let mut subtable_type = lookup_type;
+                    if subtable_type == GSUB_EXTENSION {
+                        subtable_type = subtable.get_u16(2)?;
+                        subtable = subtable.sub_table(subtable.get_u32(4)? as usize)?;
+                    }
+                    if subtable_type == GSUB_SINGLE && is_single {
+                        read_single_substitution(subtable, &mut limits, substitutions.single.entry(tag).or_default())?;
+                    } else if subtable_type == GSUB_LIGATURE && is_ligature {
+                        read_ligature_substitution(subtable, &mut limits, &mut substitutions.ligatures)?;
                     }
+                }
+            }
+        }
 
-                    /* Read y-coordinates for each point */
-                    let mut y = 0;
-                    for j in 0..(point_count as usize) {
-                        let pt = points.get_mut(j).unwrap();
-                        if (pt.flags & 4) != 0 {
-                            let dy = i32::from(*coords);
-                            coords = coords.offset(1);
-                            y += if (pt.flags & 32) != 0 { dy } else { -dy };
-                        } else if (pt.flags & 32) == 0 {
-                            y += i32::from(get_i16(coords));
-                            coords = coords.offset(2);
-                        }
+        // Longest ligatures must be checked first.
+        for ligatures in substitutions.ligatures.values_mut() {
+            ligatures.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+        }
 
-                        pt.y = y as f32;
-                    }
+        Ok(substitutions)
+    }
+
+    fn read_glyphs(&self) -> Result<Vec<TtfGlyph>, FontError> {
+        if self.glyf_table.is_none() {
+            return match self.cff_table {
+                Some(ref cff_table) => self.read_cff_glyphs(cff_table),
+                None => Err(FontError::MissingTable("glyf")),
+            };
+        }
+
+        let mut glyphs = self.read_simple_glyphs()?;
+
+        // Composite glyphs can reference glyphs with greater indices, so they are assembled
+        // when every simple glyph is loaded.
+        let mut composite_contours = Vec::new();
+        for (i, glyph) in glyphs.iter().enumerate() {
+            if glyph.num_contours < 0 {
+                composite_contours.push((i, self.read_composite_contours(&glyphs, i, 0)?));
+            }
+        }
+        for (i, contours) in composite_contours {
+            let glyph = &mut glyphs[i];
+            let contours: Vec<Polygon> = contours
+                .into_iter()
+                .map(|contour| glyph.font_units_to_local(contour))
+                .collect();
+            glyph.contours = contours;
+        }
+
+        Ok(glyphs)
+    }
+
+    /// Returns contours of glyph as sets of line segments in font units. Composite glyphs
+    /// are assembled from their components.
+    fn read_composite_contours(&self, glyphs: &[TtfGlyph], index: usize, depth: usize) -> Result<Vec<Polygon>, FontError> {
+        let glyph = match glyphs.get(index) {
+            Some(glyph) => glyph,
+            None => return Err(FontError::InvalidData("glyf")),
+        };
+
+        if glyph.num_contours >= 0 {
+            return Ok(glyph.contours
+                .iter()
+                .map(|contour| glyph.local_to_font_units(contour))
+                .collect());
+        }
+
+        // Too deep nesting is most likely cyclic reference.
+        if depth > MAX_COMPOSITE_DEPTH {
+            return Err(FontError::InvalidData("glyf"));
+        }
+
+        let data = match self.get_glyph_data(index)? {
+            Some(data) => data,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut contours = Vec::new();
+        let mut pos = 10;
+        loop {
+            let flags = data.get_u16(pos)?;
+            let component_index = data.get_u16(pos + 2)? as usize;
+            pos += 4;
+
+            let (arg1, arg2) = if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+                let args = (f32::from(data.get_i16(pos)?), f32::from(data.get_i16(pos + 2)?));
+                pos += 4;
+                args
+            } else {
+                let args = (f32::from(data.get_u8(pos)? as i8), f32::from(data.get_u8(pos + 1)? as i8));
+                pos += 2;
+                args
+            };
+
+            // Matching of points is not supported, such components are placed without offset.
+            let (dx, dy) = if flags & ARGS_ARE_XY_VALUES != 0 { (arg1, arg2) } else { (0.0, 0.0) };
+
+            let (a, b, c, d) = if flags & WE_HAVE_A_SCALE != 0 {
+                let scale = data.get_f2dot14(pos)?;
+                pos += 2;
+                (scale, 0.0, 0.0, scale)
+            } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+                let transform = (data.get_f2dot14(pos)?, 0.0, 0.0, data.get_f2dot14(pos + 2)?);
+                pos += 4;
+                transform
+            } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+                let transform = (
+                    data.get_f2dot14(pos)?,
+                    data.get_f2dot14(pos + 2)?,
+                    data.get_f2dot14(pos + 4)?,
+                    data.get_f2dot14(pos + 6)?
+                );
+                pos += 8;
+                transform
+            } else {
+                (1.0, 0.0, 0.0, 1.0)
+            };
+
+            for mut contour in self.read_composite_contours(glyphs, component_index, depth + 1)? {
+                for pt in contour.points.iter_mut() {
+                    let (x, y) = (pt.x, pt.y);
+                    pt.x = a * x + c * y + dx;
+                    pt.y = b * x + d * y + dy;
+                }
+                contours.push(contour);
+            }
+
+            if flags & MORE_COMPONENTS == 0 {
+                break;
+            }
+        }
+
+        Ok(contours)
+    }
+
+    fn read_cff_glyphs(&self, cff_table: &TableLocation) -> Result<Vec<TtfGlyph>, FontError> {
+        let outlines = cff::read_outlines(self.table(cff_table).data)
+            .ok_or(FontError::InvalidData("CFF "))?;
+
+        let hmtx = self.table(&self.hmtx_table);
+        let mut glyphs = Vec::with_capacity(self.num_glyphs as usize);
+        for i in 0..(self.num_glyphs as usize) {
+            let outline = outlines.get(i).map_or(&[][..], |outline| outline.as_slice());
+
+            let mut glyph = TtfGlyph::new();
+            glyph.num_contours = outline.len().min(std::i16::MAX as usize) as i16;
+            glyph.has_outline = !outline.is_empty();
+
+            // CFF does not store bounds of glyphs, so they are calculated from outline.
+            if glyph.has_outline {
+                let (mut x_min, mut y_min) = (std::f32::MAX, std::f32::MAX);
+                let (mut x_max, mut y_max) = (std::f32::MIN, std::f32::MIN);
+                for pt in outline.iter().flatten() {
+                    x_min = x_min.min(pt.x);
+                    y_min = y_min.min(pt.y);
+                    x_max = x_max.max(pt.x);
+                    y_max = y_max.max(pt.y);
+                }
+                glyph.x_min = x_min.floor() as i16;
+                glyph.y_min = y_min.floor() as i16;
+                glyph.x_max = x_max.ceil() as i16;
+                glyph.y_max = y_max.ceil() as i16;
+            }
 
-                    glyph.prepare_contours(points);
+            // Outlines are already flattened, so they are converted right into line sets.
+            for contour in outline {
+                let mut polygon = Polygon { points: Vec::new() };
+                for (k, begin) in contour.iter().enumerate() {
+                    let end = &contour[(k + 1) % contour.len()];
+                    polygon.points.push(Point { x: begin.x, y: begin.y, flags: ON_CURVE_POINT });
+                    polygon.points.push(Point { x: end.x, y: end.y, flags: ON_CURVE_POINT });
                 }
+                let polygon = glyph.font_units_to_local(polygon);
+                glyph.contours.push(polygon);
+            }
+
+            glyph.fill_horizontal_metrics(hmtx, self.num_of_long_hor_metrics as usize, i)?;
 
-                glyph.fill_horizontal_metrics(self.hhea_table, self.hmtx_table, i);
-                glyph.convert_curves_to_line_set();
+            glyphs.push(glyph);
+        }
+
+        Ok(glyphs)
+    }
 
-                self.glyphs.push(glyph);
+    fn read_simple_glyphs(&self) -> Result<Vec<TtfGlyph>, FontError> {
+        let hmtx = self.table(&self.hmtx_table);
+        let mut glyphs = Vec::with_capacity(self.num_glyphs as usize);
+        for i in 0..(self.num_glyphs as usize) {
+            let mut glyph = TtfGlyph::new();
+
+            if let Some(data) = self.get_glyph_data(i)? {
+                glyph.num_contours = data.get_i16(0)?;
+                glyph.x_min = data.get_i16(2)?;
+                glyph.y_min = data.get_i16(4)?;
+                glyph.x_max = data.get_i16(6)?;
+                glyph.y_max = data.get_i16(8)?;
+                glyph.has_outline = true;
+
+                /* Composite glyphs are assembled when all simple glyphs are read. */
+                if glyph.num_contours > 0 {
+                    glyph.read_simple_outline(data)?;
+                }
             }
+
+            glyph.fill_horizontal_metrics(hmtx, self.num_of_long_hor_metrics as usize, i)?;
+            glyph.convert_curves_to_line_set();
+
+            glyphs.push(glyph);
         }
+
+        Ok(glyphs)
+    }
+
+    fn get_ascender(&self) -> i16 {
+        self.ascender
+    }
+
+    fn get_descender(&self) -> i16 {
+        self.descender
     }
 }
 
@@ -722,147 +1291,245 @@ fn eval_quad_bezier(p0: &Point, p1: &Point, p2: &Point, steps: usize) -> Vec<Poi
 }
 
 impl TtfGlyph {
-    fn fill_horizontal_metrics(&mut self, hhea_table: *const u8, hmtx_table: *const u8, glyph_index: usize) {
-        unsafe {
-            let num_of_long_hor_metrics = get_u16(hhea_table.add(34)) as usize;
-            if glyph_index < num_of_long_hor_metrics {
-                self.advance = get_u16(hmtx_table.add(4 * glyph_index));
-                self.left_side_bearing = get_i16(hmtx_table.add(4 * glyph_index + 2));
-            } else {
-                self.advance = get_u16(hmtx_table.add(4 * (num_of_long_hor_metrics - 1)));
-                self.left_side_bearing = get_i16(hmtx_table.add(4 * num_of_long_hor_metrics + 2 * (glyph_index - num_of_long_hor_metrics)));
-            }
+    /// Converts contour from font units to coordinates of glyph - relative to its bounds
+    /// with Y axis pointing down.
+    fn font_units_to_local(&self, mut contour: Polygon) -> Polygon {
+        for pt in contour.points.iter_mut() {
+            pt.x -= f32::from(self.x_min);
+            pt.y = f32::from(self.y_max) - pt.y;
         }
+        contour
     }
 
-    fn convert_curves_to_line_set(&mut self) {
-        unsafe {
-            for _ in 0..self.num_contours {
-                self.contours.push(Polygon { points: Vec::new() });
-            }
+    fn local_to_font_units(&self, contour: &Polygon) -> Polygon {
+        Polygon {
+            points: contour.points
+                .iter()
+                .map(|pt| Point {
+                    x: pt.x + f32::from(self.x_min),
+                    y: f32::from(self.y_max) - pt.y,
+                    flags: pt.flags,
+                })
+                .collect()
+        }
+    }
 
-            for i in 0..(self.num_contours as usize) {
-                let contour = self.contours.get_unchecked_mut(i);
-                let raw_contour = self.raw_contours.get_unchecked(i);
-
-                /* Extract vertices */
-                let mut j = 0;
-                while j < raw_contour.points.len() {
-                    let p0 = raw_contour.points.get_unchecked(j);
-                    let p1 = raw_contour.points.get_unchecked((j + 1) % raw_contour.points.len());
-                    let p2 = raw_contour.points.get_unchecked((j + 2) % raw_contour.points.len());
-
-                    let p0_on = (p0.flags & ON_CURVE_POINT) != 0;
-                    let p1_on = (p1.flags & ON_CURVE_POINT) != 0;
-                    let p2_on = (p2.flags & ON_CURVE_POINT) != 0;
-
-                    if p0_on && !p1_on && p2_on {
-                        let points = eval_quad_bezier(p0, p1, p2, 6);
-                        for k in 0..(points.len() - 1) {
-                            contour.points.push(*points.get_unchecked(k));
-                            contour.points.push(*points.get_unchecked(k + 1));
-                        }
-                        j += 2;
-                    } else if p0_on && p1_on {
-                        contour.points.push(*p0);
-                        contour.points.push(*p1);
-                        j += 1
-                    } else {
-                        j += 2;
-                        println!("Invalid point sequence! Probably a bug in de_ttf_prepare_contours");
-                    }
-                }
-            }
+    fn new() -> TtfGlyph {
+        TtfGlyph {
+            num_contours: 0,
+            x_min: 0,
+            y_min: 0,
+            x_max: 0,
+            y_max: 0,
+            has_outline: false,
+            advance: 0,
+            left_side_bearing: 0,
+            end_points: Vec::new(),
+            raw_contours: Vec::new(),
+            contours: Vec::new(),
         }
     }
 
-    fn prepare_contours(&mut self, points: Vec<Point>) {
-        unsafe {
-            let glyph_height = self.y_max - self.y_min;
+    fn fill_horizontal_metrics(&mut self, hmtx: Table, num_of_long_hor_metrics: usize, glyph_index: usize) -> Result<(), FontError> {
+        if glyph_index < num_of_long_hor_metrics {
+            self.advance = hmtx.get_u16(4 * glyph_index)?;
+            self.left_side_bearing = hmtx.get_i16(4 * glyph_index + 2)?;
+        } else {
+            self.advance = hmtx.get_u16(4 * (num_of_long_hor_metrics - 1))?;
+            self.left_side_bearing = hmtx.get_i16(4 * num_of_long_hor_metrics + 2 * (glyph_index - num_of_long_hor_metrics))?;
+        }
+        Ok(())
+    }
 
-            /* Extract contours */
-            for _ in 0..self.num_contours {
-                self.raw_contours.push(Polygon { points: Vec::new() });
-            }
+    fn read_simple_outline(&mut self, data: Table) -> Result<(), FontError> {
+        let num_contours = self.num_contours as usize;
 
-            let mut prev_end_pt = 0;
-            for j in 0..self.num_contours {
-                let end_pt = self.end_points[j as usize];
-                let contour = self.raw_contours.get_unchecked_mut(j as usize);
+        /* Read end contour points */
+        let mut point_count = 0;
+        for j in 0..num_contours {
+            let end_point = data.get_u16(10 + j * 2)?;
+            self.end_points.push(end_point);
+            point_count = point_count.max(end_point as usize + 1);
+        }
 
-                /* Extract vertices */
-                let mut k = prev_end_pt;
-                while k <= end_pt {
-                    let pt = points.get_unchecked(k as usize);
+        let mut points = vec![Point { x: 0.0, y: 0.0, flags: 0 }; point_count];
 
-                    let off_pt = Point {
-                        x: pt.x - f32::from(self.x_min),
-                        y: pt.y - f32::from(self.y_min),
-                        flags: pt.flags,
-                    };
+        /* TODO: Skip instructions for now. Simple interpreter would be nice. */
+        let instructions = data.get_u16(10 + 2 * num_contours)? as usize;
 
-                    contour.points.push(off_pt);
+        /* Read flags for each point */
+        let mut pos = 10 + 2 * num_contours + 2 + instructions;
 
-                    k += 1;
+        let mut j = 0;
+        while j < points.len() {
+            let pt_flag = data.get_u8(pos)?;
+            pos += 1;
+            points[j].flags = pt_flag;
+            if (pt_flag & REPEAT_FLAG) != 0 {
+                let repeat_count = data.get_u8(pos)? as usize;
+                pos += 1;
+                for pt in points.iter_mut().skip(j + 1).take(repeat_count) {
+                    pt.flags = pt_flag;
                 }
+                j += repeat_count;
+            }
+            j += 1;
+        }
+
+        /* Read x-coordinates for each point */
+        let mut x = 0;
+        for pt in points.iter_mut() {
+            if (pt.flags & 2) != 0 {
+                let dx = i32::from(data.get_u8(pos)?);
+                pos += 1;
+                x += if (pt.flags & 16) != 0 { dx } else { -dx };
+            } else if (pt.flags & 16) == 0 {
+                x += i32::from(data.get_i16(pos)?);
+                pos += 2;
+            }
+            pt.x = x as f32;
+        }
+
+        /* Read y-coordinates for each point */
+        let mut y = 0;
+        for pt in points.iter_mut() {
+            if (pt.flags & 4) != 0 {
+                let dy = i32::from(data.get_u8(pos)?);
+                pos += 1;
+                y += if (pt.flags & 32) != 0 { dy } else { -dy };
+            } else if (pt.flags & 32) == 0 {
+                y += i32::from(data.get_i16(pos)?);
+                pos += 2;
+            }
+            pt.y = y as f32;
+        }
+
+        self.prepare_contours(points)
+    }
 
-                prev_end_pt = end_pt + 1;
+    fn convert_curves_to_line_set(&mut self) {
+        for raw_contour in self.raw_contours.iter() {
+            let mut contour = Polygon { points: Vec::new() };
+            let len = raw_contour.points.len();
+
+            /* Extract vertices */
+            let mut j = 0;
+            while j < len {
+                let p0 = &raw_contour.points[j];
+                let p1 = &raw_contour.points[(j + 1) % len];
+                let p2 = &raw_contour.points[(j + 2) % len];
+
+                let p0_on = (p0.flags & ON_CURVE_POINT) != 0;
+                let p1_on = (p1.flags & ON_CURVE_POINT) != 0;
+                let p2_on = (p2.flags & ON_CURVE_POINT) != 0;
+
+                if p0_on && !p1_on && p2_on {
+                    let points = eval_quad_bezier(p0, p1, p2, 6);
+                    for segment in points.windows(2) {
+                        contour.points.push(segment[0]);
+                        contour.points.push(segment[1]);
+                    }
+                    j += 2;
+                } else if p0_on && p1_on {
+                    contour.points.push(*p0);
+                    contour.points.push(*p1);
+                    j += 1
+                } else {
+                    j += 2;
+                    println!("Invalid point sequence! Probably a bug in de_ttf_prepare_contours");
+                }
             }
 
-            /* Unpack contours */
-            for j in 0..self.num_contours {
-                let raw_contour = self.raw_contours.get_unchecked_mut(j as usize);
-                let mut unpacked_contour = Polygon { points: Vec::new() };
+            self.contours.push(contour);
+        }
+    }
 
-                let start_off = (raw_contour.points[0].flags & ON_CURVE_POINT) == 0;
+    fn prepare_contours(&mut self, points: Vec<Point>) -> Result<(), FontError> {
+        let glyph_height = f32::from(self.y_max) - f32::from(self.y_min);
+        let x_min = f32::from(self.x_min);
+        let y_min = f32::from(self.y_min);
+
+        /* Extract contours */
+        let mut prev_end_pt = 0;
+        for end_pt in self.end_points.iter() {
+            let end_pt = *end_pt as usize;
+            let contour_points = points
+                .get(prev_end_pt..(end_pt + 1))
+                .ok_or(FontError::InvalidData("glyf"))?;
+
+            let contour = Polygon {
+                points: contour_points
+                    .iter()
+                    .map(|pt| Point {
+                        x: pt.x - x_min,
+                        y: pt.y - y_min,
+                        flags: pt.flags,
+                    })
+                    .collect()
+            };
+            self.raw_contours.push(contour);
 
-                let to =
-                    if start_off {
-                        /* when first point is off-curve we should add middle point between first and last points */
-                        let first = raw_contour.points.first().unwrap();
-                        let last = raw_contour.points.last().unwrap();
+            prev_end_pt = end_pt + 1;
+        }
 
-                        let middle = Point {
-                            flags: ON_CURVE_POINT,
-                            x: (first.x + last.x) / 2.0,
-                            y: f32::from(glyph_height) - (first.y + last.y) / 2.0,
-                        };
+        /* Unpack contours */
+        for raw_contour in self.raw_contours.iter_mut() {
+            if raw_contour.points.is_empty() {
+                continue;
+            }
 
-                        unpacked_contour.points.push(middle);
+            let mut unpacked_contour = Polygon { points: Vec::new() };
 
-                        /* also make sure to iterate not to the end - we already added point */
-                        raw_contour.points.len() - 1
-                    } else {
-                        raw_contour.points.len()
+            let start_off = (raw_contour.points[0].flags & ON_CURVE_POINT) == 0;
+
+            let to =
+                if start_off {
+                    /* when first point is off-curve we should add middle point between first and last points */
+                    let first = raw_contour.points.first().unwrap();
+                    let last = raw_contour.points.last().unwrap();
+
+                    let middle = Point {
+                        flags: ON_CURVE_POINT,
+                        x: (first.x + last.x) / 2.0,
+                        y: glyph_height - (first.y + last.y) / 2.0,
                     };
 
-                for k in 0..to {
-                    let p0 = raw_contour.points.get_unchecked(k as usize);
-                    let p1 = raw_contour.points.get_unchecked((k + 1) % raw_contour.points.len());
+                    unpacked_contour.points.push(middle);
+
+                    /* also make sure to iterate not to the end - we already added point */
+                    raw_contour.points.len() - 1
+                } else {
+                    raw_contour.points.len()
+                };
+
+            for k in 0..to {
+                let p0 = &raw_contour.points[k];
+                let p1 = &raw_contour.points[(k + 1) % raw_contour.points.len()];
 
-                    let p0_off_curve = (p0.flags & ON_CURVE_POINT) == 0;
-                    let p1_off_curve = (p1.flags & ON_CURVE_POINT) == 0;
+                let p0_off_curve = (p0.flags & ON_CURVE_POINT) == 0;
+                let p1_off_curve = (p1.flags & ON_CURVE_POINT) == 0;
 
-                    let flipped = Point {
-                        flags: p0.flags,
-                        x: p0.x,
-                        y: f32::from(glyph_height) - p0.y,
+                let flipped = Point {
+                    flags: p0.flags,
+                    x: p0.x,
+                    y: glyph_height - p0.y,
+                };
+                unpacked_contour.points.push(flipped);
+
+                if p0_off_curve && p1_off_curve {
+                    let middle = Point {
+                        flags: ON_CURVE_POINT,
+                        x: (p0.x + p1.x) / 2.0,
+                        y: glyph_height - (p0.y + p1.y) / 2.0,
                     };
-                    unpacked_contour.points.push(flipped);
-
-                    if p0_off_curve && p1_off_curve {
-                        let middle = Point {
-                            flags: ON_CURVE_POINT,
-                            x: (p0.x + p1.x) / 2.0,
-                            y: f32::from(glyph_height) - (p0.y + p1.y) / 2.0,
-                        };
-                        unpacked_contour.points.push(middle);
-                    }
+                    unpacked_contour.points.push(middle);
                 }
-
-                *raw_contour = unpacked_contour;
             }
+
+            *raw_contour = unpacked_contour;
         }
+
+        Ok(())
     }
 }
 
@@ -871,57 +1538,178 @@ impl Font {
         &[0x0020..0x00FF] // Basic Latin + Latin Supplement
     }
 
-    pub fn from_memory(data: Vec<u8>, height: f32, char_set: &[Range<u32>]) -> Result<Self, ()> {
-        let ttf = TrueType::new(data);
+    /// Loads font from memory. Glyphs of characters from `char_set` are rasterized immediately,
+    /// glyphs of any other characters are rasterized on demand.
+    pub fn from_memory(data: Vec<u8>, height: f32, char_set: &[Range<u32>]) -> Result<Self, FontError> {
+        Self::from_memory_with_mode(data, height, char_set, GlyphRasterMode::Bitmap)
+    }
+
+    /// Same as `from_memory` but allows to choose what will be stored in atlas. For distance
+    /// field fonts `height` defines resolution of glyphs in atlas, 32-48 pixels is usually
+    /// enough to draw text of any size.
+    pub fn from_memory_with_mode(data: Vec<u8>, height: f32, char_set: &[Range<u32>], raster_mode: GlyphRasterMode) -> Result<Self, FontError> {
+        let ttf = TrueType::new(data)?;
 
         let scale = ttf.em_to_pixels(height);
 
+        // Kerning and substitutions are optional, font with malformed tables is still usable
+        // without them.
+        let mut kerning = HashMap::new();
+        for (pair, value) in ttf.read_kerning().unwrap_or_default() {
+            kerning.insert(pair, scale * f32::from(value));
+        }
+
+        let substitutions = ttf.read_substitutions().unwrap_or_default();
+
         let mut font = Font {
             height,
+            scale,
             glyphs: Vec::new(),
+            glyph_map: HashMap::new(),
+            failed_glyphs: HashSet::new(),
             ascender: scale * f32::from(ttf.get_ascender()),
             descender: scale * f32::from(ttf.get_descender()),
+            ttf,
             char_map: HashMap::new(),
+            kerning,
+            substitutions,
             atlas: Vec::new(),
             atlas_size: 0,
+            packer: RectPacker::new(0, 0),
+            raster_mode,
+            dirty_region: None,
             texture: None
         };
 
-        for ttf_glyph in ttf.glyphs.iter() {
-            font.glyphs.push(ttf.convert_glyph(ttf_glyph, scale));
-        }
-
-        font.pack();
-
+        // Rasterize preloaded glyphs first to find out initial size of atlas.
+        let mut preloaded = Vec::new();
         for range in char_set {
             for unicode in range.start..range.end {
-                let index = ttf.unicode_to_glyph_index(unicode);
+                let index = font.ttf.unicode_to_glyph_index(unicode);
                 font.char_map.insert(unicode, index);
+                if !font.glyph_map.contains_key(&index) && index < font.ttf.glyphs.len() {
+                    // Malformed glyph is skipped, so it does not prevent other glyphs from loading.
+                    match font.ttf.convert_glyph(&font.ttf.glyphs[index], scale, raster_mode) {
+                        Ok(glyph) => {
+                            font.glyph_map.insert(index, preloaded.len());
+                            preloaded.push(glyph);
+                        }
+                        Err(_) => {
+                            font.failed_glyphs.insert(index);
+                        }
+                    }
+                }
             }
         }
 
+        let mut area = 0.0;
+        for glyph in preloaded.iter() {
+            area += glyph.bitmap_height * glyph.bitmap_width;
+        }
+        font.atlas_size = ((1.15 * f32::sqrt(area)) as i32).max(MIN_ATLAS_SIZE).min(MAX_ATLAS_SIZE);
+        font.atlas = vec![0; (font.atlas_size * font.atlas_size) as usize];
+        font.packer = RectPacker::new(font.atlas_size, font.atlas_size);
+
+        for glyph in preloaded {
+            font.add_glyph(glyph)?;
+        }
+
+        font.dirty_region = Some(Rect::new(0, 0, font.atlas_size, font.atlas_size));
+
         Ok(font)
     }
 
-    pub fn from_file(path: &Path, height: f32, char_set: &[Range<u32>]) -> Result<Self, ()> {
-        if let Ok(ref mut file) = File::open(path) {
-            let mut file_content: Vec<u8> = Vec::with_capacity(file.metadata().unwrap().len() as usize);
-            file.read_to_end(&mut file_content).unwrap();
+    pub fn from_file(path: &Path, height: f32, char_set: &[Range<u32>]) -> Result<Self, FontError> {
+        Self::from_file_with_mode(path, height, char_set, GlyphRasterMode::Bitmap)
+    }
+
+    pub fn from_file_with_mode(path: &Path, height: f32, char_set: &[Range<u32>], raster_mode: GlyphRasterMode) -> Result<Self, FontError> {
+        let mut file = File::open(path)?;
+        let mut file_content: Vec<u8> = Vec::with_capacity(file.metadata()?.len() as usize);
+        file.read_to_end(&mut file_content)?;
 
-            Self::from_memory(file_content, height, char_set)
-        } else {
-            Err(())
+        Self::from_memory_with_mode(file_content, height, char_set, raster_mode)
+    }
+
+    #[inline]
+    pub fn raster_mode(&self) -> GlyphRasterMode {
+        self.raster_mode
+    }
+
+    /// Returns true if atlas of font contains signed distance fields of glyphs.
+    #[inline]
+    pub fn is_distance_field(&self) -> bool {
+        match self.raster_mode {
+            GlyphRasterMode::DistanceField { .. } => true,
+            GlyphRasterMode::Bitmap => false,
         }
     }
 
     #[inline]
     pub fn get_glyph(&self, unicode: u32) -> Option<&FontGlyph> {
+        self.char_map
+            .get(&unicode)
+            .and_then(|index| self.get_glyph_by_index(*index))
+    }
+
+    /// Returns rasterized glyph by its index in font file, see `glyph_index`.
+    #[inline]
+    pub fn get_glyph_by_index(&self, index: usize) -> Option<&FontGlyph> {
+        self.glyph_map
+            .get(&index)
+            .and_then(|index| self.glyphs.get(*index))
+    }
+
+    /// Returns index of glyph of given character in font file, zero means that font has
+    /// no glyph for the character.
+    pub fn glyph_index(&self, unicode: u32) -> usize {
         match self.char_map.get(&unicode) {
-            Some(glyph_index) => self.glyphs.get(*glyph_index),
-            None => None
+            Some(index) => *index,
+            None => self.ttf.unicode_to_glyph_index(unicode),
+        }
+    }
+
+    /// Returns glyph that replaces given glyph in OpenType feature, for example `b"init"`
+    /// gives initial form of Arabic letter. Glyph is returned unchanged if font has no
+    /// such substitution.
+    pub fn substitute_glyph(&self, feature: &[u8; 4], glyph: usize) -> usize {
+        self.substitutions.single
+            .get(&feature_tag(feature))
+            .and_then(|map| map.get(&glyph))
+            .cloned()
+            .unwrap_or(glyph)
+    }
+
+    /// Finds longest ligature that starts at first glyph of given sequence. Returns glyph
+    /// of ligature and amount of glyphs it replaces.
+    pub fn find_ligature(&self, glyphs: &[usize]) -> Option<(usize, usize)> {
+        let (first, rest) = glyphs.split_first()?;
+        self.substitutions.ligatures
+            .get(first)?
+            .iter()
+            .find(|(components, _)| rest.starts_with(components))
+            .map(|(components, ligature)| (*ligature, components.len() + 1))
+    }
+
+    /// Returns horizontal adjustment (in pixels) of `right` character when it is placed
+    /// after `left` character. Usually negative.
+    #[inline]
+    pub fn get_kerning(&self, left: u32, right: u32) -> f32 {
+        if self.kerning.is_empty() {
+            return 0.0;
+        }
+        match (self.char_map.get(&left), self.char_map.get(&right)) {
+            (Some(left), Some(right)) => self.get_kerning_by_index(*left, *right),
+            _ => 0.0
         }
     }
 
+    /// Same as `get_kerning` but for glyph indices.
+    #[inline]
+    pub fn get_kerning_by_index(&self, left: usize, right: usize) -> f32 {
+        *self.kerning.get(&(left, right)).unwrap_or(&0.0)
+    }
+
     #[inline]
     pub fn get_height(&self) -> f32 {
         self.height
@@ -948,64 +1736,161 @@ impl Font {
     }
 
     #[inline]
-    fn compute_atlas_size(&self) -> i32 {
-        let mut area = 0.0;
-        for glyph in self.glyphs.iter() {
-            area += glyph.bitmap_height * glyph.bitmap_width;
+    pub fn get_glyph_advance(&self, c: u32) -> f32 {
+        self.get_glyph(c)
+            .map_or(self.get_height(), |glyph| glyph.get_advance())
+    }
+
+    /// Returns true if font has a glyph for given character.
+    pub fn has_glyph(&self, unicode: u32) -> bool {
+        // Glyph with zero index is "missing glyph" symbol.
+        self.glyph_index(unicode) != 0
+    }
+
+    /// Makes sure that glyph for given character is rasterized and put into atlas.
+    pub fn cache_char(&mut self, unicode: u32) -> Result<(), FontError> {
+        let index = match self.char_map.get(&unicode) {
+            Some(index) => *index,
+            None => {
+                let index = self.ttf.unicode_to_glyph_index(unicode);
+                self.char_map.insert(unicode, index);
+                index
+            }
+        };
+
+        self.cache_glyph(index)
+    }
+
+    /// Makes sure that glyph with given index in font file is rasterized and put into
+    /// atlas. Used for glyphs that have no characters, such as ligatures or contextual
+    /// forms. Glyph that has failed once is remembered and next calls do nothing for it,
+    /// see `is_glyph_failed`.
+    pub fn cache_glyph(&mut self, index: usize) -> Result<(), FontError> {
+        if !self.glyph_map.contains_key(&index) && !self.failed_glyphs.contains(&index) && index < self.ttf.glyphs.len() {
+            let result = self.ttf
+                .convert_glyph(&self.ttf.glyphs[index], self.scale, self.raster_mode)
+                .and_then(|glyph| self.add_glyph(glyph));
+            if let Err(e) = result {
+                self.failed_glyphs.insert(index);
+                return Err(e);
+            }
+            self.glyph_map.insert(index, self.glyphs.len() - 1);
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if glyph with given index could not be rasterized or put into atlas.
+    pub fn is_glyph_failed(&self, index: usize) -> bool {
+        self.failed_glyphs.contains(&index)
+    }
+
+    /// Makes sure that glyphs for every character of given UTF32 text are rasterized.
+    pub fn cache_text(&mut self, text: &[u32]) -> Result<(), FontError> {
+        for code in text {
+            self.cache_char(*code)?;
         }
-        (1.15 * area.sqrt()) as i32
+        Ok(())
+    }
+
+    /// Returns region of atlas that was changed since last call and resets it. Renderer
+    /// should call this method each frame and re-upload returned region of atlas to
+    /// texture. If size of atlas has changed, whole atlas is returned.
+    pub fn take_dirty_region(&mut self) -> Option<Rect<i32>> {
+        self.dirty_region.take()
     }
 
     #[inline]
-    pub fn get_glyph_advance(&self, c: u32) -> f32 {
-        self.get_glyph(c)
-            .map_or(self.get_height(), |glyph| glyph.get_advance())
+    pub fn dirty_region(&self) -> Option<Rect<i32>> {
+        self.dirty_region
+    }
+
+    fn mark_dirty(&mut self, bounds: Rect<i32>) {
+        self.dirty_region = Some(match self.dirty_region {
+            Some(dirty) => {
+                let x = dirty.x.min(bounds.x);
+                let y = dirty.y.min(bounds.y);
+                let right = (dirty.x + dirty.w).max(bounds.x + bounds.w);
+                let bottom = (dirty.y + dirty.h).max(bounds.y + bounds.h);
+                Rect::new(x, y, right - x, bottom - y)
+            }
+            None => bounds
+        });
     }
 
-    fn pack(&mut self) {
-        self.atlas_size = self.compute_atlas_size();
+    /// Doubles size of atlas. Glyphs that are already in atlas keep their locations in pixels.
+    fn grow_atlas(&mut self) -> Result<(), FontError> {
+        let old_size = self.atlas_size;
+        let new_size = old_size * 2;
+        if new_size > MAX_ATLAS_SIZE {
+            return Err(FontError::AtlasOverflow);
+        }
+
+        let mut atlas = vec![0; (new_size * new_size) as usize];
+        for row in 0..old_size {
+            let src = (row * old_size) as usize;
+            let dest = (row * new_size) as usize;
+            atlas[dest..(dest + old_size as usize)].copy_from_slice(&self.atlas[src..(src + old_size as usize)]);
+        }
+        self.atlas = atlas;
+        self.atlas_size = new_size;
 
-        self.atlas = vec![0; (self.atlas_size * self.atlas_size) as usize];
+        self.packer.add_free_region(Rect::new(old_size, 0, new_size - old_size, old_size));
+        self.packer.add_free_region(Rect::new(0, old_size, new_size, new_size - old_size));
 
-        let mut rect_packer = RectPacker::new(self.atlas_size, self.atlas_size);
         for glyph in self.glyphs.iter_mut() {
-            if let Some(bounds) = rect_packer.find_free(glyph.bitmap_width as i32, glyph.bitmap_height as i32) {
-                let w = bounds.w as f32 / self.atlas_size as f32;
-                let h = bounds.h as f32 / self.atlas_size as f32;
-                let x = bounds.x as f32 / self.atlas_size as f32;
-                let y = bounds.y as f32 / self.atlas_size as f32;
-
-                glyph.tex_coords[0] = Vec2 { x, y };
-                glyph.tex_coords[1] = Vec2 { x: x + w, y };
-                glyph.tex_coords[2] = Vec2 { x: x + w, y: y + h };
-                glyph.tex_coords[3] = Vec2 { x, y: y + h };
-
-                let row_end = bounds.y + bounds.h;
-                let col_end = bounds.x + bounds.w;
-
-                // Copy glyph pixels to atlas pixels
-                let mut row = bounds.y;
-                let mut src_row = 0;
-                while row < row_end {
-                    let mut col = bounds.x;
-                    let mut src_col = 0;
-                    while col < col_end {
-                        self.atlas[(row * self.atlas_size + col) as usize] = glyph.pixels[(src_row * bounds.w + src_col) as usize];
-                        col += 1;
-                        src_col += 1;
-                    }
+            glyph.compute_tex_coords(new_size);
+        }
 
-                    row += 1;
-                    src_row += 1;
-                }
-            } else {
-                println!("Insufficient atlas size!");
+        self.dirty_region = Some(Rect::new(0, 0, new_size, new_size));
+
+        Ok(())
+    }
+
+    /// Puts glyph into atlas, atlas will grow if there is no space for glyph.
+    fn add_glyph(&mut self, mut glyph: FontGlyph) -> Result<(), FontError> {
+        let w = glyph.bitmap_width as i32;
+        let h = glyph.bitmap_height as i32;
+
+        let bounds = loop {
+            if let Some(bounds) = self.packer.find_free(w, h) {
+                break bounds;
+            }
+            self.grow_atlas()?;
+        };
+
+        glyph.atlas_bounds = bounds;
+        glyph.compute_tex_coords(self.atlas_size);
+
+        // Copy glyph pixels to atlas pixels
+        for src_row in 0..bounds.h {
+            let row = bounds.y + src_row;
+            for src_col in 0..bounds.w {
+                let col = bounds.x + src_col;
+                self.atlas[(row * self.atlas_size + col) as usize] = glyph.pixels[(src_row * bounds.w + src_col) as usize];
             }
         }
+
+        self.mark_dirty(bounds);
+        self.glyphs.push(glyph);
+
+        Ok(())
     }
 }
 
 impl FontGlyph {
+    fn compute_tex_coords(&mut self, atlas_size: i32) {
+        let w = self.atlas_bounds.w as f32 / atlas_size as f32;
+        let h = self.atlas_bounds.h as f32 / atlas_size as f32;
+        let x = self.atlas_bounds.x as f32 / atlas_size as f32;
+        let y = self.atlas_bounds.y as f32 / atlas_size as f32;
+
+        self.tex_coords[0] = Vec2 { x, y };
+        self.tex_coords[1] = Vec2 { x: x + w, y };
+        self.tex_coords[2] = Vec2 { x: x + w, y: y + h };
+        self.tex_coords[3] = Vec2 { x, y: y + h };
+    }
+
     #[inline]
     pub fn get_bitmap_top(&self) -> f32 {
         self.bitmap_top
@@ -1047,31 +1932,49 @@ impl FontGlyph {
     }
 }
 
-/*
 #[cfg(test)]
 mod test {
-    use image::ColorType;
-    use std::{
-        path::PathBuf,
-        rc::Rc,
-        cell::RefCell,
-        path::Path
-    };
-    use crate::ttf::Font;
+    use crate::ttf::{Font, FontError, Table};
+
+    #[test]
+    fn table_bounds() {
+        let table = Table { tag: "test", data: &[0x12, 0x34, 0x56] };
+        assert_eq!(table.get_u16(1).unwrap(), 0x3456);
+        match table.get_u16(2) {
+            Err(FontError::TruncatedData("test")) => (),
+            _ => panic!("expected truncated data"),
+        }
+        assert!(table.get_u32(0).is_err());
+        assert!(table.get_u16(std::usize::MAX).is_err());
+        assert_eq!(table.sub_table(3).unwrap().data.len(), 0);
+        assert!(table.sub_table(4).is_err());
+        assert!(table.sub_range(2..4).is_err());
+    }
 
     #[test]
-    fn font_test() {
-        let font_bytes = std::include_bytes!("built_in_font.ttf").to_vec();
-        let font = Font::from_memory(font_bytes, 20.0, Font::default_char_set()).unwrap();
-        let font = Rc::new(RefCell::new(font));
-        let raster_path = Path::new("./raster");
-        if !raster_path.exists() {
-            std::fs::create_dir(raster_path).unwrap();
-        }
-        let path = PathBuf::from("./raster/_atlas.png");
-        image::save_buffer(path, font.borrow().atlas.as_slice(),
-                           font.borrow().atlas_size as u32,
-                           font.borrow().atlas_size as u32,
-                           ColorType::Gray(8)).unwrap();
-    }
-}*/
\ No newline at end of file
+    fn malformed_font() {
+        let font_bytes = std::include_bytes!("built_in_font.ttf");
+        // Truncated inside of offset table, table directory and tables.
+        for len in [0, 4, 12, 30, font_bytes.len() / 2].iter() {
+            assert!(Font::from_memory(font_bytes[..*len].to_vec(), 20.0, Font::default_char_set()).is_err());
+        }
+
+        // Pseudo-random garbage, linear congruential generator makes it reproducible.
+        let mut state = 12345u32;
+        for len in [16, 256, 4096].iter() {
+            let garbage = (0..*len)
+                .map(|_| {
+                    state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    (state >> 16) as u8
+                })
+                .collect::<Vec<u8>>();
+            assert!(Font::from_memory(garbage, 20.0, Font::default_char_set()).is_err());
+        }
+
+        // Directory entry that points outside of data.
+        let mut header = vec![0, 1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0];
+        header.extend_from_slice(b"head");
+        header.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 28, 0xFF, 0xFF, 0xFF, 0xFF]);
+        assert!(Font::from_memory(header, 20.0, Font::default_char_set()).is_err());
+    }
+}
@@ -9,6 +9,7 @@ use crate::{
     },
     VerticalAlignment,
     HorizontalAlignment,
+    NavigationDirection,
     Thickness,
     UserInterface,
     UINode,
@@ -19,7 +20,12 @@ use crate::{
         WidgetMessage,
         UiMessage,
         WidgetProperty
-    }
+    },
+    serialization::{
+        Visit,
+        Visitor,
+        VisitResult,
+    },
 };
 use std::{
     cell::{
@@ -77,6 +83,19 @@ pub struct Widget<M: 'static, C: 'static + Control<M, C>> {
     pub(in crate) prev_measure: Cell<Vec2>,
     pub(in crate) prev_arrange: Cell<Rect<f32>>,
     z_index: usize,
+    /// Can node receive keyboard focus by Tab navigation or not.
+    focusable: bool,
+    /// Explicit position of node in Tab navigation order. Nodes without tab index will be
+    /// visited after nodes with tab index in order of appearance in the tree.
+    tab_index: Option<usize>,
+    /// Keeps Tab navigation inside node's subtree when focus is already inside it.
+    focus_scope: bool,
+    /// Explicit targets for directional navigation, NONE means that target will be
+    /// found by geometry of nodes.
+    navigation_up: Handle<UINode<M, C>>,
+    navigation_down: Handle<UINode<M, C>>,
+    navigation_left: Handle<UINode<M, C>>,
+    navigation_right: Handle<UINode<M, C>>,
 }
 
 impl<M, C: 'static + Control<M, C>> Default for Widget<M, C> {
@@ -85,6 +104,39 @@ impl<M, C: 'static + Control<M, C>> Default for Widget<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for Widget<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        visitor.enter_region("widget");
+        visitor.visit("name", &mut self.name)?;
+        visitor.visit_property("desired_local_position", self.desired_local_position.get(), |v| self.desired_local_position.set(v))?;
+        visitor.visit_property("width", self.width.get(), |v| self.width.set(v))?;
+        visitor.visit_property("height", self.height.get(), |v| self.height.set(v))?;
+        visitor.visit("min_size", &mut self.min_size)?;
+        visitor.visit("max_size", &mut self.max_size)?;
+        visitor.visit("background", &mut self.background)?;
+        visitor.visit("foreground", &mut self.foreground)?;
+        visitor.visit("row", &mut self.row)?;
+        visitor.visit("column", &mut self.column)?;
+        visitor.visit("vertical_alignment", &mut self.vertical_alignment)?;
+        visitor.visit("horizontal_alignment", &mut self.horizontal_alignment)?;
+        visitor.visit("margin", &mut self.margin)?;
+        visitor.visit("visibility", &mut self.visibility)?;
+        visitor.visit_handles("children", &mut self.children)?;
+        visitor.visit_weak_handle("parent", &mut self.parent)?;
+        visitor.visit("hit_test_visibility", &mut self.hit_test_visibility)?;
+        visitor.visit("z_index", &mut self.z_index)?;
+        visitor.visit("focusable", &mut self.focusable)?;
+        visitor.visit("tab_index", &mut self.tab_index)?;
+        visitor.visit("focus_scope", &mut self.focus_scope)?;
+        visitor.visit_weak_handle("navigation_up", &mut self.navigation_up)?;
+        visitor.visit_weak_handle("navigation_down", &mut self.navigation_down)?;
+        visitor.visit_weak_handle("navigation_left", &mut self.navigation_left)?;
+        visitor.visit_weak_handle("navigation_right", &mut self.navigation_right)?;
+        visitor.leave_region();
+        Ok(())
+    }
+}
+
 impl<M, C: 'static + Control<M, C>> Widget<M, C> {
     #[inline]
     pub fn post_message(&self, message: UiMessage<M, C>) {
@@ -98,7 +150,7 @@ impl<M, C: 'static + Control<M, C>> Widget<M, C> {
     }
 
     #[inline]
-    fn post_property_changed_message(&self, property: WidgetProperty) {
+    fn post_property_changed_message(&self, property: WidgetProperty<M, C>) {
         self.post_message(UiMessage::new(UiMessageData::Widget(WidgetMessage::Property(property))));
     }
 
@@ -292,6 +344,13 @@ impl<M, C: 'static + Control<M, C>> Widget<M, C> {
             prev_measure: Default::default(),
             prev_arrange: Default::default(),
             z_index: self.z_index,
+            focusable: self.focusable,
+            tab_index: self.tab_index,
+            focus_scope: self.focus_scope,
+            navigation_up: self.navigation_up,
+            navigation_down: self.navigation_down,
+            navigation_left: self.navigation_left,
+            navigation_right: self.navigation_right,
         }
     }
 
@@ -438,6 +497,75 @@ impl<M, C: 'static + Control<M, C>> Widget<M, C> {
         self.visibility
     }
 
+    #[inline]
+    pub fn set_focusable(&mut self, focusable: bool) -> &mut Self {
+        if self.focusable != focusable {
+            self.focusable = focusable;
+            self.post_property_changed_message(WidgetProperty::Focusable(focusable));
+        }
+        self
+    }
+
+    #[inline]
+    pub fn is_focusable(&self) -> bool {
+        self.focusable
+    }
+
+    #[inline]
+    pub fn set_tab_index(&mut self, tab_index: Option<usize>) -> &mut Self {
+        if self.tab_index != tab_index {
+            self.tab_index = tab_index;
+            self.post_property_changed_message(WidgetProperty::TabIndex(tab_index));
+        }
+        self
+    }
+
+    #[inline]
+    pub fn tab_index(&self) -> Option<usize> {
+        self.tab_index
+    }
+
+    #[inline]
+    pub fn set_focus_scope(&mut self, focus_scope: bool) -> &mut Self {
+        if self.focus_scope != focus_scope {
+            self.focus_scope = focus_scope;
+            self.post_property_changed_message(WidgetProperty::FocusScope(focus_scope));
+        }
+        self
+    }
+
+    #[inline]
+    pub fn is_focus_scope(&self) -> bool {
+        self.focus_scope
+    }
+
+    /// Sets explicit node which will be focused when directional navigation goes from this
+    /// node in given direction. Pass `Handle::NONE` to use geometric search.
+    #[inline]
+    pub fn set_navigation_target(&mut self, direction: NavigationDirection, target: Handle<UINode<M, C>>) -> &mut Self {
+        let current = match direction {
+            NavigationDirection::Up => &mut self.navigation_up,
+            NavigationDirection::Down => &mut self.navigation_down,
+            NavigationDirection::Left => &mut self.navigation_left,
+            NavigationDirection::Right => &mut self.navigation_right,
+        };
+        if *current != target {
+            *current = target;
+            self.post_property_changed_message(WidgetProperty::NavigationTarget { direction, target });
+        }
+        self
+    }
+
+    #[inline]
+    pub fn navigation_target(&self, direction: NavigationDirection) -> Handle<UINode<M, C>> {
+        match direction {
+            NavigationDirection::Up => self.navigation_up,
+            NavigationDirection::Down => self.navigation_down,
+            NavigationDirection::Left => self.navigation_left,
+            NavigationDirection::Right => self.navigation_right,
+        }
+    }
+
     pub fn has_descendant(&self, node_handle: Handle<UINode<M, C>>, ui: &UserInterface<M, C>) -> bool {
         for child_handle in self.children.iter() {
             if *child_handle == node_handle {
@@ -549,6 +677,23 @@ impl<M, C: 'static + Control<M, C>> Widget<M, C> {
                                 self.invalidate_layout();
                             }
                         }
+                        WidgetProperty::Focusable(focusable) => {
+                            self.focusable = *focusable
+                        }
+                        WidgetProperty::TabIndex(tab_index) => {
+                            self.tab_index = *tab_index
+                        }
+                        WidgetProperty::FocusScope(focus_scope) => {
+                            self.focus_scope = *focus_scope
+                        }
+                        WidgetProperty::NavigationTarget { direction, target } => {
+                            match direction {
+                                NavigationDirection::Up => self.navigation_up = *target,
+                                NavigationDirection::Down => self.navigation_down = *target,
+                                NavigationDirection::Left => self.navigation_left = *target,
+                                NavigationDirection::Right => self.navigation_right = *target,
+                            }
+                        }
                         _ => ()
                     }
                 }
@@ -656,6 +801,13 @@ pub struct WidgetBuilder<M: 'static, C: 'static + Control<M, C>> {
     pub is_hit_test_visible: bool,
     pub visibility: bool,
     pub z_index: usize,
+    pub focusable: Option<bool>,
+    pub tab_index: Option<usize>,
+    pub focus_scope: Option<bool>,
+    pub navigation_up: Handle<UINode<M, C>>,
+    pub navigation_down: Handle<UINode<M, C>>,
+    pub navigation_left: Handle<UINode<M, C>>,
+    pub navigation_right: Handle<UINode<M, C>>,
 }
 
 impl<M, C: 'static + Control<M, C>> Default for WidgetBuilder<M, C> {
@@ -684,6 +836,13 @@ impl<M, C: 'static + Control<M, C>> WidgetBuilder<M, C> {
             is_hit_test_visible: true,
             visibility: true,
             z_index: 0,
+            focusable: None,
+            tab_index: None,
+            focus_scope: None,
+            navigation_up: Handle::NONE,
+            navigation_down: Handle::NONE,
+            navigation_left: Handle::NONE,
+            navigation_right: Handle::NONE,
         }
     }
 
@@ -781,6 +940,31 @@ impl<M, C: 'static + Control<M, C>> WidgetBuilder<M, C> {
         self
     }
 
+    pub fn with_focusable(mut self, focusable: bool) -> Self {
+        self.focusable = Some(focusable);
+        self
+    }
+
+    pub fn with_tab_index(mut self, tab_index: usize) -> Self {
+        self.tab_index = Some(tab_index);
+        self
+    }
+
+    pub fn with_focus_scope(mut self, focus_scope: bool) -> Self {
+        self.focus_scope = Some(focus_scope);
+        self
+    }
+
+    pub fn with_navigation_target(mut self, direction: NavigationDirection, target: Handle<UINode<M, C>>) -> Self {
+        match direction {
+            NavigationDirection::Up => self.navigation_up = target,
+            NavigationDirection::Down => self.navigation_down = target,
+            NavigationDirection::Left => self.navigation_left = target,
+            NavigationDirection::Right => self.navigation_right = target,
+        }
+        self
+    }
+
     pub fn build(self) -> Widget<M, C> {
         Widget {
             name: self.name,
@@ -814,6 +998,13 @@ impl<M, C: 'static + Control<M, C>> WidgetBuilder<M, C> {
             prev_measure: Default::default(),
             prev_arrange: Default::default(),
             z_index: self.z_index,
+            focusable: self.focusable.unwrap_or(false),
+            tab_index: self.tab_index,
+            focus_scope: self.focus_scope.unwrap_or(false),
+            navigation_up: self.navigation_up,
+            navigation_down: self.navigation_down,
+            navigation_left: self.navigation_left,
+            navigation_right: self.navigation_right,
         }
     }
 }
\ No newline at end of file
@@ -36,6 +36,7 @@ use crate::{
         GradientPoint,
     },
     NodeHandleMapping,
+    serialization::{Visit, Visitor, VisitResult},
 };
 
 /// Represents a widget looking as window in Windows - with title, minimize and close buttons.
@@ -243,6 +244,19 @@ impl<M, C: 'static + Control<M, C>> Window<M, C> {
     }
 }
 
+impl<M, C: 'static + Control<M, C>> Visit<M, C> for Window<M, C> {
+    fn visit(&mut self, visitor: &mut Visitor<M, C>) -> VisitResult {
+        self.widget.visit(visitor)?;
+        visitor.visit("minimized", &mut self.minimized)?;
+        visitor.visit("can_minimize", &mut self.can_minimize)?;
+        visitor.visit("can_close", &mut self.can_close)?;
+        visitor.visit_handle("header", &mut self.header)?;
+        visitor.visit_handle("minimize_button", &mut self.minimize_button)?;
+        visitor.visit_handle("close_button", &mut self.close_button)?;
+        visitor.visit_handle("scroll_viewer", &mut self.scroll_viewer)
+    }
+}
+
 pub struct WindowBuilder<'a, M: 'static, C: 'static + Control<M, C>> {
     widget_builder: WidgetBuilder<M, C>,
     content: Handle<UINode<M, C>>,
@@ -322,7 +336,11 @@ impl<'a, M, C: 'static + Control<M, C>> WindowBuilder<'a, M, C> {
         self
     }
 
-    pub fn build(self, ui: &mut UserInterface<M, C>) -> Handle<UINode<M, C>> {
+    pub fn build(mut self, ui: &mut UserInterface<M, C>) -> Handle<UINode<M, C>> {
+        if self.widget_builder.focus_scope.is_none() {
+            self.widget_builder.focus_scope = Some(true);
+        }
+
         let minimize_button;
         let close_button;
 